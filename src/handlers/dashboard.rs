@@ -0,0 +1,102 @@
+/// Self-contained live status page. It polls `/metrics` from the browser, so it
+/// needs no external assets and shows per-IP detail only when the metrics
+/// endpoint does.
+pub const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Rusty-SSL Dashboard</title>
+    <style>
+        body { font-family: Arial, sans-serif; max-width: 960px; margin: 0 auto; padding: 20px; background-color: #f5f5f5; }
+        .container { background: white; padding: 30px; border-radius: 8px; box-shadow: 0 2px 10px rgba(0,0,0,0.1); }
+        h1 { color: #333; }
+        .cards { display: flex; flex-wrap: wrap; gap: 15px; margin: 20px 0; }
+        .card { flex: 1 1 180px; background: #f8f9fa; padding: 15px; border-radius: 5px; border-left: 4px solid #007bff; }
+        .card .value { font-size: 28px; font-weight: bold; color: #333; }
+        .card .label { color: #666; font-size: 13px; }
+        .card.warn { border-left-color: #dc3545; }
+        table { width: 100%; border-collapse: collapse; font-size: 14px; }
+        th, td { text-align: left; padding: 6px 8px; border-bottom: 1px solid #eee; }
+        #error { color: #dc3545; }
+        footer { margin-top: 30px; padding-top: 20px; border-top: 1px solid #eee; color: #666; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h1>🦀 Rusty-SSL Dashboard</h1>
+        <p id="error"></p>
+
+        <div class="cards">
+            <div class="card"><div class="value" id="active">-</div><div class="label">Active connections</div></div>
+            <div class="card"><div class="value" id="total">-</div><div class="label">Total connections</div></div>
+            <div class="card"><div class="value" id="avg-ttl">-</div><div class="label">Average TTL (s)</div></div>
+            <div class="card" id="cert-card"><div class="value" id="cert-expiry">-</div><div class="label">Days until certificate expiry</div></div>
+        </div>
+
+        <h2>Active Connections</h2>
+        <table>
+            <thead><tr><th>IP</th><th>Requests</th><th>Protocol</th><th>Idle (s)</th><th>Expires in (s)</th></tr></thead>
+            <tbody id="connections"><tr><td colspan="5">Per-connection detail requires a metrics token</td></tr></tbody>
+        </table>
+
+        <footer>
+            <p>Powered by Rust 🦀 | Version: {version} | Updated: <span id="updated">never</span></p>
+        </footer>
+    </div>
+    <script>
+        function setText(id, value) {
+            document.getElementById(id).textContent = value;
+        }
+
+        function renderConnections(connections) {
+            var body = document.getElementById('connections');
+            if (!Array.isArray(connections)) {
+                return;
+            }
+            body.replaceChildren();
+            connections.forEach(function (conn) {
+                var row = document.createElement('tr');
+                [conn.ip, conn.request_count, conn.protocol, conn.last_activity, conn.time_until_expiry]
+                    .forEach(function (value) {
+                        var cell = document.createElement('td');
+                        cell.textContent = value === null || value === undefined ? '-' : value;
+                        row.appendChild(cell);
+                    });
+                body.appendChild(row);
+            });
+        }
+
+        function refresh() {
+            fetch('/metrics', { cache: 'no-store' })
+                .then(function (response) {
+                    if (!response.ok) {
+                        throw new Error('/metrics returned ' + response.status);
+                    }
+                    return response.json();
+                })
+                .then(function (metrics) {
+                    var stats = metrics.ttl_stats || {};
+                    setText('active', stats.active_connections);
+                    setText('total', stats.total_connections);
+                    setText('avg-ttl', stats.average_ttl_seconds);
+
+                    var cert = metrics.certificate;
+                    setText('cert-expiry', cert ? cert.days_until_expiry : '-');
+                    document.getElementById('cert-card').className = cert && cert.days_until_expiry <= 7 ? 'card warn' : 'card';
+
+                    renderConnections(metrics.active_connections);
+                    setText('error', '');
+                    setText('updated', new Date().toLocaleTimeString());
+                })
+                .catch(function (err) {
+                    setText('error', 'Failed to load metrics: ' + err.message);
+                });
+        }
+
+        refresh();
+        setInterval(refresh, 5000);
+    </script>
+</body>
+</html>
+"#;
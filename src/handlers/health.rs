@@ -1,10 +1,29 @@
+use crate::server::{SharedCertificateInfo, StartTime, TtlController};
+use crate::utils::http::json_content_type;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use http_body_util::Full;
 use hyper::body::Bytes;
 use hyper::{Response, StatusCode};
-use serde_json::json;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::debug;
+use serde_json::{Map, Value, json};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// How long each registered check gets unless configured otherwise
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of a health check: `Err` carries why the dependency is unhealthy
+pub type HealthCheckResult = std::result::Result<(), String>;
+
+/// Future returned by a `HealthCheck`
+pub type HealthCheckFuture = Pin<Box<dyn Future<Output = HealthCheckResult> + Send>>;
+
+/// Dependency check added with `HealthHandler::register_check`, run on
+/// every `/health` request
+pub type HealthCheck = Arc<dyn Fn() -> HealthCheckFuture + Send + Sync>;
 
 #[derive(Debug, Clone)]
 pub struct HealthStatus {
@@ -15,16 +34,96 @@ pub struct HealthStatus {
 }
 
 pub struct HealthHandler {
-    start_time: SystemTime,
+    start_time: StartTime,
     version: String,
+    json_content_type: String,
+    // Sources for the readiness probes; a missing one fails its probe
+    certificate_info: Option<SharedCertificateInfo>,
+    ttl_controller: Option<Arc<TtlController>>,
+    // Reported under `checks` in registration order
+    checks: Vec<(String, HealthCheck)>,
+    check_timeout: Duration,
 }
 
 impl HealthHandler {
     pub fn new(version: String) -> Self {
         Self {
-            start_time: SystemTime::now(),
+            start_time: StartTime::now(),
             version,
+            json_content_type: json_content_type(None),
+            certificate_info: None,
+            ttl_controller: None,
+            checks: Vec::new(),
+            check_timeout: DEFAULT_CHECK_TIMEOUT,
+        }
+    }
+
+    pub fn set_json_content_type(&mut self, content_type: String) {
+        self.json_content_type = content_type;
+    }
+
+    /// Shares the server's start time, so `/health` and `/metrics` agree on uptime
+    pub fn set_start_time(&mut self, start_time: StartTime) {
+        self.start_time = start_time;
+    }
+
+    pub fn set_certificate_info(&mut self, certificate_info: SharedCertificateInfo) {
+        self.certificate_info = Some(certificate_info);
+    }
+
+    pub fn set_ttl_controller(&mut self, ttl_controller: Arc<TtlController>) {
+        self.ttl_controller = Some(ttl_controller);
+    }
+
+    /// Longest any one registered check may run before it counts as failed
+    pub fn set_check_timeout(&mut self, timeout: Duration) {
+        self.check_timeout = timeout;
+    }
+
+    /// Adds a dependency check (database, cache, upstream...) reported under
+    /// `checks.<name>` by `/health`. Any failing check makes the service
+    /// `unhealthy` with a `503`. Registering a name again replaces its check.
+    pub fn register_check<F, Fut>(&mut self, name: &str, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HealthCheckResult> + Send + 'static,
+    {
+        let check: HealthCheck = Arc::new(move || Box::pin(check()));
+        match self
+            .checks
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some((_, existing)) => *existing = check,
+            None => self.checks.push((name.to_string(), check)),
+        }
+    }
+
+    /// Runs every registered check concurrently, each under the check timeout
+    async fn run_checks(&self) -> Vec<(&str, HealthCheckResult)> {
+        let timeout = self.check_timeout;
+        let running: Vec<_> = self
+            .checks
+            .iter()
+            .map(|(name, check)| {
+                let check = check();
+                let task = tokio::spawn(async move {
+                    tokio::time::timeout(timeout, check)
+                        .await
+                        .unwrap_or_else(|_| Err(format!("timed out after {timeout:?}")))
+                });
+                (name.as_str(), task)
+            })
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(running.len());
+        for (name, task) in running {
+            let outcome = task
+                .await
+                .unwrap_or_else(|e| Err(format!("check panicked: {e}")));
+            outcomes.push((name, outcome));
         }
+        outcomes
     }
 
     pub async fn handle_health_check(&self) -> Result<Response<Full<Bytes>>> {
@@ -33,13 +132,36 @@ impl HealthHandler {
         let now = SystemTime::now();
         let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
 
-        let uptime_seconds = now
-            .duration_since(self.start_time)
-            .unwrap_or_default()
-            .as_secs();
+        let uptime_seconds = self.start_time.uptime().as_secs();
+
+        let outcomes = self.run_checks().await;
+        let failed: Vec<&str> = outcomes
+            .iter()
+            .filter(|(_, outcome)| outcome.is_err())
+            .map(|(name, _)| *name)
+            .collect();
+        let checks: Map<String, Value> = outcomes
+            .into_iter()
+            .map(|(name, outcome)| {
+                let outcome = outcome.map_or_else(|reason| reason, |()| "ok".to_string());
+                (name.to_string(), Value::from(outcome))
+            })
+            .collect();
+
+        let status = if failed.is_empty() {
+            StatusCode::OK
+        } else {
+            warn!("Health check failed: {}", failed.join(", "));
+            StatusCode::SERVICE_UNAVAILABLE
+        };
 
         let health_status = HealthStatus {
-            status: "healthy".to_string(),
+            status: if failed.is_empty() {
+                "healthy"
+            } else {
+                "unhealthy"
+            }
+            .to_string(),
             timestamp,
             uptime_seconds,
             version: self.version.clone(),
@@ -51,52 +173,95 @@ impl HealthHandler {
             "uptime_seconds": health_status.uptime_seconds,
             "version": health_status.version,
             "service": "rusty-ssl",
-            "checks": {
-                "ssl": "ok",
-                "ttl_manager": "ok",
-                "memory": "ok"
-            }
+            "checks": checks
         });
 
         let response = Response::builder()
-            .status(StatusCode::OK)
-            .header("Content-Type", "application/json")
+            .status(status)
+            .header("Content-Type", &self.json_content_type)
             .header("Cache-Control", "no-cache")
             .body(Full::new(Bytes::from(response_body.to_string())))?;
 
         Ok(response)
     }
 
+    /// Ready once a certificate is loaded and unexpired and the TTL cleanup
+    /// task is running. Otherwise `503`, naming the failing checks.
     pub async fn handle_readiness_check(&self) -> Result<Response<Full<Bytes>>> {
         debug!("Readiness check requested");
 
-        // In a real implementation, you would check:
-        // - SSL certificates are loaded and valid
-        // - TTL controller is operational
-        // - External dependencies are reachable
+        let probes = [
+            ("ssl_certificates", self.check_certificate()),
+            ("ttl_controller", self.check_ttl_controller()),
+        ];
+        let failed: Vec<&str> = probes
+            .iter()
+            .filter(|(_, outcome)| outcome.is_err())
+            .map(|(name, _)| *name)
+            .collect();
+        let checks: Map<String, Value> = probes
+            .into_iter()
+            .map(|(name, outcome)| {
+                let outcome = outcome.map_or_else(|reason| reason, |()| "ready".to_string());
+                (name.to_string(), Value::from(outcome))
+            })
+            .collect();
+
+        let status = if failed.is_empty() {
+            StatusCode::OK
+        } else {
+            warn!("Readiness check failed: {}", failed.join(", "));
+            StatusCode::SERVICE_UNAVAILABLE
+        };
 
         let response_body = json!({
-            "status": "ready",
+            "status": if failed.is_empty() { "ready" } else { "not_ready" },
             "timestamp": SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
-            "checks": {
-                "ssl_certificates": "ready",
-                "ttl_controller": "ready",
-                "network": "ready"
-            }
+            "failed": failed,
+            "checks": checks
         });
 
         let response = Response::builder()
-            .status(StatusCode::OK)
-            .header("Content-Type", "application/json")
+            .status(status)
+            .header("Content-Type", &self.json_content_type)
             .header("Cache-Control", "no-cache")
             .body(Full::new(Bytes::from(response_body.to_string())))?;
 
         Ok(response)
     }
 
+    fn check_certificate(&self) -> Result<(), String> {
+        let Some(certificate_info) = &self.certificate_info else {
+            return Err("no certificate loaded".to_string());
+        };
+        let certificate_info = certificate_info
+            .read()
+            .map_err(|_| "certificate info unavailable".to_string())?;
+        let Some(cert) = certificate_info.as_ref() else {
+            return Err("no certificate loaded".to_string());
+        };
+
+        // The flag is only refreshed periodically, so check the date too
+        if cert.is_expired || cert.not_after <= SystemTime::now() {
+            return Err(format!(
+                "certificate expired at {}",
+                DateTime::<Utc>::from(cert.not_after).to_rfc3339()
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_ttl_controller(&self) -> Result<(), String> {
+        match &self.ttl_controller {
+            Some(ttl_controller) if ttl_controller.cleanup_running() => Ok(()),
+            Some(_) => Err("cleanup task not running".to_string()),
+            None => Err("no TTL controller attached".to_string()),
+        }
+    }
+
     pub async fn handle_liveness_check(&self) -> Result<Response<Full<Bytes>>> {
         debug!("Liveness check requested");
 
@@ -111,7 +276,7 @@ impl HealthHandler {
 
         let response = Response::builder()
             .status(StatusCode::OK)
-            .header("Content-Type", "application/json")
+            .header("Content-Type", &self.json_content_type)
             .header("Cache-Control", "no-cache")
             .body(Full::new(Bytes::from(response_body.to_string())))?;
 
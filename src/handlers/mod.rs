@@ -1,3 +1,8 @@
+pub mod dashboard;
 pub mod health;
+pub mod proxy;
+pub mod static_files;
 
-pub use health::HealthHandler;
+pub use health::{HealthCheck, HealthCheckResult, HealthHandler};
+pub use proxy::ProxyHandler;
+pub use static_files::StaticHandler;
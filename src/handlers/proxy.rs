@@ -0,0 +1,253 @@
+use crate::server::forwarded::X_FORWARDED_FOR;
+use crate::utils::config::ProxyConfig;
+use crate::utils::http::json_content_type;
+use anyhow::Result;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{CONNECTION, CONTENT_TYPE, HOST, HeaderMap, HeaderName, HeaderValue};
+use hyper::http::uri::{Authority, Scheme};
+use hyper::{Request, Response, StatusCode, Uri, Version};
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use serde_json::json;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+pub const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Headers that describe a single hop, never forwarded (RFC 9110 §7.6.1)
+const HOP_BY_HOP: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Error, Debug)]
+pub enum ProxyConfigError {
+    #[error("prefix '{0}' must be a plain path starting with '/'")]
+    InvalidPrefix(String),
+    #[error("upstream '{url}' for '{prefix}' must be an http:// URL without a query")]
+    InvalidUpstream { prefix: String, url: String },
+}
+
+/// Where requests below one prefix are sent
+#[derive(Debug, Clone)]
+struct Upstream {
+    prefix: String,
+    authority: Authority,
+    base_path: String,
+}
+
+impl Upstream {
+    fn parse(prefix: &str, url: &str) -> Result<Self, ProxyConfigError> {
+        if !prefix.starts_with('/') || prefix.contains([':', '*', '?']) {
+            return Err(ProxyConfigError::InvalidPrefix(prefix.to_string()));
+        }
+        let invalid = || ProxyConfigError::InvalidUpstream {
+            prefix: prefix.to_string(),
+            url: url.to_string(),
+        };
+
+        let uri: Uri = url.parse().map_err(|_| invalid())?;
+        if uri.scheme() != Some(&Scheme::HTTP) || uri.query().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            authority: uri.authority().cloned().ok_or_else(invalid)?,
+            base_path: uri.path().trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Whether `path` is the prefix itself or below it, on a segment boundary
+    fn matches(&self, path: &str) -> bool {
+        path.strip_prefix(&self.prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// The upstream URI for `uri`, with the prefix swapped for the upstream's path
+    fn target(&self, uri: &Uri) -> Result<Uri> {
+        let rest = &uri.path()[self.prefix.len()..];
+        let mut path = format!("{}{}", self.base_path, rest);
+        if path.is_empty() {
+            path.push('/');
+        }
+        if let Some(query) = uri.query() {
+            path = format!("{path}?{query}");
+        }
+        Ok(Uri::builder()
+            .scheme(Scheme::HTTP)
+            .authority(self.authority.clone())
+            .path_and_query(path)
+            .build()?)
+    }
+}
+
+/// Forwards requests below configured prefixes to backend HTTP services,
+/// relaying their responses. Unreachable backends are answered with 502.
+pub struct ProxyHandler {
+    // Longest prefix first, so the most specific mapping wins
+    upstreams: Vec<Upstream>,
+    client: Client<HttpConnector, Incoming>,
+    // Responses are buffered whole, so this bounds the memory each one takes
+    max_response_bytes: usize,
+    json_content_type: String,
+}
+
+impl ProxyHandler {
+    pub fn new(config: &ProxyConfig) -> Result<Self, ProxyConfigError> {
+        let mut upstreams = config
+            .routes
+            .iter()
+            .map(|(prefix, url)| Upstream::parse(prefix, url))
+            .collect::<Result<Vec<_>, _>>()?;
+        upstreams.sort_by_key(|upstream| std::cmp::Reverse(upstream.prefix.len()));
+
+        Ok(Self {
+            upstreams,
+            client: Client::builder(TokioExecutor::new()).build_http(),
+            max_response_bytes: config.max_response_bytes,
+            json_content_type: json_content_type(None),
+        })
+    }
+
+    pub fn set_json_content_type(&mut self, content_type: String) {
+        self.json_content_type = content_type;
+    }
+
+    /// Whether a request for `path` would be forwarded
+    pub fn matches(&self, path: &str) -> bool {
+        self.upstream_for(path).is_some()
+    }
+
+    fn upstream_for(&self, path: &str) -> Option<&Upstream> {
+        self.upstreams
+            .iter()
+            .find(|upstream| upstream.matches(path))
+    }
+
+    /// Sends `req` to its upstream with hop-by-hop headers removed and the
+    /// client appended to `X-Forwarded-For`. `peer_ip` is the address that
+    /// connected; a forwarded chain is only kept when it vouched for
+    /// `client_ip`, so clients can't forge their own.
+    pub async fn handle(
+        &self,
+        req: Request<Incoming>,
+        client_ip: IpAddr,
+        peer_ip: IpAddr,
+    ) -> Result<Response<Full<Bytes>>> {
+        let Some(upstream) = self.upstream_for(req.uri().path()) else {
+            anyhow::bail!("no upstream configured for {}", req.uri().path());
+        };
+        let target = upstream.target(req.uri())?;
+        let (mut parts, body) = req.into_parts();
+
+        let original_host = parts
+            .headers
+            .get(HOST)
+            .cloned()
+            .or_else(|| parts.uri.authority().and_then(|a| a.as_str().parse().ok()));
+        let forwarded_for = forwarded_for(&parts.headers, client_ip, peer_ip);
+        strip_hop_by_hop(&mut parts.headers);
+        // The client sets Host to the upstream's authority
+        parts.headers.remove(HOST);
+        parts.headers.insert(X_FORWARDED_FOR, forwarded_for);
+        if let Some(host) = original_host {
+            parts.headers.insert(X_FORWARDED_HOST, host);
+        }
+
+        debug!("Proxying {} {} to {}", parts.method, parts.uri, target);
+        parts.uri = target.clone();
+        parts.version = Version::HTTP_11;
+        let upstream_response = match self.client.request(Request::from_parts(parts, body)).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("502 Bad Gateway: upstream {} failed: {}", target, e);
+                return self.bad_gateway(&target);
+            }
+        };
+
+        let (mut parts, body) = upstream_response.into_parts();
+        let body = match Limited::new(body, self.max_response_bytes).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) if e.is::<LengthLimitError>() => {
+                warn!(
+                    "502 Bad Gateway: upstream {} response exceeds proxy.max_response_bytes ({})",
+                    target, self.max_response_bytes
+                );
+                return self.bad_gateway(&target);
+            }
+            Err(e) => {
+                warn!(
+                    "502 Bad Gateway: upstream {} response failed: {}",
+                    target, e
+                );
+                return self.bad_gateway(&target);
+            }
+        };
+        strip_hop_by_hop(&mut parts.headers);
+        Ok(Response::from_parts(parts, Full::new(body)))
+    }
+
+    fn bad_gateway(&self, target: &Uri) -> Result<Response<Full<Bytes>>> {
+        let body = json!({
+            "error": "Bad Gateway",
+            "message": format!(
+                "Upstream {} is unavailable",
+                target.authority().map_or("", Authority::as_str)
+            ),
+            "status": 502,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        Ok(Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .header(CONTENT_TYPE, &self.json_content_type)
+            .body(Full::new(Bytes::from(body.to_string())))?)
+    }
+}
+
+/// Drops the fixed hop-by-hop headers and any the `Connection` header names
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let named: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    for name in named {
+        headers.remove(name);
+    }
+    for name in HOP_BY_HOP {
+        headers.remove(name);
+    }
+}
+
+/// `X-Forwarded-For` for the upstream: the inbound chain plus the peer when
+/// a trusted proxy resolved the client, otherwise just the client
+fn forwarded_for(headers: &HeaderMap, client_ip: IpAddr, peer_ip: IpAddr) -> HeaderValue {
+    let inbound: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    let chain = if client_ip == peer_ip || inbound.is_empty() {
+        client_ip.to_string()
+    } else {
+        format!("{}, {}", inbound.join(", "), peer_ip)
+    };
+    HeaderValue::from_str(&chain).expect("IP addresses are valid header values")
+}
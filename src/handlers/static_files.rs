@@ -0,0 +1,257 @@
+use crate::server::path_params::PathParams;
+use crate::utils::config::StaticFilesConfig;
+use crate::utils::http::json_content_type;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{
+    CACHE_CONTROL, CONTENT_TYPE, ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use hyper::{Response, StatusCode};
+use serde_json::json;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+use tracing::{debug, warn};
+
+/// Route parameter holding the file's path below the prefix
+pub const PATH_PARAM: &str = "path";
+
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Serves files below a directory, e.g. a status dashboard's assets
+pub struct StaticHandler {
+    root: PathBuf,
+    max_file_bytes: u64,
+    json_content_type: String,
+}
+
+impl StaticHandler {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            max_file_bytes: StaticFilesConfig::default().max_file_bytes,
+            json_content_type: json_content_type(None),
+        }
+    }
+
+    /// Files are buffered whole, so larger ones are refused rather than
+    /// read into memory
+    pub fn set_max_file_bytes(&mut self, max_file_bytes: u64) {
+        self.max_file_bytes = max_file_bytes;
+    }
+
+    pub fn set_json_content_type(&mut self, content_type: String) {
+        self.json_content_type = content_type;
+    }
+
+    /// Serves the file named by the `path` route parameter. Paths that
+    /// climb out of the root are refused with 403; missing files and
+    /// directories are 404, and files over `max_file_bytes` are 500.
+    /// Answers 304 to matching conditional requests.
+    pub async fn handle(
+        &self,
+        params: &PathParams,
+        headers: &HeaderMap,
+    ) -> Result<Response<Full<Bytes>>> {
+        let requested = params.get(PATH_PARAM).unwrap_or_default();
+        let Some(relative) = sanitize(requested) else {
+            warn!(
+                "403 Forbidden: static path '{}' escapes the root",
+                requested
+            );
+            return self.error(StatusCode::FORBIDDEN, requested);
+        };
+
+        let path = match self.resolve(&relative).await {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                warn!(
+                    "403 Forbidden: static path '{}' links outside the root",
+                    requested
+                );
+                return self.error(StatusCode::FORBIDDEN, requested);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return self.error(StatusCode::NOT_FOUND, requested);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let metadata = tokio::fs::metadata(&path).await?;
+        if !metadata.is_file() {
+            return self.error(StatusCode::NOT_FOUND, requested);
+        }
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        let etag = entity_tag(metadata.len(), modified);
+        let last_modified = DateTime::<Utc>::from(modified)
+            .format(HTTP_DATE_FORMAT)
+            .to_string();
+
+        if not_modified(headers, &etag, modified) {
+            debug!("Static file {} not modified", path.display());
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(ETAG, etag)
+                .header(LAST_MODIFIED, last_modified)
+                .body(Full::new(Bytes::new()))?);
+        }
+
+        if metadata.len() > self.max_file_bytes {
+            warn!(
+                "500 Internal Server Error: static file {} ({} bytes) exceeds static_files.max_file_bytes ({})",
+                path.display(),
+                metadata.len(),
+                self.max_file_bytes
+            );
+            return self.error(StatusCode::INTERNAL_SERVER_ERROR, requested);
+        }
+
+        // Bounded again in case the file grew since the metadata was read
+        let mut contents = Vec::with_capacity(metadata.len() as usize);
+        tokio::fs::File::open(&path)
+            .await?
+            .take(self.max_file_bytes + 1)
+            .read_to_end(&mut contents)
+            .await?;
+        if contents.len() as u64 > self.max_file_bytes {
+            warn!(
+                "500 Internal Server Error: static file {} grew past static_files.max_file_bytes ({})",
+                path.display(),
+                self.max_file_bytes
+            );
+            return self.error(StatusCode::INTERNAL_SERVER_ERROR, requested);
+        }
+        debug!(
+            "Serving static file {} ({} bytes)",
+            path.display(),
+            contents.len()
+        );
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, content_type(&path))
+            .header(ETAG, etag)
+            .header(LAST_MODIFIED, last_modified)
+            .header(CACHE_CONTROL, "no-cache")
+            .body(Full::new(Bytes::from(contents)))?)
+    }
+
+    /// Joins `relative` to the root, refusing symlinks that lead out of it
+    async fn resolve(&self, relative: &Path) -> io::Result<Option<PathBuf>> {
+        let root = tokio::fs::canonicalize(&self.root).await?;
+        let path = tokio::fs::canonicalize(root.join(relative)).await?;
+        Ok(path.starts_with(&root).then_some(path))
+    }
+
+    fn error(&self, status: StatusCode, path: &str) -> Result<Response<Full<Bytes>>> {
+        let reason = status.canonical_reason().unwrap_or_default();
+        let body = json!({
+            "error": reason,
+            "message": format!("Static file '{}': {}", path, reason),
+            "status": status.as_u16(),
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        Ok(Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, &self.json_content_type)
+            .body(Full::new(Bytes::from(body.to_string())))?)
+    }
+}
+
+/// Percent-decodes the requested path and keeps it only if every segment
+/// is a plain name: no `..`, `.`, empty segments, backslashes or NULs
+fn sanitize(requested: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(requested)?;
+    if decoded.contains(['\\', '\0']) {
+        return None;
+    }
+
+    let path = PathBuf::from(&decoded);
+    let plain = decoded.split('/').all(|segment| !segment.is_empty())
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    plain.then_some(path)
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Changes whenever the file's size or modification time does
+fn entity_tag(len: u64, modified: SystemTime) -> String {
+    let nanos = modified
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("\"{len:x}-{nanos:x}\"")
+}
+
+/// `If-None-Match` takes precedence over `If-Modified-Since`, as in RFC 9110
+fn not_modified(headers: &HeaderMap, etag: &str, modified: SystemTime) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+        return if_none_match.to_str().is_ok_and(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+    }
+
+    headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok())
+        .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+        .is_some_and(|since| {
+            // HTTP dates have whole-second precision
+            let modified_secs = DateTime::<Utc>::from(modified).timestamp();
+            modified_secs <= since.timestamp()
+        })
+}
+
+/// Guesses the `Content-Type` from the file extension
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
@@ -2,5 +2,7 @@ pub mod handlers;
 pub mod server;
 pub mod utils;
 
-pub use server::{Router, SslManager, TtlController};
+pub use server::{
+    AcceptPolicy, ConnectionHandler, Decision, Router, ServerStats, SslManager, TtlController,
+};
 pub use utils::{AppConfig, init_logging};
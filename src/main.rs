@@ -1,137 +1,357 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
-use hyper::server::conn::http1;
-use hyper::service::service_fn;
-use hyper_util::rt::TokioIo;
-use rusty_ssl::{AppConfig, Router, SslManager, TtlController, init_logging};
-use tokio::net::TcpListener;
+use clap::Parser;
+#[cfg(feature = "audit-sqlite")]
+use rusty_ssl::server::audit::SqliteAuditLog;
+use rusty_ssl::server::{
+    AccessControl, AccessLog, AdaptiveTtlStrategy, ConfigReloader, ConnectionSnapshot, ReadyFile,
+    StartupTimings, serve_https_redirect, serve_listener, shutdown_listeners,
+    spawn_certificate_monitor, spawn_certificate_watcher, spawn_snapshot_task,
+};
+#[cfg(unix)]
+use rusty_ssl::server::{serve_unix_listener, spawn_sighup_reloader};
+use rusty_ssl::utils::cli::ConfigFormat;
+use rusty_ssl::utils::config::AccessLogFormat;
+#[cfg(unix)]
+use rusty_ssl::utils::net::bind_unix;
+use rusty_ssl::utils::net::{bind_error, bind_tcp};
+use rusty_ssl::utils::{AppConfig, CliArgs};
+use rusty_ssl::{ConnectionHandler, Router, SslManager, TtlController, init_logging};
 use tokio::sync::Mutex;
-use tokio_rustls::TlsAcceptor;
+use tokio::task::JoinSet;
 use tracing::{error, info, warn};
 
+#[cfg(feature = "audit-sqlite")]
+const AUDIT_CHANNEL_CAPACITY: usize = 1024;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Load configuration
-    let config = AppConfig::load().map_err(|e| {
+    let mut startup = StartupTimings::default();
+
+    // Load configuration; command-line flags override files and env vars
+    let phase_started = Instant::now();
+    let args = CliArgs::parse();
+    if let Some(format) = args.dump_default_config {
+        print!("{}", format.render(&AppConfig::default())?);
+        return Ok(());
+    }
+    let config = args.load_config().map_err(|e| {
         eprintln!("Failed to load configuration: {}", e);
         std::process::exit(1);
     })?;
+    if args.print_config {
+        print!("{}", ConfigFormat::Toml.render(&config)?);
+        return Ok(());
+    }
+    if let Err(errors) = config.validate() {
+        for error in &errors {
+            eprintln!("Invalid configuration: {}", error);
+        }
+        std::process::exit(1);
+    }
+    startup.config_load = phase_started.elapsed();
 
     // Initialize logging
-    init_logging(&config.logging)?;
+    let logging_guard = init_logging(&config.logging, &config.telemetry)?;
     info!("Starting Rusty-SSL server v{}", env!("CARGO_PKG_VERSION"));
+    #[cfg(not(feature = "otlp"))]
+    if config.telemetry.otlp_endpoint.is_some() {
+        warn!("telemetry.otlp_endpoint is set but the otlp feature is not enabled");
+    }
 
     // Initialize SSL manager
-    let ssl_manager = SslManager::new(
-        &config.ssl.cert_path,
-        &config.ssl.key_path,
-        config.cert_check_interval(),
-    )
-    .map_err(|e| {
+    let phase_started = Instant::now();
+    let ssl_manager = SslManager::provision(&config.ssl).await.map_err(|e| {
         error!("Failed to initialize SSL manager: {}", e);
         std::process::exit(1);
     })?;
 
-    let tls_config = ssl_manager.get_config();
-    let acceptor = TlsAcceptor::from(tls_config);
+    startup.cert_load = phase_started.elapsed();
+
+    // Serving a certificate for another domain is otherwise only noticed by clients
+    if let Some(cert) = ssl_manager.get_certificate_info()
+        && config.server.host.parse::<std::net::IpAddr>().is_err()
+        && !cert.covers(&config.server.host)
+    {
+        warn!(
+            "server.host {} is not among the certificate's DNS names: {}",
+            config.server.host,
+            cert.dns_names.join(", ")
+        );
+    }
+
+    let acceptor = ssl_manager.acceptor();
 
     // Initialize TTL controller
-    let ttl_controller = Arc::new(Mutex::new(TtlController::new(
+    let mut ttl_controller = TtlController::new(
         config.default_ttl(),
         config.max_ttl(),
         config.cleanup_interval(),
+        config.server.max_connections,
+    );
+    ttl_controller.set_ttl_strategy(Arc::new(AdaptiveTtlStrategy::new(
+        config.ttl.adaptive.clone(),
     )));
+    ttl_controller.set_history_limit(config.ttl.history_size);
+    ttl_controller.set_history_budget_bytes(config.ttl.history_budget_bytes);
+    ttl_controller.set_ttl_bands(config.ttl.bands.clone());
+    ttl_controller.set_reconnect_grace(config.reconnect_grace());
+    ttl_controller.set_never_expire(config.access_control.allow.clone());
+    if let Some(path) = &config.ttl.stats_path {
+        ttl_controller.set_stats_path(path.clone());
+    }
+
+    // Persist connection lifecycle events when an audit log is configured
+    #[cfg(feature = "audit-sqlite")]
+    if let Some(path) = &config.audit.sqlite_path {
+        let audit_log = SqliteAuditLog::open(path).map_err(|e| {
+            error!("Failed to open audit log: {}", e);
+            std::process::exit(1);
+        })?;
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(AUDIT_CHANNEL_CAPACITY);
+        ttl_controller.set_event_sender(event_tx);
+        audit_log.spawn(event_rx);
+    }
+
+    #[cfg(not(feature = "audit-sqlite"))]
+    if config.audit.sqlite_path.is_some() {
+        warn!("audit.sqlite_path is set but the audit-sqlite feature is not enabled");
+    }
+
+    // Pick up connections tracked before a crash or restart
+    if let Some(path) = &config.ttl.snapshot_path {
+        match ConnectionSnapshot::load(path) {
+            Ok(snapshot) => {
+                ttl_controller.restore_snapshot(&snapshot);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Ignoring unreadable snapshot {}: {}", path.display(), e),
+        }
+    }
+
+    let ttl_controller = Arc::new(ttl_controller);
 
     // Initialize router
-    let router = Arc::new(Router::new(ttl_controller.clone()));
+    let mut router = Router::new(ttl_controller.clone(), &config);
+    router.set_certificate_info(ssl_manager.certificate_info_handle());
+    if let Some(path) = &config.logging.access_log_path
+        && config.logging.access_log_format != AccessLogFormat::None
+    {
+        let access_log = AccessLog::open(config.logging.access_log_format, path).map_err(|e| {
+            error!("Failed to open access log {}: {}", path.display(), e);
+            std::process::exit(1);
+        })?;
+        router.set_access_log(access_log);
+    }
+    let ssl_manager = Arc::new(Mutex::new(ssl_manager));
+    router.set_ssl_manager(ssl_manager.clone());
+    let stats = router.stats();
+    let shared_config = router.config_handle();
+    let router = Arc::new(router);
+    let mut connection_handler = ConnectionHandler::new(router, &config);
+    connection_handler.set_accept_policy(Arc::new(AccessControl::new(&config.access_control)));
+    let connection_handler = Arc::new(connection_handler);
 
-    // Bind to address
-    let addr = config.server_addr()?;
-    let listener = TcpListener::bind(&addr).await?;
-    info!("Server listening on https://{}", addr);
+    // Bind every listen address up front so a bad one fails startup. A
+    // Unix socket replaces the TCP listeners.
+    let phase_started = Instant::now();
+    #[cfg(unix)]
+    let unix_listener = match &config.server.unix_socket {
+        Some(path) => {
+            let listener = bind_unix(path, config.server.unix_socket_force).map_err(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            })?;
+            info!("Server listening on unix:{}", path.display());
+            Some(listener)
+        }
+        None => None,
+    };
+    let addrs = if config.server.unix_socket.is_some() {
+        Vec::new()
+    } else {
+        config.listen_addrs()?
+    };
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let listener = bind_tcp(addr, &config).map_err(|e| {
+            error!("{}", bind_error(addr, e));
+            std::process::exit(1);
+        })?;
+        info!("Server listening on https://{}", addr);
+        listeners.push(listener);
+    }
+    let redirect_listener = match config.http_redirect_addr().transpose()? {
+        Some(addr) => {
+            let listener = bind_tcp(addr, &config).map_err(|e| {
+                error!("{}", bind_error(addr, e));
+                std::process::exit(1);
+            })?;
+            info!("Redirecting http://{} to HTTPS", addr);
+            Some(listener)
+        }
+        None => None,
+    };
+    startup.listener_bind = phase_started.elapsed();
+    info!(
+        "Socket options: tcp_nodelay={}, so_keepalive={:?}, listen_backlog={}",
+        config.server.tcp_nodelay,
+        config.so_keepalive(),
+        config.server.listen_backlog
+    );
+    info!(
+        "Startup took {:?} (config {:?}, certificates {:?}, bind {:?})",
+        startup.config_load + startup.cert_load + startup.listener_bind,
+        startup.config_load,
+        startup.cert_load,
+        startup.listener_bind
+    );
+    stats.record_startup(startup);
 
     // Start background tasks
-    let ssl_task = {
-        let mut ssl_manager_clone = ssl_manager;
-        tokio::spawn(async move {
-            ssl_manager_clone.start_certificate_monitoring().await;
-        })
+    let ssl_task = spawn_certificate_monitor(ssl_manager.clone(), config.cert_check_interval());
+
+    // Polling only catches expiry; the watcher picks up renewals right away
+    let watcher_task = if config.ssl.watch_files {
+        spawn_certificate_watcher(ssl_manager, &config.ssl, config.reload_debounce())
+            .map_err(|e| warn!("Certificate file watching unavailable: {}", e))
+            .ok()
+    } else {
+        None
     };
 
+    // SIGHUP re-reads the config and applies what can change live
+    let mut reloader = ConfigReloader::new(config.clone(), ttl_controller.clone());
+    if let Some(log_level) = logging_guard.log_level() {
+        reloader.set_log_level_handle(log_level);
+    }
+    reloader.set_connection_handler(connection_handler.clone());
+    reloader.set_shared_config(shared_config);
+    #[cfg(unix)]
+    let reload_task = spawn_sighup_reloader(reloader, move || args.load_config())
+        .map_err(|e| warn!("Config reload on SIGHUP unavailable: {}", e))
+        .ok();
+    #[cfg(not(unix))]
+    drop(reloader);
+
     let ttl_task = {
         let ttl_controller_clone = ttl_controller.clone();
         tokio::spawn(async move {
-            let mut ttl_controller = ttl_controller_clone.lock().await;
-            ttl_controller.start_cleanup_task().await;
+            ttl_controller_clone.start_cleanup_task().await;
         })
     };
 
-    // Server loop
-    let server_task = tokio::spawn(async move {
-        loop {
-            match listener.accept().await {
-                Ok((stream, remote_addr)) => {
-                    let acceptor = acceptor.clone();
-                    let router = router.clone();
-
-                    tokio::spawn(async move {
-                        let client_ip = remote_addr.ip();
-
-                        // Handle TLS handshake
-                        let tls_stream = match acceptor.accept(stream).await {
-                            Ok(tls_stream) => tls_stream,
-                            Err(e) => {
-                                warn!("TLS handshake failed for {}: {}", client_ip, e);
-                                return;
-                            }
-                        };
-
-                        let io = TokioIo::new(tls_stream);
-
-                        // Handle HTTP requests
-                        if let Err(e) = http1::Builder::new()
-                            .serve_connection(
-                                io,
-                                service_fn(move |req| {
-                                    let router = router.clone();
-                                    async move { router.route(req, client_ip).await }
-                                }),
-                            )
-                            .await
-                        {
-                            warn!("HTTP connection error for {}: {}", client_ip, e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    error!("Failed to accept connection: {}", e);
-                }
-            }
-        }
-    });
+    let snapshot_task =
+        config.ttl.snapshot_path.clone().map(|path| {
+            spawn_snapshot_task(ttl_controller.clone(), path, config.snapshot_interval())
+        });
+
+    // One accept loop per listener; each stops accepting once shutdown is
+    // requested and hands back the connections still being served
+    let shutdown = connection_handler.shutdown_token();
+    let mut server_tasks = JoinSet::new();
+    for listener in listeners {
+        server_tasks.spawn(serve_listener(
+            connection_handler.clone(),
+            listener,
+            acceptor.clone(),
+        ));
+    }
+    if let Some(listener) = redirect_listener {
+        server_tasks.spawn(serve_https_redirect(
+            listener,
+            config.https_port(),
+            shutdown.clone(),
+        ));
+    }
+    #[cfg(unix)]
+    if let Some(listener) = unix_listener {
+        let acceptor = config.server.unix_socket_tls.then(|| acceptor.clone());
+        server_tasks.spawn(serve_unix_listener(
+            connection_handler.clone(),
+            listener,
+            acceptor,
+        ));
+    }
+
+    // Signal readiness to orchestrators polling the filesystem
+    let ready_file = ReadyFile::new(config.server.ready_file.clone());
+    ready_file.mark_ready();
 
     // Setup graceful shutdown
     let shutdown_signal = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to install CTRL+C signal handler");
-        info!("Shutdown signal received");
+        let ctrl_c = async {
+            tokio::signal::ctrl_c()
+                .await
+                .expect("Failed to install CTRL+C signal handler");
+        };
+
+        // systemd and Kubernetes stop services with SIGTERM
+        #[cfg(unix)]
+        let terminate = async {
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Failed to install SIGTERM signal handler")
+                .recv()
+                .await;
+        };
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        let signal = tokio::select! {
+            _ = ctrl_c => "SIGINT",
+            _ = terminate => "SIGTERM",
+        };
+        info!("Shutdown signal received ({})", signal);
     };
 
-    // Wait for either server task completion or shutdown signal
+    // Wait for either a listener to stop or a shutdown signal
     tokio::select! {
-        _ = server_task => {
+        _ = server_tasks.join_next() => {
             info!("Server task completed");
         }
         _ = shutdown_signal => {
             info!("Shutting down gracefully...");
         }
+    };
+    shutdown_listeners(
+        &shutdown,
+        &mut server_tasks,
+        &ready_file,
+        config.shutdown_grace(),
+    )
+    .await;
+
+    // A stale socket file would block the next start
+    if let Some(path) = &config.server.unix_socket
+        && let Err(e) = std::fs::remove_file(path)
+    {
+        warn!("Failed to remove unix socket {}: {}", path.display(), e);
     }
 
     // Cancel background tasks
     ssl_task.abort();
+    if let Some(watcher_task) = watcher_task {
+        watcher_task.abort();
+    }
     ttl_task.abort();
+    #[cfg(unix)]
+    if let Some(reload_task) = reload_task {
+        reload_task.abort();
+    }
+    if let Some(snapshot_task) = snapshot_task {
+        snapshot_task.abort();
+    }
+
+    if let Some(path) = &config.ttl.snapshot_path
+        && let Err(e) = ttl_controller.snapshot().write_atomic(path)
+    {
+        warn!("Failed to write final snapshot {}: {}", path.display(), e);
+    }
+    if let Err(e) = ttl_controller.persist_stats() {
+        warn!("Failed to persist TTL stats: {}", e);
+    }
 
     info!("Server shutdown complete");
     Ok(())
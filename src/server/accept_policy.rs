@@ -0,0 +1,22 @@
+use std::net::SocketAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject,
+}
+
+/// Decides whether a freshly accepted TCP connection may proceed to the TLS
+/// handshake. Embedders can plug in their own logic (geo, reputation, ...).
+pub trait AcceptPolicy: Send + Sync {
+    fn decide(&self, peer: SocketAddr) -> Decision;
+}
+
+/// Default policy that lets every connection through
+pub struct AcceptAll;
+
+impl AcceptPolicy for AcceptAll {
+    fn decide(&self, _peer: SocketAddr) -> Decision {
+        Decision::Accept
+    }
+}
@@ -0,0 +1,40 @@
+use crate::server::accept_policy::{AcceptPolicy, Decision};
+use crate::utils::cidr::IpCidr;
+use crate::utils::config::AccessControlConfig;
+use std::net::{IpAddr, SocketAddr};
+
+/// CIDR allow and deny lists. A denied address is refused unless an allow
+/// entry also covers it; addresses on neither list are served normally.
+#[derive(Debug, Clone, Default)]
+pub struct AccessControl {
+    allow: Vec<IpCidr>,
+    deny: Vec<IpCidr>,
+}
+
+impl AccessControl {
+    pub fn new(config: &AccessControlConfig) -> Self {
+        Self {
+            allow: config.allow.clone(),
+            deny: config.deny.clone(),
+        }
+    }
+
+    /// Allowlisted clients bypass rate limiting and TTL expiry
+    pub fn is_allowlisted(&self, ip: IpAddr) -> bool {
+        self.allow.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        self.is_allowlisted(ip) || !self.deny.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+impl AcceptPolicy for AccessControl {
+    fn decide(&self, peer: SocketAddr) -> Decision {
+        if self.permits(peer.ip()) {
+            Decision::Accept
+        } else {
+            Decision::Reject
+        }
+    }
+}
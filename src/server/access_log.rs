@@ -0,0 +1,156 @@
+use crate::utils::config::AccessLogFormat;
+use chrono::{DateTime, Utc};
+use hyper::body::Body;
+use hyper::header::{CONTENT_LENGTH, HeaderValue, REFERER, USER_AGENT};
+use hyper::{Method, Request, Response, Version};
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// What one request contributes to its access line
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    pub client_ip: IpAddr,
+    pub method: Method,
+    pub target: String, // path and query, as requested
+    pub version: Version,
+    pub status: u16,
+    pub bytes: Option<u64>, // unknown for streamed bodies
+    pub referer: Option<String>,
+    pub user_agent: Option<String>,
+    pub time: DateTime<Utc>,
+    pub latency: Duration,
+}
+
+impl AccessLogEntry {
+    /// Captures the request side; status, size and latency are filled in by
+    /// `complete` once the handler has run
+    pub fn new<B>(req: &Request<B>, client_ip: IpAddr) -> Self {
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        Self {
+            client_ip,
+            method: req.method().clone(),
+            target: req
+                .uri()
+                .path_and_query()
+                .map_or_else(|| req.uri().path().to_string(), ToString::to_string),
+            version: req.version(),
+            status: 0,
+            bytes: None,
+            referer: header(REFERER),
+            user_agent: header(USER_AGENT),
+            time: Utc::now(),
+            latency: Duration::ZERO,
+        }
+    }
+
+    pub fn complete<B: Body>(&mut self, response: &Response<B>, latency: Duration) {
+        self.status = response.status().as_u16();
+        self.bytes = response.body().size_hint().exact().or_else(|| {
+            response
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse().ok())
+        });
+        self.latency = latency;
+    }
+
+    /// Renders the Common or Combined Log Format line, with the latency in
+    /// milliseconds appended. `None` renders nothing.
+    pub fn format(&self, format: AccessLogFormat) -> Option<String> {
+        if format == AccessLogFormat::None {
+            return None;
+        }
+
+        let mut line = format!(
+            "{} - - [{}] \"{} {} {:?}\" {} ",
+            self.client_ip,
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            escape(&self.target),
+            self.version,
+            self.status,
+        );
+        match self.bytes {
+            Some(bytes) => {
+                let _ = write!(line, "{bytes}");
+            }
+            None => line.push('-'),
+        }
+        if format == AccessLogFormat::Combined {
+            let _ = write!(
+                line,
+                " \"{}\" \"{}\"",
+                self.referer.as_deref().map_or("-".into(), escape),
+                self.user_agent.as_deref().map_or("-".into(), escape),
+            );
+        }
+        let _ = write!(line, " {:.3}", self.latency.as_secs_f64() * 1000.0);
+        Some(line)
+    }
+}
+
+/// Writes access lines to their own sink, apart from the diagnostic logs,
+/// so operators can ship them separately
+pub struct AccessLog {
+    format: AccessLogFormat,
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl AccessLog {
+    pub fn new(format: AccessLogFormat, sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            format,
+            sink: Mutex::new(sink),
+        }
+    }
+
+    pub fn stdout(format: AccessLogFormat) -> Self {
+        Self::new(format, Box::new(io::stdout()))
+    }
+
+    /// Appends to the file at `path`, creating it if needed
+    pub fn open(format: AccessLogFormat, path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(format, Box::new(file)))
+    }
+
+    pub fn record(&self, entry: &AccessLogEntry) {
+        let Some(mut line) = entry.format(self.format) else {
+            return;
+        };
+        line.push('\n');
+
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = sink.write_all(line.as_bytes()) {
+            warn!("Failed to write access log line: {}", e);
+        }
+    }
+}
+
+/// Escapes quotes, backslashes and control characters the way Apache does,
+/// so a client can't forge extra fields or lines
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
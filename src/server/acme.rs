@@ -0,0 +1,193 @@
+use crate::server::ssl_manager::{SslError, install_crypto_provider};
+use crate::utils::config::{AcmeConfig, SslConfig};
+use dashmap::DashMap;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use instant_acme::{
+    Account, AuthorizationStatus, ChallengeType, Identifier, NewAccount, NewOrder, OrderStatus,
+    RetryPolicy,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const CHALLENGE_PREFIX: &str = "/.well-known/acme-challenge/";
+
+/// Challenge token -> key authorization
+type ChallengeTokens = Arc<DashMap<String, String>>;
+
+fn acme_error(e: instant_acme::Error) -> SslError {
+    SslError::Acme(e.to_string())
+}
+
+/// Orders a certificate for `acme.domains` and writes the key and chain to
+/// `key_path` and `cert_path`
+pub async fn obtain_certificate(ssl_config: &SslConfig, acme: &AcmeConfig) -> Result<(), SslError> {
+    if acme.domains.is_empty() {
+        return Err(SslError::Acme("ssl.acme.domains is empty".to_string()));
+    }
+    info!(
+        "Requesting certificate for {} from {}",
+        acme.domains.join(", "),
+        acme.directory_url
+    );
+
+    // The CA client's TLS connections use the process default provider
+    install_crypto_provider();
+    let tokens = ChallengeTokens::default();
+    let challenge_server =
+        spawn_challenge_server(&acme.http_challenge_addr, tokens.clone()).await?;
+    let result = complete_order(acme, &tokens).await;
+    challenge_server.abort();
+    let (private_key_pem, cert_chain_pem) = result?;
+
+    // Key first: the file watcher may reload as soon as the chain lands
+    if let Some(dir) = ssl_config.key_path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&ssl_config.key_path, private_key_pem).await?;
+    if let Some(dir) = ssl_config.cert_path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&ssl_config.cert_path, cert_chain_pem).await?;
+
+    info!(
+        "ACME certificate written to {}",
+        ssl_config.cert_path.display()
+    );
+    Ok(())
+}
+
+/// Runs the order flow, returning the private key and certificate chain PEMs
+async fn complete_order(
+    acme: &AcmeConfig,
+    tokens: &ChallengeTokens,
+) -> Result<(String, String), SslError> {
+    let contact = acme
+        .contact_email
+        .as_ref()
+        .map(|email| format!("mailto:{email}"));
+    let contacts: Vec<&str> = contact.iter().map(String::as_str).collect();
+
+    let (account, _) = Account::builder()
+        .map_err(acme_error)?
+        .create(
+            &NewAccount {
+                contact: &contacts,
+                terms_of_service_agreed: true,
+                only_return_existing: false,
+            },
+            acme.directory_url.clone(),
+            None,
+        )
+        .await
+        .map_err(acme_error)?;
+
+    let identifiers: Vec<Identifier> = acme
+        .domains
+        .iter()
+        .map(|domain| Identifier::Dns(domain.clone()))
+        .collect();
+    let mut order = account
+        .new_order(&NewOrder::new(&identifiers))
+        .await
+        .map_err(acme_error)?;
+
+    let mut authorizations = order.authorizations();
+    while let Some(authz) = authorizations.next().await {
+        let mut authz = authz.map_err(acme_error)?;
+        match authz.status {
+            AuthorizationStatus::Pending => {}
+            AuthorizationStatus::Valid => continue,
+            status => {
+                return Err(SslError::Acme(format!(
+                    "authorization is {status:?}, expected pending"
+                )));
+            }
+        }
+
+        let mut challenge = authz
+            .challenge(ChallengeType::Http01)
+            .ok_or_else(|| SslError::Acme("CA offered no http-01 challenge".to_string()))?;
+        debug!("Answering http-01 challenge for {}", challenge.identifier());
+        tokens.insert(
+            challenge.token.clone(),
+            challenge.key_authorization().as_str().to_string(),
+        );
+        challenge.set_ready().await.map_err(acme_error)?;
+    }
+
+    let status = order
+        .poll_ready(&RetryPolicy::default())
+        .await
+        .map_err(acme_error)?;
+    if status != OrderStatus::Ready {
+        return Err(SslError::Acme(format!(
+            "order is {status:?}, expected ready"
+        )));
+    }
+
+    let private_key_pem = order.finalize().await.map_err(acme_error)?;
+    let cert_chain_pem = order
+        .poll_certificate(&RetryPolicy::default())
+        .await
+        .map_err(acme_error)?;
+    Ok((private_key_pem, cert_chain_pem))
+}
+
+/// Serves key authorizations over plain HTTP for the duration of an order
+async fn spawn_challenge_server(
+    addr: &str,
+    tokens: ChallengeTokens,
+) -> Result<JoinHandle<()>, SslError> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Answering ACME http-01 challenges on {}", addr);
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("ACME challenge listener error: {}", e);
+                    continue;
+                }
+            };
+            let tokens = tokens.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| {
+                    let tokens = tokens.clone();
+                    async move { Ok::<_, Infallible>(challenge_response(&req, &tokens)) }
+                });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!("ACME challenge connection error: {}", e);
+                }
+            });
+        }
+    }))
+}
+
+fn challenge_response<B>(req: &Request<B>, tokens: &ChallengeTokens) -> Response<Full<Bytes>> {
+    let key_authorization = req
+        .uri()
+        .path()
+        .strip_prefix(CHALLENGE_PREFIX)
+        .and_then(|token| tokens.get(token));
+
+    match key_authorization {
+        Some(key_authorization) => Response::new(Full::new(Bytes::from(key_authorization.clone()))),
+        None => {
+            let mut response = Response::new(Full::new(Bytes::new()));
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    }
+}
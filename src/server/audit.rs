@@ -0,0 +1,67 @@
+use crate::server::ttl_controller::ConnectionEvent;
+use rusqlite::{Connection, params};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+pub struct SqliteAuditLog {
+    conn: Connection,
+}
+
+impl SqliteAuditLog {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS connection_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recorded_at INTEGER NOT NULL,
+                event TEXT NOT NULL,
+                ip TEXT NOT NULL,
+                connection_id TEXT NOT NULL,
+                request_count INTEGER NOT NULL,
+                lifetime_ms INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        info!("Audit log opened at: {}", path.as_ref().display());
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, event: &ConnectionEvent) -> rusqlite::Result<()> {
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        self.conn.execute(
+            "INSERT INTO connection_events
+                (recorded_at, event, ip, connection_id, request_count, lifetime_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                recorded_at as i64,
+                event.kind.as_str(),
+                event.ip.to_string(),
+                event.connection_id.to_string(),
+                event.request_count as i64,
+                event.lifetime.as_millis() as i64,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Drains lifecycle events into the database until every sender is dropped
+    pub fn spawn(self, mut events: mpsc::Receiver<ConnectionEvent>) -> JoinHandle<()> {
+        // rusqlite is blocking, so keep it off the async worker threads
+        tokio::task::spawn_blocking(move || {
+            while let Some(event) = events.blocking_recv() {
+                if let Err(e) = self.record(&event) {
+                    error!("Failed to write audit event for IP {}: {}", event.ip, e);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use crate::utils::config::AuthConfig;
+use hyper::Request;
+use hyper::header::AUTHORIZATION;
+
+/// Returns true when the request carries one of the configured bearer tokens.
+/// With no tokens configured, authentication is disabled and every request passes.
+pub fn is_authorized<B>(req: &Request<B>, config: &AuthConfig) -> bool {
+    config.tokens.is_empty() || has_valid_token(req, config)
+}
+
+/// Returns true only when a configured token was actually presented
+pub fn has_valid_token<B>(req: &Request<B>, config: &AuthConfig) -> bool {
+    let Some(token) = bearer_token(req) else {
+        return false;
+    };
+
+    config
+        .tokens
+        .iter()
+        .any(|expected| constant_time_eq(expected.as_bytes(), token.as_bytes()))
+}
+
+/// The raw token from an `Authorization: Bearer` header, if any
+pub fn bearer_token<B>(req: &Request<B>) -> Option<&str> {
+    req.headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+// Avoid leaking how much of a token matched through response timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,86 @@
+use crate::server::SharedSslManager;
+use crate::utils::config::SslConfig;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Every certificate and key file the server loads
+fn certificate_files(ssl_config: &SslConfig) -> Vec<PathBuf> {
+    let mut files = vec![ssl_config.cert_path.clone(), ssl_config.key_path.clone()];
+    for entry in &ssl_config.sni_certs {
+        files.push(entry.cert_path.clone());
+        files.push(entry.key_path.clone());
+    }
+    files
+}
+
+/// Resolves the directory part so event paths, which notify reports under
+/// the watched directory, compare equal
+fn absolute_file(path: &Path) -> std::io::Result<PathBuf> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} does not name a file", path.display()),
+        )
+    })?;
+    Ok(dir.canonicalize()?.join(name))
+}
+
+/// Reloads certificates whenever a certificate or key file changes on disk.
+/// Directories are watched rather than files, since renewals commonly swap
+/// files in by rename or symlink. Changes arriving within `debounce` of each
+/// other, such as a certificate and key written back to back, trigger one
+/// reload.
+pub fn spawn_certificate_watcher(
+    ssl_manager: SharedSslManager,
+    ssl_config: &SslConfig,
+    debounce: Duration,
+) -> notify::Result<JoinHandle<()>> {
+    let files = certificate_files(ssl_config)
+        .iter()
+        .map(|path| absolute_file(path))
+        .collect::<std::io::Result<BTreeSet<_>>>()?;
+    let dirs: BTreeSet<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.parent().map(Path::to_path_buf))
+        .collect();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        match event {
+            // Reads, including our own reloads, must not retrigger
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+            Ok(event) if event.paths.iter().any(|path| files.contains(path)) => {
+                debug!("Certificate file change: {:?}", event);
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Certificate watcher error: {}", e),
+        }
+    })?;
+    for dir in &dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        info!("Watching {} for certificate changes", dir.display());
+    }
+
+    Ok(tokio::spawn(async move {
+        // Dropping the watcher would stop event delivery
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            while let Ok(Some(())) = tokio::time::timeout(debounce, rx.recv()).await {}
+
+            info!("Certificate files changed on disk");
+            if let Err(e) = ssl_manager.lock().await.reload_certificates().await {
+                error!("Failed to reload changed certificates: {}", e);
+            }
+        }
+    }))
+}
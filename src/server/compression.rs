@@ -0,0 +1,131 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::{self, Write};
+
+/// gzip levels run from 1 (fastest) to 9 (smallest)
+pub const GZIP_LEVELS: std::ops::RangeInclusive<u32> = 1..=9;
+/// brotli qualities run from 0 (fastest) to 11 (smallest)
+pub const BROTLI_QUALITIES: std::ops::RangeInclusive<u32> = 0..=11;
+
+// Window size brotli uses for HTTP content (the encoder's default)
+const BROTLI_WINDOW_BITS: u32 = 22;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// Token used in `Accept-Encoding` and `Content-Encoding`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Picks the encoding for a request's `Accept-Encoding`, preferring
+    /// brotli at equal weight. `None` means the body goes out as is.
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let mut brotli = None;
+        let mut gzip = None;
+        let mut wildcard = None;
+        for item in accept_encoding.split(',') {
+            let mut params = item.split(';');
+            let token = params.next().unwrap_or_default().trim();
+            let weight = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+            let Some(weight) = weight else {
+                continue;
+            };
+            if token.eq_ignore_ascii_case("br") {
+                brotli = Some(weight);
+            } else if token.eq_ignore_ascii_case("gzip") || token.eq_ignore_ascii_case("x-gzip") {
+                gzip = Some(weight);
+            } else if token == "*" {
+                wildcard = Some(weight);
+            }
+        }
+
+        [
+            (ContentEncoding::Brotli, brotli.or(wildcard)),
+            (ContentEncoding::Gzip, gzip.or(wildcard)),
+        ]
+        .into_iter()
+        .filter_map(|(encoding, weight)| Some((encoding, weight?)))
+        .filter(|(_, weight)| *weight > 0.0)
+        .fold(None, |best: Option<(Self, f32)>, candidate| match best {
+            Some((_, best_weight)) if best_weight >= candidate.1 => best,
+            _ => Some(candidate),
+        })
+        .map(|(encoding, _)| encoding)
+    }
+}
+
+/// Whether a body of `content_type` is worth compressing. Images, archives
+/// and other binary formats are already compressed.
+pub fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json" | "application/javascript" | "application/xml"
+        )
+}
+
+/// Compresses response bodies at the configured levels, trading CPU for size
+#[derive(Debug, Clone, Copy)]
+pub struct Compressor {
+    gzip_level: u32,
+    brotli_quality: u32,
+}
+
+impl Compressor {
+    /// Out-of-range levels are clamped to the nearest valid one
+    pub fn new(gzip_level: u32, brotli_quality: u32) -> Self {
+        Self {
+            gzip_level: gzip_level.clamp(*GZIP_LEVELS.start(), *GZIP_LEVELS.end()),
+            brotli_quality: brotli_quality
+                .clamp(*BROTLI_QUALITIES.start(), *BROTLI_QUALITIES.end()),
+        }
+    }
+
+    pub fn gzip_level(&self) -> u32 {
+        self.gzip_level
+    }
+
+    pub fn brotli_quality(&self) -> u32 {
+        self.brotli_quality
+    }
+
+    pub fn compress(&self, encoding: ContentEncoding, body: &[u8]) -> io::Result<Vec<u8>> {
+        match encoding {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::new(self.gzip_level));
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            ContentEncoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(
+                    Vec::new(),
+                    BROTLI_BUFFER_SIZE,
+                    self.brotli_quality,
+                    BROTLI_WINDOW_BITS,
+                );
+                encoder.write_all(body)?;
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+}
@@ -0,0 +1,796 @@
+use crate::server::proxy_protocol;
+use crate::server::ssl_manager::{ClientCertInfo, MissingSni, NegotiatedAlpn, NegotiatedTls};
+use crate::server::{
+    AcceptAll, AcceptPolicy, Decision, ReadyFile, Router, ServerStats, TlsAcceptor,
+};
+use crate::utils::AppConfig;
+use hyper::Version;
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+use tokio::task::JoinSet;
+use tokio::time::Sleep;
+use tokio_rustls::server::TlsStream;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Error from serving a connection over either HTTP/1.1 or HTTP/2
+pub type ServeError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Per-connection audit record emitted once the connection closes
+#[derive(Debug, Clone)]
+pub struct ConnectionSummary {
+    pub client_ip: IpAddr,
+    pub duration: Duration,
+    pub request_count: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub last_path: Option<String>,
+    pub protocol: Option<Version>,
+    pub clean: bool,
+    /// Whether the client ended its TLS session with `close_notify`; `None`
+    /// for plain connections and ones the server closed first
+    pub close_notify: Option<bool>,
+}
+
+/// Semaphore-backed connection cap that may change at runtime
+#[derive(Debug)]
+struct ConnectionLimit {
+    semaphore: Arc<Semaphore>,
+    max: AtomicUsize,
+    // Permits still owed after the cap was lowered below current usage;
+    // closing connections forget their permit until this is paid off
+    debt: AtomicUsize,
+    // Set while a lowered cap sits below current usage
+    over_cap: AtomicBool,
+    // Publishes permits in use and the cap to /metrics
+    stats: Arc<ServerStats>,
+}
+
+/// Held for the lifetime of an admitted connection; dropping it frees the slot
+#[must_use = "the connection slot is released when the permit drops"]
+pub struct ConnectionPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limit: Arc<ConnectionLimit>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take()
+            && self
+                .limit
+                .debt
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |debt| {
+                    debt.checked_sub(1)
+                })
+                .is_ok()
+        {
+            permit.forget();
+        }
+
+        let active = self
+            .limit
+            .stats
+            .connection_permits_in_use
+            .fetch_sub(1, Ordering::AcqRel)
+            - 1;
+        let max = self.limit.max.load(Ordering::Acquire);
+        if active < max && self.limit.over_cap.swap(false, Ordering::AcqRel) {
+            info!(
+                "Connection usage ({}) back under max_connections ({}); accepting again",
+                active, max
+            );
+        }
+    }
+}
+
+/// Serves HTTP on an already-accepted (and, in production, TLS-wrapped) stream
+pub struct ConnectionHandler {
+    router: Arc<Router>,
+    stats: Arc<ServerStats>,
+    accept_policy: Arc<dyn AcceptPolicy>,
+    header_read_timeout: Duration,
+    write_timeout: Duration,
+    request_timeout: Duration,
+    http2_enabled: bool,
+    http2_max_concurrent_streams: u32,
+    limit: Arc<ConnectionLimit>,
+    wait_for_connection_slot: bool,
+    proxy_protocol: bool,
+    shutdown: CancellationToken,
+    summary_sender: Option<mpsc::Sender<ConnectionSummary>>,
+}
+
+impl ConnectionHandler {
+    pub fn new(router: Arc<Router>, config: &AppConfig) -> Self {
+        let stats = router.stats();
+        let max_connections = config.server.max_connections;
+        stats
+            .max_connections
+            .store(max_connections, Ordering::Release);
+        Self {
+            stats: stats.clone(),
+            router,
+            accept_policy: Arc::new(AcceptAll),
+            header_read_timeout: config.header_read_timeout(),
+            write_timeout: config.write_timeout(),
+            request_timeout: config.request_timeout(),
+            http2_enabled: config.server.http2_enabled,
+            http2_max_concurrent_streams: config.server.http2_max_concurrent_streams,
+            limit: Arc::new(ConnectionLimit {
+                semaphore: Arc::new(Semaphore::new(max_connections)),
+                max: AtomicUsize::new(max_connections),
+                debt: AtomicUsize::new(0),
+                over_cap: AtomicBool::new(false),
+                stats,
+            }),
+            wait_for_connection_slot: config.server.wait_for_connection_slot,
+            proxy_protocol: config.server.proxy_protocol,
+            shutdown: CancellationToken::new(),
+            summary_sender: None,
+        }
+    }
+
+    pub fn set_accept_policy(&mut self, policy: Arc<dyn AcceptPolicy>) {
+        self.accept_policy = policy;
+    }
+
+    pub fn set_summary_sender(&mut self, sender: mpsc::Sender<ConnectionSummary>) {
+        self.summary_sender = Some(sender);
+    }
+
+    /// Cancelling this token asks every connection to finish its in-flight
+    /// requests and close instead of waiting for more
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Changes the connection cap, e.g. on config reload. Lowering it below
+    /// current usage keeps existing connections; new ones are refused until
+    /// enough of them close.
+    pub fn set_max_connections(&self, max: usize) {
+        let previous = self.limit.max.swap(max, Ordering::AcqRel);
+        self.stats.max_connections.store(max, Ordering::Release);
+        if max > previous {
+            // Settle permits still owed from an earlier lowering first
+            let grow = max - previous;
+            let owed = self
+                .limit
+                .debt
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |debt| {
+                    Some(debt.saturating_sub(grow))
+                })
+                .unwrap_or_default();
+            self.limit.semaphore.add_permits(grow - owed.min(grow));
+        } else if max < previous {
+            let shrink = previous - max;
+            let forgotten = self.limit.semaphore.forget_permits(shrink);
+            self.limit
+                .debt
+                .fetch_add(shrink - forgotten, Ordering::AcqRel);
+        }
+
+        let active = self.active_connections();
+        if active > max {
+            self.limit.over_cap.store(true, Ordering::Release);
+            warn!(
+                "max_connections lowered to {} with {} connections in flight; \
+                 refusing new connections until usage drops",
+                max, active
+            );
+        }
+    }
+
+    pub fn active_connections(&self) -> usize {
+        self.stats.connection_permits_in_use.load(Ordering::Acquire)
+    }
+
+    /// Consults the accept policy and connection cap before any TLS work is
+    /// done for `peer`. At the cap this either waits for a slot to free up
+    /// or refuses `peer`, per `server.wait_for_connection_slot`. The returned
+    /// permit must be held until the connection closes.
+    pub async fn admit(&self, peer: SocketAddr) -> Option<ConnectionPermit> {
+        if self.accept_policy.decide(peer) == Decision::Reject {
+            self.stats
+                .rejected_connections
+                .fetch_add(1, Ordering::Relaxed);
+            debug!("Connection from {} rejected by accept policy", peer);
+            return None;
+        }
+
+        let semaphore = self.limit.semaphore.clone();
+        let permit = if self.wait_for_connection_slot {
+            // The semaphore is never closed, so acquiring cannot fail
+            semaphore.acquire_owned().await.ok()?
+        } else {
+            match semaphore.try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    self.stats
+                        .rejected_connections
+                        .fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Connection from {} dropped: max_connections ({}) reached",
+                        peer,
+                        self.limit.max.load(Ordering::Acquire)
+                    );
+                    return None;
+                }
+            }
+        };
+
+        self.stats
+            .connection_permits_in_use
+            .fetch_add(1, Ordering::AcqRel);
+        Some(ConnectionPermit {
+            permit: Some(permit),
+            limit: self.limit.clone(),
+        })
+    }
+
+    /// Finds the address the connection really comes from. With
+    /// `server.proxy_protocol` on, this reads and strips the PROXY protocol
+    /// header the load balancer sends ahead of the TLS handshake, then puts
+    /// the source it names through the accept policy. `None` means the
+    /// connection should be closed.
+    pub async fn resolve_peer<IO>(&self, stream: &mut IO, peer: SocketAddr) -> Option<IpAddr>
+    where
+        IO: AsyncRead + Unpin,
+    {
+        if !self.proxy_protocol {
+            return Some(peer.ip());
+        }
+
+        let header = tokio::time::timeout(
+            self.header_read_timeout,
+            proxy_protocol::read_header(stream),
+        )
+        .await;
+        let source = match header {
+            Ok(Ok(Some(source))) => source,
+            // The proxy's own health checks and the like
+            Ok(Ok(None)) => return Some(peer.ip()),
+            Ok(Err(e)) => {
+                error!("Closing connection from {}: {}", peer, e);
+                return None;
+            }
+            Err(_) => {
+                error!(
+                    "Closing connection from {}: PROXY protocol header timed out",
+                    peer
+                );
+                return None;
+            }
+        };
+
+        if self.accept_policy.decide(source) == Decision::Reject {
+            self.stats
+                .rejected_connections
+                .fetch_add(1, Ordering::Relaxed);
+            debug!(
+                "Connection from {} via {} rejected by accept policy",
+                source, peer
+            );
+            return None;
+        }
+        Some(source.ip())
+    }
+
+    /// Completes the TLS handshake, feeding the outcome to the handshake
+    /// failure watchdog
+    pub async fn accept_tls<IO>(
+        &self,
+        acceptor: &TlsAcceptor,
+        stream: IO,
+        client_ip: IpAddr,
+    ) -> Option<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        match acceptor.accept(stream).await {
+            Ok(tls_stream) => {
+                self.stats.handshake_watchdog.record_success();
+                Some(tls_stream)
+            }
+            Err(e) => {
+                if e.get_ref().is_some_and(|inner| inner.is::<MissingSni>()) {
+                    self.stats.sni_rejections.fetch_add(1, Ordering::Relaxed);
+                }
+                self.stats
+                    .handshake_failures
+                    .fetch_add(1, Ordering::Relaxed);
+                self.stats.handshake_watchdog.record_failure();
+                warn!("TLS handshake failed for {}: {}", client_ip, e);
+                None
+            }
+        }
+    }
+
+    pub async fn serve<I>(&self, io: I, client_ip: IpAddr) -> Result<(), ServeError>
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        self.serve_with_tls_session(io, client_ip, None).await
+    }
+
+    /// Serves a completed TLS connection, exposing any verified client
+    /// certificate, the negotiated ALPN protocol and the TLS version and
+    /// cipher suite to the router as `ClientCertInfo`, `NegotiatedAlpn` and
+    /// `NegotiatedTls` request extensions
+    pub async fn serve_tls<IO>(
+        &self,
+        stream: TlsStream<IO>,
+        client_ip: IpAddr,
+    ) -> Result<(), ServeError>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let conn = stream.get_ref().1;
+        let session = TlsSession {
+            client_cert: ClientCertInfo::from_connection(conn),
+            alpn: NegotiatedAlpn::from_connection(conn),
+            tls: NegotiatedTls::from_connection(conn),
+        };
+        self.serve_with_tls_session(stream, client_ip, Some(session))
+            .await
+    }
+
+    async fn serve_with_tls_session<I>(
+        &self,
+        io: I,
+        client_ip: IpAddr,
+        session: Option<TlsSession>,
+    ) -> Result<(), ServeError>
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let started = Instant::now();
+        let tracker = Arc::new(ConnectionTracker {
+            tls: session.is_some(),
+            ..Default::default()
+        });
+        let TlsSession {
+            client_cert,
+            alpn,
+            tls,
+        } = session.unwrap_or_default();
+        let io = CountingIo {
+            inner: io,
+            tracker: tracker.clone(),
+            write_timeout: self.write_timeout,
+            write_deadline: None,
+        };
+
+        let router = self.router.clone();
+        let request_tracker = tracker.clone();
+        let request_timeout = self.request_timeout;
+
+        // The header timeout closes connections that dribble request headers
+        // (slow-loris) without bounding how long a body may take
+        // Either protocol is detected from the connection preface
+        let mut builder = auto::Builder::new(TokioExecutor::new());
+        if !self.http2_enabled {
+            builder = builder.http1_only();
+        }
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .header_read_timeout(self.header_read_timeout);
+        // Bounds how many requests one h2 connection may have in flight
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .max_concurrent_streams(self.http2_max_concurrent_streams);
+
+        let connection = builder.serve_connection(
+            TokioIo::new(io),
+            service_fn(move |mut req| {
+                request_tracker.record_request(req.uri().path(), req.version());
+                if let Some(client_cert) = &client_cert {
+                    req.extensions_mut().insert(client_cert.clone());
+                }
+                if let Some(alpn) = &alpn {
+                    req.extensions_mut().insert(alpn.clone());
+                }
+                if let Some(tls) = &tls {
+                    req.extensions_mut().insert(tls.clone());
+                }
+                let router = router.clone();
+                let version = req.version();
+                // A stuck handler fails the service rather than being left
+                // to hold its slot: HTTP/1 tears down the connection, h2
+                // resets just that stream
+                async move {
+                    let started = Instant::now();
+                    match tokio::time::timeout(request_timeout, router.route(req, client_ip)).await
+                    {
+                        Ok(response) => response,
+                        Err(_) => {
+                            warn!(
+                                "Request from {} timed out after {:?}; {}",
+                                client_ip,
+                                started.elapsed(),
+                                if version == Version::HTTP_2 {
+                                    "resetting stream"
+                                } else {
+                                    "dropping connection"
+                                }
+                            );
+                            Err(anyhow::anyhow!(
+                                "request exceeded request_timeout ({:?})",
+                                request_timeout
+                            ))
+                        }
+                    }
+                }
+            }),
+        );
+        tokio::pin!(connection);
+        let result = tokio::select! {
+            result = connection.as_mut() => result,
+            _ = self.shutdown.cancelled() => {
+                connection.as_mut().graceful_shutdown();
+                connection.await
+            }
+        };
+
+        if tracker.write_timed_out.load(Ordering::Relaxed) {
+            self.stats.slow_client_drops.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "Dropped slow client {}: no write progress for {:?}",
+                client_ip, self.write_timeout
+            );
+        }
+
+        let summary = tracker.summarize(client_ip, started.elapsed(), result.is_ok());
+        match summary.close_notify {
+            Some(true) => self.stats.clean_close.fetch_add(1, Ordering::Relaxed),
+            Some(false) => self.stats.abrupt_close.fetch_add(1, Ordering::Relaxed),
+            None => 0,
+        };
+        self.router.ttl_controller().connection_closed(client_ip);
+        self.emit_summary(summary);
+        result
+    }
+
+    fn emit_summary(&self, summary: ConnectionSummary) {
+        info!(
+            client_ip = %summary.client_ip,
+            duration_ms = summary.duration.as_millis() as u64,
+            request_count = summary.request_count,
+            bytes_in = summary.bytes_in,
+            bytes_out = summary.bytes_out,
+            last_path = summary.last_path.as_deref().unwrap_or("-"),
+            protocol = ?summary.protocol,
+            clean = summary.clean,
+            close_notify = ?summary.close_notify,
+            "Connection closed"
+        );
+
+        if let Some(sender) = &self.summary_sender
+            && let Err(e) = sender.try_send(summary)
+        {
+            warn!("Dropped connection summary: {}", e);
+        }
+    }
+}
+
+/// Waits up to `grace` for the connection tasks to finish, then aborts any
+/// still running. Returns how many had to be aborted.
+pub async fn drain_connections(connections: &mut JoinSet<()>, grace: Duration) -> usize {
+    let drained = tokio::time::timeout(grace, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_ok() {
+        return 0;
+    }
+
+    let remaining = connections.len();
+    warn!(
+        "Shutdown grace period ({:?}) expired with {} connections still active; closing them",
+        grace, remaining
+    );
+    connections.abort_all();
+    remaining
+}
+
+/// Stops the listeners in `server_tasks` by cancelling `shutdown`, stops
+/// advertising readiness, then lets their in-flight connections finish
+/// within one shared `grace`. Returns how many had to be aborted.
+pub async fn shutdown_listeners(
+    shutdown: &CancellationToken,
+    server_tasks: &mut JoinSet<JoinSet<()>>,
+    ready_file: &ReadyFile,
+    grace: Duration,
+) -> usize {
+    shutdown.cancel();
+    let mut connections = Vec::new();
+    while let Some(listener_connections) = server_tasks.join_next().await {
+        connections.extend(listener_connections.ok());
+    }
+
+    // Withdrawn before draining, so orchestrators stop routing to us while
+    // the last requests finish
+    ready_file.mark_not_ready();
+
+    let active: usize = connections.iter().map(JoinSet::len).sum();
+    let deadline = Instant::now() + grace;
+    let mut aborted = 0;
+    for listener_connections in &mut connections {
+        let grace = deadline.saturating_duration_since(Instant::now());
+        aborted += drain_connections(listener_connections, grace).await;
+    }
+    if aborted == 0 {
+        info!("Drained {} in-flight connections", active);
+    }
+    aborted
+}
+
+/// Stand-in peer address for Unix socket clients, which have no IP. They
+/// share one TTL entry and go through the accept policy as loopback.
+pub const UNIX_SOCKET_PEER: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Accepts TLS connections on `listener` until the handler's shutdown token
+/// is cancelled, then hands back the connections still being served. Every
+/// listener served with the same handler shares its connection cap.
+pub async fn serve_listener(
+    handler: Arc<ConnectionHandler>,
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+) -> JoinSet<()> {
+    let listener = &listener;
+    accept_loop(handler, Some(acceptor), move || listener.accept()).await
+}
+
+/// Like `serve_listener` for a Unix socket, over TLS when given an acceptor
+/// and plaintext otherwise. Clients are tracked as `UNIX_SOCKET_PEER`.
+#[cfg(unix)]
+pub async fn serve_unix_listener(
+    handler: Arc<ConnectionHandler>,
+    listener: UnixListener,
+    acceptor: Option<TlsAcceptor>,
+) -> JoinSet<()> {
+    let listener = &listener;
+    accept_loop(handler, acceptor, move || async move {
+        let (stream, _) = listener.accept().await?;
+        Ok((stream, UNIX_SOCKET_PEER))
+    })
+    .await
+}
+
+async fn accept_loop<S, F, Fut>(
+    handler: Arc<ConnectionHandler>,
+    acceptor: Option<TlsAcceptor>,
+    mut accept: F,
+) -> JoinSet<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<(S, SocketAddr)>>,
+{
+    let shutdown = handler.shutdown_token();
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        // Dropping the stream closes the socket before any TLS work.
+        // Waiting for a slot here stops accepting, leaving further
+        // clients queued in the kernel backlog.
+        let admitted = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            permit = handler.admit(remote_addr) => permit,
+        };
+        let Some(permit) = admitted else {
+            continue;
+        };
+
+        let acceptor = acceptor.clone();
+        let handler = handler.clone();
+
+        connections.spawn(async move {
+            let _permit = permit;
+            let mut stream = stream;
+            let Some(client_ip) = handler.resolve_peer(&mut stream, remote_addr).await else {
+                return;
+            };
+
+            // Handle HTTP requests, after the TLS handshake when there is one
+            let served = match acceptor {
+                Some(acceptor) => {
+                    let Some(tls_stream) = handler.accept_tls(&acceptor, stream, client_ip).await
+                    else {
+                        return;
+                    };
+                    handler.serve_tls(tls_stream, client_ip).await
+                }
+                None => handler.serve(stream, client_ip).await,
+            };
+            if let Err(e) = served {
+                warn!("HTTP connection error for {}: {}", client_ip, e);
+            }
+        });
+
+        // Reap finished connections so the set only holds live ones
+        while connections.try_join_next().is_some() {}
+    }
+    connections
+}
+
+/// What the router learns about a connection from its TLS handshake
+#[derive(Default)]
+struct TlsSession {
+    client_cert: Option<ClientCertInfo>,
+    alpn: Option<NegotiatedAlpn>,
+    tls: Option<NegotiatedTls>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PeerClose {
+    CloseNotify,
+    Abrupt,
+}
+
+#[derive(Default)]
+struct ConnectionTracker {
+    tls: bool,
+    // How the client ended a TLS connection, once it has
+    peer_close: OnceLock<PeerClose>,
+    request_count: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    write_timed_out: AtomicBool,
+    last_request: Mutex<Option<(String, Version)>>,
+}
+
+impl ConnectionTracker {
+    fn record_request(&self, path: &str, version: Version) {
+        self.request_count.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut last_request) = self.last_request.lock() {
+            *last_request = Some((path.to_string(), version));
+        }
+    }
+
+    fn summarize(&self, client_ip: IpAddr, duration: Duration, clean: bool) -> ConnectionSummary {
+        let last_request = self
+            .last_request
+            .lock()
+            .map(|last| last.clone())
+            .unwrap_or_default();
+
+        ConnectionSummary {
+            client_ip,
+            duration,
+            request_count: self.request_count.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            protocol: last_request.as_ref().map(|(_, version)| *version),
+            last_path: last_request.map(|(path, _)| path),
+            clean,
+            close_notify: self
+                .peer_close
+                .get()
+                .map(|close| *close == PeerClose::CloseNotify),
+        }
+    }
+}
+
+/// Counts decrypted bytes flowing through a connection and fails writes that
+/// make no progress within `write_timeout`, so slow readers can't pin buffers
+struct CountingIo<I> {
+    inner: I,
+    tracker: Arc<ConnectionTracker>,
+    write_timeout: Duration,
+    write_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for CountingIo<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        match &poll {
+            Poll::Ready(Ok(())) => {
+                let read = (buf.filled().len() - before) as u64;
+                self.tracker.bytes_in.fetch_add(read, Ordering::Relaxed);
+                // TLS streams only report EOF once close_notify arrived
+                if read == 0 && buf.remaining() > 0 && self.tracker.tls {
+                    let _ = self.tracker.peer_close.set(PeerClose::CloseNotify);
+                }
+            }
+            Poll::Ready(Err(e))
+                if self.tracker.tls
+                    && matches!(
+                        e.kind(),
+                        io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset
+                    ) =>
+            {
+                let _ = self.tracker.peer_close.set(PeerClose::Abrupt);
+            }
+            _ => {}
+        }
+        poll
+    }
+}
+
+impl<I> CountingIo<I> {
+    /// Fails a write, flush or shutdown that has made no progress for
+    /// `write_timeout`. A TLS stream accepts small responses into its buffer
+    /// right away, so a client that stops reading often only stalls the
+    /// flush that follows.
+    fn enforce_write_timeout<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        poll: Poll<io::Result<T>>,
+    ) -> Poll<io::Result<T>> {
+        if poll.is_ready() {
+            self.write_deadline = None;
+            return poll;
+        }
+
+        let write_timeout = self.write_timeout;
+        let deadline = self
+            .write_deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(write_timeout)));
+        if deadline.as_mut().poll(cx).is_ready() {
+            self.write_deadline = None;
+            self.tracker.write_timed_out.store(true, Ordering::Relaxed);
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "client is not reading the response",
+            )));
+        }
+        Poll::Pending
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for CountingIo<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = &poll {
+            self.tracker
+                .bytes_out
+                .fetch_add(*written as u64, Ordering::Relaxed);
+        }
+        self.enforce_write_timeout(cx, poll)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_flush(cx);
+        self.enforce_write_timeout(cx, poll)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_shutdown(cx);
+        self.enforce_write_timeout(cx, poll)
+    }
+}
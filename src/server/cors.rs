@@ -0,0 +1,113 @@
+use crate::server::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::utils::config::CorsConfig;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS,
+    ACCESS_CONTROL_REQUEST_METHOD, HeaderMap, HeaderValue, ORIGIN, VARY,
+};
+use hyper::{Method, Request, Response, StatusCode};
+use tracing::debug;
+
+/// Adds `Access-Control-Allow-*` headers for allowed origins and answers
+/// their preflight requests with `204 No Content`. Requests from other
+/// origins pass through untouched, so browsers block them.
+#[derive(Debug, Clone)]
+pub struct CorsMiddleware {
+    any_origin: bool,
+    origins: Vec<String>,
+    methods: HeaderValue,
+    allow_credentials: bool,
+    max_age: HeaderValue,
+}
+
+impl CorsMiddleware {
+    pub fn new(config: &CorsConfig) -> Self {
+        let methods = config
+            .allowed_methods
+            .iter()
+            .map(|method| method.trim().to_ascii_uppercase())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            any_origin: config.allowed_origins.iter().any(|origin| origin == "*"),
+            origins: config
+                .allowed_origins
+                .iter()
+                .map(|origin| origin.trim_end_matches('/').to_string())
+                .collect(),
+            methods: HeaderValue::from_str(&methods).unwrap_or(HeaderValue::from_static("GET")),
+            allow_credentials: config.allow_credentials,
+            max_age: HeaderValue::from(config.max_age_secs),
+        }
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        self.any_origin
+            || self
+                .origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Headers every response to an allowed origin carries. Credentialed
+    /// requests may not see `*`, so the origin is echoed for them.
+    fn apply(&self, headers: &mut HeaderMap, origin: &HeaderValue) {
+        if self.any_origin && !self.allow_credentials {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        } else {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+        }
+        if self.allow_credentials {
+            headers.insert(
+                ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+impl Middleware for CorsMiddleware {
+    fn handle<'a>(&'a self, req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a> {
+        let origin = req
+            .headers()
+            .get(ORIGIN)
+            .filter(|origin| origin.to_str().is_ok_and(|origin| self.allows(origin)))
+            .cloned();
+        let Some(origin) = origin else {
+            return next.run(req);
+        };
+
+        let preflight = req.method() == Method::OPTIONS
+            && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+        if preflight {
+            debug!("CORS preflight for {} from {:?}", req.uri().path(), origin);
+            let request_headers = req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS).cloned();
+            return Box::pin(async move {
+                let mut response = Response::builder()
+                    .status(StatusCode::NO_CONTENT)
+                    .body(Full::new(Bytes::new()).boxed())?;
+                let headers = response.headers_mut();
+                self.apply(headers, &origin);
+                headers.insert(ACCESS_CONTROL_ALLOW_METHODS, self.methods.clone());
+                headers.insert(ACCESS_CONTROL_MAX_AGE, self.max_age.clone());
+                if let Some(request_headers) = request_headers {
+                    headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, request_headers);
+                    headers.append(
+                        VARY,
+                        HeaderValue::from_static("Access-Control-Request-Headers"),
+                    );
+                }
+                Ok(response)
+            });
+        }
+
+        Box::pin(async move {
+            let mut response = next.run(req).await?;
+            self.apply(response.headers_mut(), &origin);
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use crate::utils::cidr::IpCidr;
+use hyper::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+pub const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Resolves the client behind `peer_ip`. Only a peer in `trusted_proxies`
+/// may vouch for another address, so anyone else forging
+/// `X-Forwarded-For` is still seen as themselves. The header is read right
+/// to left, skipping trusted proxies, and the first other hop is the
+/// client; a malformed hop stops the walk at the last hop vouched for.
+pub fn client_ip(peer_ip: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpCidr]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|cidr| cidr.contains(ip));
+    if !is_trusted(peer_ip) {
+        return peer_ip;
+    }
+
+    // Repeated headers form one list, in order
+    let hops: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .collect();
+
+    let mut client = peer_ip;
+    for hop in hops.into_iter().rev() {
+        let Some(ip) = parse_hop(hop) else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// Accepts a bare address or one with a port (`203.0.113.7:4711`,
+/// `[2001:db8::7]:4711`)
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
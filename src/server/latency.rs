@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Default upper bucket bounds, in milliseconds
+pub const DEFAULT_BUCKETS_MS: [f64; 12] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// Request latencies counted into fixed buckets, cheap enough to record on
+/// every request without a lock
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    bounds: Vec<Duration>,
+    // One count per bound, then one for everything above the last bound
+    counts: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+}
+
+/// Point-in-time copy of a histogram's buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySnapshot {
+    /// Upper bucket bounds, ascending
+    pub bounds: Vec<Duration>,
+    /// Per-bucket counts; the final entry counts samples above every bound
+    pub counts: Vec<u64>,
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    /// `bounds_ms` must be ascending; see `AppConfig::validate`
+    pub fn new(bounds_ms: &[f64]) -> Self {
+        let bounds: Vec<Duration> = bounds_ms
+            .iter()
+            .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+            .collect();
+        Self {
+            counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            bounds,
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, latency: Duration) {
+        let bucket = self.bounds.partition_point(|bound| *bound < latency);
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LatencySnapshot {
+        LatencySnapshot {
+            bounds: self.bounds.clone(),
+            counts: self
+                .counts
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            sum: Duration::from_micros(self.sum_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl LatencySnapshot {
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Estimates the `quantile` (0.0..=1.0) by interpolating within the
+    /// bucket it falls in. Samples above the last bound report that bound.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = (quantile.clamp(0.0, 1.0) * count as f64).max(1.0);
+        let mut seen = 0;
+        for (bucket, &in_bucket) in self.counts.iter().enumerate() {
+            if in_bucket == 0 || ((seen + in_bucket) as f64) < rank {
+                seen += in_bucket;
+                continue;
+            }
+
+            let Some(&upper) = self.bounds.get(bucket) else {
+                return self.bounds.last().copied().or(Some(Duration::ZERO));
+            };
+            let lower = bucket
+                .checked_sub(1)
+                .map_or(Duration::ZERO, |below| self.bounds[below]);
+            let fraction = (rank - seen as f64) / in_bucket as f64;
+            return Some(lower + (upper - lower).mul_f64(fraction));
+        }
+        self.bounds.last().copied()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let ms = |quantile| {
+            self.percentile(quantile)
+                .map(|latency| latency.as_secs_f64() * 1000.0)
+        };
+        serde_json::json!({
+            "count": self.count(),
+            "sum_seconds": self.sum.as_secs_f64(),
+            "p50_ms": ms(0.5),
+            "p95_ms": ms(0.95),
+            "p99_ms": ms(0.99),
+        })
+    }
+}
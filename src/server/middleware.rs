@@ -0,0 +1,123 @@
+use crate::utils::http::ResponseBody;
+use anyhow::Result;
+use hyper::body::Incoming;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{Request, Response};
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longer inbound ids are replaced rather than echoed
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Future returned by a middleware or the rest of the chain
+pub type MiddlewareFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response<ResponseBody>>> + Send + 'a>>;
+
+/// The client a request is attributed to, after `X-Forwarded-For`
+/// resolution. Set as a request extension for every middleware after the
+/// router's connection tracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Address that actually connected, set by `Router::route`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PeerIp(pub IpAddr);
+
+/// Cross-cutting request handling wrapped around every route. Middlewares
+/// run in the order they were added; each decides whether to call `next`,
+/// and may alter the request before and the response after.
+pub trait Middleware: Send + Sync {
+    fn handle<'a>(&'a self, req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a>;
+}
+
+type Endpoint<'a> = dyn Fn(Request<Incoming>) -> MiddlewareFuture<'a> + Send + Sync + 'a;
+
+/// The middlewares still to run, then the matched route
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    endpoint: &'a Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middlewares: &'a [Arc<dyn Middleware>], endpoint: &'a Endpoint<'a>) -> Self {
+        Self {
+            middlewares,
+            endpoint,
+        }
+    }
+
+    pub fn run(self, req: Request<Incoming>) -> MiddlewareFuture<'a> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => middleware.handle(req, Next::new(rest, self.endpoint)),
+            None => (self.endpoint)(req),
+        }
+    }
+}
+
+/// The `X-Request-Id` the client or a proxy sent, or a fresh UUID when it
+/// is missing or unreasonably long
+pub fn request_id<B>(req: &Request<B>) -> HeaderValue {
+    req.headers()
+        .get(X_REQUEST_ID)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("a UUID is a valid header value")
+        })
+}
+
+/// Tags each request with an `X-Request-Id`, keeping one the client or a
+/// proxy already sent, and echoes it on the response. `Router::route`
+/// already does this, so this is only needed in front of other services.
+#[derive(Debug, Default)]
+pub struct RequestIdMiddleware;
+
+impl Middleware for RequestIdMiddleware {
+    fn handle<'a>(&'a self, mut req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a> {
+        let request_id = request_id(&req);
+        req.headers_mut().insert(X_REQUEST_ID, request_id.clone());
+
+        Box::pin(async move {
+            let mut response = next.run(req).await?;
+            response.headers_mut().insert(X_REQUEST_ID, request_id);
+            Ok(response)
+        })
+    }
+}
+
+/// Logs one line per completed request with its status and latency
+#[derive(Debug, Default)]
+pub struct AccessLogMiddleware;
+
+impl Middleware for AccessLogMiddleware {
+    fn handle<'a>(&'a self, req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a> {
+        let started = Instant::now();
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let client = req
+            .extensions()
+            .get::<ClientIp>()
+            .map_or_else(|| "-".to_string(), |ClientIp(ip)| ip.to_string());
+
+        Box::pin(async move {
+            let response = next.run(req).await?;
+            info!(
+                target: "access",
+                "{} \"{} {}\" {} {:.3}ms",
+                client,
+                method,
+                path,
+                response.status().as_u16(),
+                started.elapsed().as_secs_f64() * 1000.0
+            );
+            Ok(response)
+        })
+    }
+}
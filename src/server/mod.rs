@@ -1,7 +1,65 @@
+pub mod accept_policy;
+pub mod access_control;
+pub mod access_log;
+#[cfg(feature = "acme")]
+pub mod acme;
+#[cfg(feature = "audit-sqlite")]
+pub mod audit;
+pub mod auth;
+pub mod cert_watcher;
+pub mod compression;
+pub mod connection;
+pub mod cors;
+pub mod forwarded;
+pub mod latency;
+pub mod middleware;
+pub mod path_params;
+pub mod prometheus;
+pub mod proxy_protocol;
+pub mod rate_limit;
+pub mod readiness;
+pub mod redirect;
+pub mod reload;
+pub mod route_table;
 pub mod router;
+pub mod snapshot;
+pub mod sni;
 pub mod ssl_manager;
+pub mod stats;
 pub mod ttl_controller;
+pub mod ttl_strategy;
+pub mod watchdog;
 
-pub use router::Router;
-pub use ssl_manager::SslManager;
-pub use ttl_controller::TtlController;
+pub use accept_policy::{AcceptAll, AcceptPolicy, Decision};
+pub use access_control::AccessControl;
+pub use access_log::{AccessLog, AccessLogEntry};
+pub use cert_watcher::spawn_certificate_watcher;
+pub use compression::{Compressor, ContentEncoding};
+#[cfg(unix)]
+pub use connection::serve_unix_listener;
+pub use connection::{
+    ConnectionHandler, UNIX_SOCKET_PEER, drain_connections, serve_listener, shutdown_listeners,
+};
+pub use cors::CorsMiddleware;
+pub use latency::{LatencyHistogram, LatencySnapshot};
+pub use middleware::{
+    AccessLogMiddleware, ClientIp, Middleware, MiddlewareFuture, Next, RequestIdMiddleware,
+};
+pub use path_params::{PathParams, match_path};
+pub use rate_limit::{RateLimitDecision, RateLimiter};
+pub use readiness::ReadyFile;
+pub use redirect::serve_https_redirect;
+#[cfg(unix)]
+pub use reload::spawn_sighup_reloader;
+pub use reload::{ConfigReloader, ReloadOutcome, SharedAppConfig};
+pub use route_table::{RouteTable, RouteTableError};
+pub use router::{RouteFuture, RouteHandler, Router};
+pub use snapshot::{ConnectionSnapshot, StatsSnapshot, spawn_snapshot_task};
+pub use sni::SniResolver;
+pub use ssl_manager::{
+    SharedCertificateInfo, SharedSslManager, SslManager, TlsAcceptor, spawn_certificate_monitor,
+};
+pub use stats::{ServerStats, StartTime, StartupTimings};
+pub use ttl_controller::{ConnectionEvent, ConnectionEventKind, TtlController, TtlLimits};
+pub use ttl_strategy::{AdaptiveTtlStrategy, TtlContext, TtlStrategy};
+pub use watchdog::HandshakeWatchdog;
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PathParamError {
+    #[error("missing path parameter '{0}'")]
+    Missing(String),
+    #[error("invalid path parameter '{name}': '{value}'")]
+    Invalid { name: String, value: String },
+}
+
+/// Named segments captured from a request path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams {
+    params: HashMap<String, String>,
+}
+
+impl PathParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Parses a captured segment, e.g. `params.parse::<IpAddr>("ip")`
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<T, PathParamError> {
+        let value = self
+            .get(name)
+            .ok_or_else(|| PathParamError::Missing(name.to_string()))?;
+
+        value.parse().map_err(|_| PathParamError::Invalid {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Whether `pattern` captures anything, i.e. has `:name` or `*name` segments
+pub fn is_pattern(pattern: &str) -> bool {
+    pattern
+        .split('/')
+        .any(|segment| segment.starts_with(':') || segment.starts_with('*'))
+}
+
+/// Matches `path` against a pattern such as `/connections/:ip`. Segments
+/// starting with `:` capture exactly one non-empty path segment; a final
+/// `*name` segment captures the non-empty rest of the path, slashes included.
+pub fn match_path(pattern: &str, path: &str) -> Option<PathParams> {
+    let mut pattern_segments = pattern.split('/');
+    let mut path_segments = path.split('/');
+    let mut params = HashMap::new();
+
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (None, None) => return Some(PathParams { params }),
+            (Some(expected), Some(actual)) => {
+                if let Some(name) = expected.strip_prefix('*') {
+                    let rest = std::iter::once(actual)
+                        .chain(path_segments)
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    if rest.is_empty() || pattern_segments.next().is_some() {
+                        return None;
+                    }
+                    params.insert(name.to_string(), rest);
+                    return Some(PathParams { params });
+                } else if let Some(name) = expected.strip_prefix(':') {
+                    if actual.is_empty() {
+                        return None;
+                    }
+                    params.insert(name.to_string(), actual.to_string());
+                } else if expected != actual {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::server::latency::LatencySnapshot;
+use crate::server::stats::StartTime;
+use crate::server::ttl_controller::TtlStats;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Builds a Prometheus text exposition document one metric family at a time
+#[derive(Debug, Default)]
+pub struct PrometheusText {
+    out: String,
+}
+
+impl PrometheusText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) -> &mut Self {
+        self.family(name, help, "gauge");
+        let _ = writeln!(self.out, "{name} {value}");
+        self
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) -> &mut Self {
+        self.family(name, help, "counter");
+        let _ = writeln!(self.out, "{name} {value}");
+        self
+    }
+
+    /// One gauge sample per `(label value, value)` pair
+    pub fn labeled_gauges(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        samples: &[(String, f64)],
+    ) -> &mut Self {
+        self.family(name, help, "gauge");
+        for (label_value, value) in samples {
+            let _ = writeln!(self.out, "{name}{{{label}=\"{label_value}\"}} {value}");
+        }
+        self
+    }
+
+    /// Cumulative buckets in seconds, as `histogram_quantile` expects
+    pub fn histogram(&mut self, name: &str, help: &str, snapshot: &LatencySnapshot) -> &mut Self {
+        self.family(name, help, "histogram");
+        let mut cumulative = 0;
+        for (bound, count) in snapshot.bounds.iter().zip(&snapshot.counts) {
+            cumulative += count;
+            let _ = writeln!(
+                self.out,
+                "{name}_bucket{{le=\"{}\"}} {cumulative}",
+                bound.as_secs_f64()
+            );
+        }
+        let _ = writeln!(
+            self.out,
+            "{name}_bucket{{le=\"+Inf\"}} {}",
+            snapshot.count()
+        );
+        let _ = writeln!(self.out, "{name}_sum {}", snapshot.sum.as_secs_f64());
+        let _ = writeln!(self.out, "{name}_count {}", snapshot.count());
+        self
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// Request latency percentiles published as gauges
+const QUANTILES: [f64; 3] = [0.5, 0.95, 0.99];
+
+/// Renders the connection tracking stats and request latencies, plus when
+/// the server started and how long gathering them took
+pub fn render(
+    stats: &TtlStats,
+    latency: &LatencySnapshot,
+    start_time: &StartTime,
+    scrape_duration: Duration,
+) -> String {
+    let percentiles: Vec<(String, f64)> = QUANTILES
+        .iter()
+        .filter_map(|&quantile| {
+            let value = latency.percentile(quantile)?;
+            Some((quantile.to_string(), value.as_secs_f64()))
+        })
+        .collect();
+
+    let mut text = PrometheusText::new();
+    text.gauge(
+        "rusty_ssl_active_connections",
+        "Connections currently tracked by the TTL controller",
+        stats.active_connections as f64,
+    )
+    .counter(
+        "rusty_ssl_total_connections",
+        "Connections registered since startup",
+        stats.total_connections,
+    )
+    .counter(
+        "rusty_ssl_expired_connections",
+        "Connections removed after their TTL lapsed",
+        stats.expired_connections,
+    )
+    .gauge(
+        "rusty_ssl_average_ttl_seconds",
+        "Mean TTL across tracked connections",
+        stats.average_ttl_secs as f64,
+    )
+    .histogram(
+        "rusty_ssl_request_duration_seconds",
+        "Time from a request reaching the router to its response",
+        latency,
+    )
+    .labeled_gauges(
+        "rusty_ssl_request_duration_percentile_seconds",
+        "Estimated request latency percentiles",
+        "quantile",
+        &percentiles,
+    )
+    .gauge(
+        "process_start_time_seconds",
+        "Start time of the process since unix epoch in seconds",
+        start_time.unix_seconds(),
+    )
+    .gauge(
+        "rusty_ssl_scrape_duration_seconds",
+        "Time spent gathering this document",
+        scrape_duration.as_secs_f64(),
+    );
+    text.finish()
+}
@@ -0,0 +1,157 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Opening bytes of every binary (v2) header
+pub const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+// A v1 line, CRLF included, is at most 107 bytes
+const V1_MAX_LEN: usize = 107;
+
+#[derive(Error, Debug)]
+pub enum ProxyProtocolError {
+    #[error("failed to read PROXY protocol header: {0}")]
+    Io(#[from] io::Error),
+    #[error("connection did not start with a PROXY protocol header")]
+    MissingHeader,
+    #[error("malformed PROXY protocol header: {0}")]
+    Malformed(String),
+}
+
+/// Reads and strips a v1 (text) or v2 (binary) PROXY protocol header,
+/// leaving the stream positioned at the first byte the client sent.
+/// Returns the original source address, or `None` when the proxy sent the
+/// connection on its own behalf (v1 `UNKNOWN`, v2 `LOCAL` or a non-IP
+/// family).
+pub async fn read_header<S>(stream: &mut S) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    // Shorter than either header, so this never eats into the payload
+    let mut prefix = [0u8; V2_SIGNATURE.len()];
+    stream.read_exact(&mut prefix).await?;
+
+    if prefix == V2_SIGNATURE {
+        read_v2(stream).await
+    } else if prefix.starts_with(b"PROXY ") {
+        read_v1(stream, &prefix).await
+    } else {
+        Err(ProxyProtocolError::MissingHeader)
+    }
+}
+
+async fn read_v1<S>(stream: &mut S, prefix: &[u8]) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    // Byte at a time: anything past the CRLF belongs to the TLS handshake
+    let mut line = prefix.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LEN {
+            return Err(ProxyProtocolError::Malformed(
+                "v1 header exceeds 107 bytes".to_string(),
+            ));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .map_err(|_| ProxyProtocolError::Malformed("v1 header is not ASCII".to_string()))?;
+    parse_v1(line)
+}
+
+/// Parses a v1 line without its CRLF, e.g.
+/// `PROXY TCP4 203.0.113.7 192.0.2.1 56324 443`
+pub fn parse_v1(line: &str) -> Result<Option<SocketAddr>, ProxyProtocolError> {
+    let malformed = || ProxyProtocolError::Malformed(format!("invalid v1 header '{line}'"));
+    let mut fields = line.split(' ');
+    if fields.next() != Some("PROXY") {
+        return Err(malformed());
+    }
+
+    let family = fields.next().ok_or_else(malformed)?;
+    if family == "UNKNOWN" {
+        return Ok(None);
+    }
+
+    let fields: Vec<&str> = fields.collect();
+    let [source, destination, source_port, destination_port] = fields[..] else {
+        return Err(malformed());
+    };
+    let source: IpAddr = source.parse().map_err(|_| malformed())?;
+    let destination: IpAddr = destination.parse().map_err(|_| malformed())?;
+    let source_port: u16 = source_port.parse().map_err(|_| malformed())?;
+    destination_port.parse::<u16>().map_err(|_| malformed())?;
+
+    let family_matches = match family {
+        "TCP4" => source.is_ipv4() && destination.is_ipv4(),
+        "TCP6" => source.is_ipv6() && destination.is_ipv6(),
+        _ => false,
+    };
+    if !family_matches {
+        return Err(malformed());
+    }
+
+    Ok(Some(SocketAddr::new(source, source_port)))
+}
+
+async fn read_v2<S>(stream: &mut S) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut fixed = [0u8; 4];
+    stream.read_exact(&mut fixed).await?;
+    let [version_command, family, len_high, len_low] = fixed;
+
+    if version_command >> 4 != 2 {
+        return Err(ProxyProtocolError::Malformed(format!(
+            "unsupported version {}",
+            version_command >> 4
+        )));
+    }
+
+    // Read the whole address block even when it goes unused
+    let mut addresses = vec![0u8; u16::from_be_bytes([len_high, len_low]) as usize];
+    stream.read_exact(&mut addresses).await?;
+
+    match version_command & 0x0f {
+        0x0 => return Ok(None),
+        0x1 => {}
+        command => {
+            return Err(ProxyProtocolError::Malformed(format!(
+                "unsupported command {command}"
+            )));
+        }
+    }
+
+    let too_short = || ProxyProtocolError::Malformed("v2 address block too short".to_string());
+    match family >> 4 {
+        // AF_INET: source, destination, source port, destination port
+        0x1 => {
+            let block: [u8; 12] = addresses
+                .get(..12)
+                .and_then(|block| block.try_into().ok())
+                .ok_or_else(too_short)?;
+            let source = Ipv4Addr::new(block[0], block[1], block[2], block[3]);
+            let port = u16::from_be_bytes([block[8], block[9]]);
+            Ok(Some(SocketAddr::new(source.into(), port)))
+        }
+        // AF_INET6
+        0x2 => {
+            let block: [u8; 36] = addresses
+                .get(..36)
+                .and_then(|block| block.try_into().ok())
+                .ok_or_else(too_short)?;
+            let mut source = [0u8; 16];
+            source.copy_from_slice(&block[..16]);
+            let port = u16::from_be_bytes([block[32], block[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(source).into(), port)))
+        }
+        // AF_UNSPEC and AF_UNIX carry no client IP
+        0x0 | 0x3 => Ok(None),
+        other => Err(ProxyProtocolError::Malformed(format!(
+            "unknown address family {other}"
+        ))),
+    }
+}
@@ -0,0 +1,108 @@
+use arc_swap::ArcSwap;
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Outcome of charging one request against a client's bucket, with the
+/// figures advertised in `RateLimit-*` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_secs: u64,
+    /// Seconds until the next request would be allowed; 0 when allowed
+    pub retry_after_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    requests_per_sec: f64,
+    burst: u32,
+}
+
+/// Per-IP token buckets holding up to `burst` requests, refilled at
+/// `requests_per_sec`
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: ArcSwap<Limits>,
+    buckets: DashMap<IpAddr, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_sec: f64, burst: u32) -> Self {
+        Self {
+            limits: ArcSwap::from_pointee(Limits::new(requests_per_sec, burst)),
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Changes the limits for every client, keeping the tokens they hold
+    /// (capped to the new burst)
+    pub fn set_limits(&self, requests_per_sec: f64, burst: u32) {
+        self.limits
+            .store(Arc::new(Limits::new(requests_per_sec, burst)));
+    }
+
+    pub fn check(&self, ip: IpAddr) -> RateLimitDecision {
+        let now = Instant::now();
+        let limits = **self.limits.load();
+        let capacity = f64::from(limits.burst);
+        let mut bucket = self.buckets.entry(ip).or_insert(TokenBucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limits.requests_per_sec).min(capacity);
+        bucket.refilled_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        RateLimitDecision {
+            allowed,
+            limit: limits.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: limits.secs_to_refill(capacity - bucket.tokens),
+            retry_after_secs: if allowed {
+                0
+            } else {
+                limits.secs_to_refill(1.0 - bucket.tokens)
+            },
+        }
+    }
+
+    /// Drops the client's bucket; its next request starts with a full one
+    pub fn forget(&self, ip: IpAddr) {
+        self.buckets.remove(&ip);
+    }
+
+    /// Clients currently holding a bucket
+    pub fn tracked_clients(&self) -> usize {
+        self.buckets.len()
+    }
+}
+
+impl Limits {
+    fn new(requests_per_sec: f64, burst: u32) -> Self {
+        Self {
+            requests_per_sec,
+            burst: burst.max(1),
+        }
+    }
+
+    fn secs_to_refill(&self, tokens: f64) -> u64 {
+        (tokens.max(0.0) / self.requests_per_sec).ceil() as u64
+    }
+}
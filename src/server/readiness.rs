@@ -0,0 +1,45 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Mirrors readiness into a marker file for orchestrators that poll the filesystem
+#[derive(Debug, Clone, Default)]
+pub struct ReadyFile {
+    path: Option<PathBuf>,
+}
+
+impl ReadyFile {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    pub fn mark_ready(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let contents = format!("pid={}\nready_since={}\n", std::process::id(), timestamp);
+
+        match std::fs::write(path, contents) {
+            Ok(()) => debug!("Wrote ready file: {}", path.display()),
+            Err(e) => warn!("Failed to write ready file {}: {}", path.display(), e),
+        }
+    }
+
+    pub fn mark_not_ready(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        match std::fs::remove_file(path) {
+            Ok(()) => debug!("Removed ready file: {}", path.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove ready file {}: {}", path.display(), e),
+        }
+    }
+}
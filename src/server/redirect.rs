@@ -0,0 +1,94 @@
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{HOST, LOCATION};
+use hyper::http::uri::Authority;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+/// Answers plaintext HTTP/1.1 on `listener` with a 301 to the same host and
+/// path over HTTPS on `https_port`, until `shutdown` is cancelled. Hands back
+/// the connections still being served, like `serve_listener`.
+pub async fn serve_https_redirect(
+    listener: TcpListener,
+    https_port: u16,
+    shutdown: CancellationToken,
+) -> JoinSet<()> {
+    let mut connections = JoinSet::new();
+    loop {
+        let stream = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    error!("Failed to accept redirect connection: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        connections.spawn(async move {
+            let service = service_fn(move |req| async move {
+                Ok::<_, Infallible>(redirect_response(&req, https_port))
+            });
+            // One response per connection, so shutdown never waits on idle clients
+            if let Err(e) = http1::Builder::new()
+                .keep_alive(false)
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Redirect connection error: {}", e);
+            }
+        });
+
+        // Reap finished connections so the set only holds live ones
+        while connections.try_join_next().is_some() {}
+    }
+    connections
+}
+
+pub fn redirect_response<B>(req: &Request<B>, https_port: u16) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::new()));
+    match https_location(req, https_port) {
+        Some(location) => {
+            *response.status_mut() = StatusCode::MOVED_PERMANENTLY;
+            if let Ok(location) = location.parse() {
+                response.headers_mut().insert(LOCATION, location);
+            }
+        }
+        // Without a host there is nothing to redirect to
+        None => *response.status_mut() = StatusCode::BAD_REQUEST,
+    }
+    response
+}
+
+/// The `https://` URL for `req`, keeping its host, path and query. The port
+/// is left out when it's the HTTPS default.
+fn https_location<B>(req: &Request<B>, https_port: u16) -> Option<String> {
+    let authority = match req.uri().authority() {
+        Some(authority) => authority.clone(),
+        None => req
+            .headers()
+            .get(HOST)?
+            .to_str()
+            .ok()?
+            .parse::<Authority>()
+            .ok()?,
+    };
+    let host = authority.host();
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+
+    Some(match https_port {
+        443 => format!("https://{host}{path}"),
+        port => format!("https://{host}:{port}{path}"),
+    })
+}
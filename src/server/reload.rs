@@ -0,0 +1,199 @@
+use crate::server::ConnectionHandler;
+use crate::server::ttl_controller::{TtlController, TtlLimits};
+use crate::utils::AppConfig;
+use crate::utils::config::ConfigValidationError;
+use crate::utils::logger::LogLevelHandle;
+use arc_swap::ArcSwap;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// The config the server is running with, swapped whole on reload
+pub type SharedAppConfig = Arc<ArcSwap<AppConfig>>;
+
+/// What a reload changed, as dotted setting names like `ttl.max_ttl_secs`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadOutcome {
+    pub applied: Vec<String>,
+    // Changed in the new config but left at their running values
+    pub requires_restart: Vec<String>,
+}
+
+/// Applies a freshly loaded config to a running server. TTL bounds, the
+/// cleanup interval, the log level, rate limits and the connection cap
+/// change in place, without touching open connections; any other change is
+/// reported as needing a restart and ignored.
+pub struct ConfigReloader {
+    current: SharedAppConfig,
+    ttl_controller: Arc<TtlController>,
+    log_level: Option<LogLevelHandle>,
+    connection_handler: Option<Arc<ConnectionHandler>>,
+}
+
+impl ConfigReloader {
+    pub fn new(config: AppConfig, ttl_controller: Arc<TtlController>) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(config)),
+            ttl_controller,
+            log_level: None,
+            connection_handler: None,
+        }
+    }
+
+    /// Without a handle, log level changes need a restart
+    pub fn set_log_level_handle(&mut self, handle: LogLevelHandle) {
+        self.log_level = Some(handle);
+    }
+
+    /// Without a handler, `server.max_connections` changes need a restart
+    pub fn set_connection_handler(&mut self, handler: Arc<ConnectionHandler>) {
+        self.connection_handler = Some(handler);
+    }
+
+    /// Publishes reloaded settings through `shared`, e.g. the router's
+    /// `config_handle()`, so its readers see them on their next load
+    pub fn set_shared_config(&mut self, shared: SharedAppConfig) {
+        shared.store(self.current.load_full());
+        self.current = shared;
+    }
+
+    /// The config as last applied: the startup one with reloaded settings
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.current.load_full()
+    }
+
+    /// Validates `new` and applies what can change live. An invalid config
+    /// is rejected whole, leaving the running one in place.
+    pub fn apply(&self, new: AppConfig) -> Result<ReloadOutcome, Vec<ConfigValidationError>> {
+        new.validate()?;
+
+        let old = self.current.load_full();
+        let mut running = (*old).clone();
+        running.ttl.default_ttl_secs = new.ttl.default_ttl_secs;
+        running.ttl.max_ttl_secs = new.ttl.max_ttl_secs;
+        running.ttl.cleanup_interval_secs = new.ttl.cleanup_interval_secs;
+        self.ttl_controller.set_limits(TtlLimits {
+            default_ttl: running.default_ttl(),
+            max_ttl: running.max_ttl(),
+            cleanup_interval: running.cleanup_interval(),
+        });
+
+        if let Some(log_level) = &self.log_level
+            && new.logging.level != old.logging.level
+        {
+            match log_level.set_level(&new.logging.level) {
+                Ok(()) => running.logging.level = new.logging.level.clone(),
+                Err(e) => warn!("Keeping log level {}: {}", old.logging.level, e),
+            }
+        }
+
+        // The limiter only exists when rate limiting was on at startup, and
+        // turning it off would leave it attached
+        if let Some(rate_limiter) = self.ttl_controller.rate_limiter()
+            && new.rate_limit.requests_per_sec > 0.0
+        {
+            rate_limiter.set_limits(new.rate_limit.requests_per_sec, new.rate_limit.burst);
+            running.rate_limit = new.rate_limit.clone();
+        }
+
+        // Lowering the cap keeps connections already open, but evicts
+        // tracked ones over it as new IPs register
+        if let Some(handler) = &self.connection_handler
+            && new.server.max_connections != old.server.max_connections
+        {
+            handler.set_max_connections(new.server.max_connections);
+            self.ttl_controller
+                .set_max_connections(new.server.max_connections);
+            running.server.max_connections = new.server.max_connections;
+        }
+
+        let outcome = ReloadOutcome {
+            applied: changed_settings(&old, &running),
+            requires_restart: changed_settings(&running, &new),
+        };
+        for setting in &outcome.applied {
+            info!("Reloaded {}", setting);
+        }
+        for setting in &outcome.requires_restart {
+            warn!(
+                "{} changed but requires restart; keeping the running value",
+                setting
+            );
+        }
+        self.current.store(Arc::new(running));
+        Ok(outcome)
+    }
+}
+
+/// Re-runs `load` and applies the result on every SIGHUP. Load and
+/// validation failures are logged and leave the running config alone.
+#[cfg(unix)]
+pub fn spawn_sighup_reloader<F>(
+    reloader: ConfigReloader,
+    load: F,
+) -> std::io::Result<tokio::task::JoinHandle<()>>
+where
+    F: Fn() -> Result<AppConfig, config::ConfigError> + Send + 'static,
+{
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("SIGHUP received; reloading configuration");
+            let config = match load() {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("Failed to reload configuration: {}", e);
+                    continue;
+                }
+            };
+            match reloader.apply(config) {
+                Ok(outcome) if outcome.applied.is_empty() => {
+                    info!("Configuration reloaded; nothing to apply")
+                }
+                Ok(_) => {}
+                Err(errors) => {
+                    for error in &errors {
+                        error!("Invalid configuration, not reloaded: {}", error);
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Dotted names of the settings whose values differ between `a` and `b`
+fn changed_settings(a: &AppConfig, b: &AppConfig) -> Vec<String> {
+    let mut changed = Vec::new();
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => diff_values("", &a, &b, &mut changed),
+        _ => changed.push("(unserializable config)".to_string()),
+    }
+    changed
+}
+
+fn diff_values(path: &str, a: &Value, b: &Value, changed: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                diff_values(
+                    &path,
+                    a.get(key).unwrap_or(&Value::Null),
+                    b.get(key).unwrap_or(&Value::Null),
+                    changed,
+                );
+            }
+        }
+        (a, b) if a != b => changed.push(path.to_string()),
+        _ => {}
+    }
+}
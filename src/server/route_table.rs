@@ -0,0 +1,109 @@
+use crate::server::path_params::{PathParams, is_pattern, match_path};
+use hyper::Method;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RouteTableError {
+    #[error("route table is full ({0} routes)")]
+    Full(usize),
+    #[error("route {method} {path} is already registered")]
+    Duplicate { method: Method, path: String },
+}
+
+/// Routes keyed by method and path. Exact paths resolve with one hash
+/// lookup; paths with `:name` or `*name` segments are tried in
+/// registration order only when no exact path matches.
+#[derive(Debug)]
+pub struct RouteTable<T> {
+    exact: HashMap<String, HashMap<Method, T>>,
+    patterns: Vec<(String, HashMap<Method, T>)>,
+    len: usize,
+    max_routes: usize,
+}
+
+impl<T> RouteTable<T> {
+    pub fn new(max_routes: usize) -> Self {
+        Self {
+            exact: HashMap::new(),
+            patterns: Vec::new(),
+            len: 0,
+            max_routes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn max_routes(&self) -> usize {
+        self.max_routes
+    }
+
+    pub fn insert(&mut self, method: Method, path: &str, route: T) -> Result<(), RouteTableError> {
+        if self.len >= self.max_routes {
+            return Err(RouteTableError::Full(self.max_routes));
+        }
+
+        let methods = if is_pattern(path) {
+            match self
+                .patterns
+                .iter()
+                .position(|(pattern, _)| pattern == path)
+            {
+                Some(index) => &mut self.patterns[index].1,
+                None => {
+                    self.patterns.push((path.to_string(), HashMap::new()));
+                    &mut self.patterns.last_mut().expect("pattern was just pushed").1
+                }
+            }
+        } else {
+            self.exact.entry(path.to_string()).or_default()
+        };
+
+        if methods.contains_key(&method) {
+            return Err(RouteTableError::Duplicate {
+                method,
+                path: path.to_string(),
+            });
+        }
+        methods.insert(method, route);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Methods with a route on `path`, sorted; empty when the path itself
+    /// is unknown. Used for `405 Method Not Allowed` and its `Allow` header.
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let mut methods: Vec<Method> = match self.exact.get(path) {
+            Some(methods) => methods.keys().cloned().collect(),
+            None => self
+                .patterns
+                .iter()
+                .filter(|(pattern, _)| match_path(pattern, path).is_some())
+                .flat_map(|(_, methods)| methods.keys().cloned())
+                .collect(),
+        };
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        methods.dedup();
+        methods
+    }
+
+    /// Finds the route for `method` on `path`, with any captured parameters
+    pub fn lookup(&self, method: &Method, path: &str) -> Option<(&T, PathParams)> {
+        if let Some(methods) = self.exact.get(path) {
+            return methods
+                .get(method)
+                .map(|route| (route, PathParams::default()));
+        }
+
+        self.patterns.iter().find_map(|(pattern, methods)| {
+            let route = methods.get(method)?;
+            match_path(pattern, path).map(|params| (route, params))
+        })
+    }
+}
@@ -1,69 +1,860 @@
-use crate::handlers::HealthHandler;
-use crate::server::TtlController;
+use crate::handlers::dashboard::DASHBOARD_HTML;
+use crate::handlers::{HealthCheckResult, HealthHandler, ProxyHandler, StaticHandler};
+use crate::server::access_control::AccessControl;
+use crate::server::access_log::{AccessLog, AccessLogEntry};
+use crate::server::auth;
+use crate::server::compression::{Compressor, ContentEncoding, is_compressible};
+use crate::server::cors::CorsMiddleware;
+use crate::server::forwarded;
+use crate::server::latency::LatencyHistogram;
+use crate::server::middleware::{
+    ClientIp, Middleware, MiddlewareFuture, Next, PeerIp, X_REQUEST_ID, request_id,
+};
+use crate::server::path_params::PathParams;
+use crate::server::prometheus;
+use crate::server::rate_limit::{RateLimitDecision, RateLimiter};
+use crate::server::route_table::{RouteTable, RouteTableError};
+use crate::server::ssl_manager::{ClientCertInfo, NegotiatedAlpn, NegotiatedTls};
+use crate::server::ttl_controller::ConnectionInfo;
+use crate::server::{
+    HandshakeWatchdog, ServerStats, SharedAppConfig, SharedCertificateInfo, SharedSslManager,
+    TtlController,
+};
+use crate::utils::AppConfig;
+use crate::utils::cidr::IpCidr;
+use crate::utils::config::{AccessLogFormat, RootPage};
+use crate::utils::http::{ChannelBody, ResponseBody, json_content_type};
+#[cfg(feature = "otlp")]
+use crate::utils::telemetry::continue_trace;
 use anyhow::Result;
-use http_body_util::Full;
+use arc_swap::ArcSwap;
+use chrono::{DateTime, SecondsFormat, Utc};
+use http_body_util::{BodyExt, Full};
 use hyper::body::{Bytes, Incoming};
-use hyper::{Method, Request, Response, StatusCode};
+use hyper::header::{
+    ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, HeaderValue, VARY,
+};
+use hyper::{Method, Request, Response, StatusCode, Uri};
+use std::collections::HashMap;
+use std::future::Future;
 use std::net::IpAddr;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{debug, info, warn};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::interval;
+use tracing::{Instrument, debug, info, info_span, warn};
+
+const STANDARD_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::CONNECT,
+    Method::OPTIONS,
+    Method::TRACE,
+    Method::PATCH,
+];
+
+// Largest integer JavaScript consumers can represent exactly (2^53 - 1)
+const JSON_MAX_SAFE_INTEGER: u64 = 9_007_199_254_740_991;
+
+fn json_safe_count(count: u64) -> u64 {
+    count.min(JSON_MAX_SAFE_INTEGER)
+}
+
+/// Routes whose full responses require a bearer token
+fn is_protected_path(path: &str) -> bool {
+    path == "/metrics"
+        || path == "/metrics/stream"
+        || path == "/metrics/prometheus"
+        || path == "/connections.csv"
+        || path.starts_with("/connections/")
+        || path.starts_with("/admin/")
+}
+
+/// Routes that `ssl.client_auth.require_for_metrics` reserves for clients
+/// with a verified certificate
+fn requires_client_cert(path: &str) -> bool {
+    path == "/metrics"
+        || path == "/metrics/stream"
+        || path == "/metrics/prometheus"
+        || path.starts_with("/admin/")
+}
+
+/// Path used for routing. Proxies may send absolute-form targets
+/// (`GET https://host/path`); only the path portion is routed, and an
+/// absolute target without one addresses the root.
+fn request_path(uri: &Uri) -> &str {
+    match uri.path() {
+        "" => "/",
+        path => path,
+    }
+}
+
+fn unix_secs(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn rfc3339(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn connection_json(conn: &ConnectionInfo) -> serde_json::Value {
+    serde_json::json!({
+        "ip": conn.ip.to_string(),
+        "peer_ip": conn.peer_ip.to_string(),
+        "connection_id": conn.id.to_string(),
+        "established_at": conn.established_at.elapsed().as_secs(),
+        "last_activity": conn.last_activity.elapsed().as_secs(),
+        "ttl_seconds": conn.ttl.as_secs(),
+        "time_until_expiry": conn.time_until_expiry().map(|d| d.as_secs()),
+        "request_count": json_safe_count(conn.request_count),
+        "protocol": format!("{:?}", conn.protocol),
+        "is_expired": conn.is_expired()
+    })
+}
+
+/// Owned handles needed to build a /metrics document, so it can also be
+/// produced from the streaming task
+struct MetricsSource {
+    ttl_controller: Arc<TtlController>,
+    stats: Arc<ServerStats>,
+    latency: Arc<LatencyHistogram>,
+    certificate_info: Option<SharedCertificateInfo>,
+}
+
+impl MetricsSource {
+    async fn snapshot(&self, detailed: bool) -> serde_json::Value {
+        let started = Instant::now();
+        let ttl_stats = self.ttl_controller.get_stats();
+        let connections_snapshot = detailed.then(|| self.ttl_controller.get_connections_snapshot());
+        let start_time = self.stats.start_time();
+
+        let mut metrics = serde_json::json!({
+            "ttl_stats": {
+                "active_connections": ttl_stats.active_connections,
+                "distinct_ips": ttl_stats.distinct_ips,
+                "total_connections": ttl_stats.total_connections,
+                "expired_connections": ttl_stats.expired_connections,
+                "average_ttl_seconds": ttl_stats.average_ttl_secs,
+                "http1_connections": ttl_stats.http1_connections,
+                "http2_connections": ttl_stats.http2_connections,
+                "active_connections_v4": ttl_stats.active_connections_v4,
+                "active_connections_v6": ttl_stats.active_connections_v6,
+                "total_connections_v4": ttl_stats.total_connections_v4,
+                "total_connections_v6": ttl_stats.total_connections_v6
+            },
+            "server_stats": self.stats.to_json(),
+            "latency": self.latency.snapshot().to_json(),
+            "certificate": self.certificate_summary(),
+            "uptime_seconds": start_time.uptime().as_secs(),
+            "started_at": DateTime::<Utc>::from(start_time.started_at())
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        // Per-IP detail identifies clients, so only token holders may see it
+        if let Some(connections) = connections_snapshot {
+            metrics["active_connections"] = connections
+                .iter()
+                .map(|(_, conn)| connection_json(conn))
+                .collect();
+        }
+
+        // Lets operators spot /metrics itself slowing down as connections grow
+        metrics["scrape_duration_seconds"] = started.elapsed().as_secs_f64().into();
+        metrics
+    }
+
+    fn certificate_summary(&self) -> serde_json::Value {
+        let info = self
+            .certificate_info
+            .as_ref()
+            .and_then(|info| info.read().ok().and_then(|info| info.clone()));
+
+        match info {
+            Some(cert) => serde_json::json!({
+                "not_after": cert
+                    .not_after
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                "days_until_expiry": cert.days_until_expiry,
+                "is_expired": cert.is_expired
+            }),
+            None => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Future returned by a `RouteHandler`
+pub type RouteFuture = Pin<Box<dyn Future<Output = Result<Response<Full<Bytes>>>> + Send>>;
+
+/// Handler for a route added with `Router::register`. It gets the request
+/// (with any `:name` segments as a `PathParams` extension) and the client
+/// IP, after the router's rate limiting, TTL tracking and auth checks.
+pub type RouteHandler = Arc<dyn Fn(Request<Incoming>, IpAddr) -> RouteFuture + Send + Sync>;
+
+/// Endpoints the router serves itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Health,
+    HealthReady,
+    HealthLive,
+    SslStatus,
+    Metrics,
+    MetricsStream,
+    MetricsPrometheus,
+    ConnectionsCsv,
+    ReloadCertificate,
+    Whoami,
+    Root,
+    ConnectionDetail,
+    /// Index into `Router::custom_handlers`
+    Custom(usize),
+}
+
+/// Routes registered on every router; `server.max_routes` can't go below this
+pub const BUILTIN_ROUTE_COUNT: usize = BUILTIN_ROUTES.len();
+
+const BUILTIN_ROUTES: [(Method, &str, Route); 12] = [
+    (Method::GET, "/health", Route::Health),
+    (Method::GET, "/health/ready", Route::HealthReady),
+    (Method::GET, "/health/live", Route::HealthLive),
+    (Method::GET, "/ssl-status", Route::SslStatus),
+    (Method::GET, "/metrics", Route::Metrics),
+    (Method::GET, "/metrics/stream", Route::MetricsStream),
+    (Method::GET, "/metrics/prometheus", Route::MetricsPrometheus),
+    (Method::GET, "/connections.csv", Route::ConnectionsCsv),
+    (Method::POST, "/admin/reload-cert", Route::ReloadCertificate),
+    (Method::GET, "/whoami", Route::Whoami),
+    (Method::GET, "/", Route::Root),
+    (Method::GET, "/connections/:ip", Route::ConnectionDetail),
+];
+
+/// Outermost middleware: attributes each request to its client, enforces
+/// the rate limit and keeps the TTL controller up to date
+struct ConnectionTracking {
+    ttl_controller: Arc<TtlController>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    access_control: AccessControl,
+    trusted_proxies: Vec<IpCidr>,
+    json_content_type: String,
+}
+
+impl Middleware for ConnectionTracking {
+    fn handle<'a>(&'a self, mut req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let Some(&PeerIp(peer_ip)) = req.extensions().get::<PeerIp>() else {
+                anyhow::bail!("request reached the middleware chain without a peer address");
+            };
+            let client_ip = forwarded::client_ip(peer_ip, req.headers(), &self.trusted_proxies);
+
+            // The accept policy only saw the proxy; a forwarded client from a
+            // denied network is refused here instead
+            if !self.access_control.permits(client_ip) {
+                return Ok(self.forbidden(client_ip)?.map(BodyExt::boxed));
+            }
+
+            // Throttled requests neither reach a handler nor extend the client's
+            // TTL. Allowlisted networks (monitoring, internal callers) are exempt.
+            if let Some(rate_limiter) = &self.rate_limiter
+                && !self.access_control.is_allowlisted(client_ip)
+            {
+                let decision = rate_limiter.check(client_ip);
+                if !decision.allowed {
+                    return Ok(self
+                        .too_many_requests(client_ip, &decision)
+                        .await?
+                        .map(BodyExt::boxed));
+                }
+            }
+
+            // Register/update connection in TTL controller
+            self.ttl_controller
+                .register_forwarded_connection(client_ip, peer_ip);
+            self.ttl_controller
+                .record_protocol(client_ip, req.version());
+            req.extensions_mut().insert(ClientIp(client_ip));
+
+            let response = next.run(req).await?;
+
+            // Update connection activity after successful request
+            self.ttl_controller.update_connection_activity(client_ip);
+            Ok(response)
+        })
+    }
+}
+
+impl ConnectionTracking {
+    fn forbidden(&self, client_ip: IpAddr) -> Result<Response<Full<Bytes>>> {
+        warn!("403 Forbidden: {} is denied by access_control", client_ip);
+
+        let error_response = serde_json::json!({
+            "error": "Forbidden",
+            "message": "Access from this address is denied",
+            "status": 403,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Content-Type", &self.json_content_type)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn too_many_requests(
+        &self,
+        client_ip: IpAddr,
+        decision: &RateLimitDecision,
+    ) -> Result<Response<Full<Bytes>>> {
+        warn!("429 Too Many Requests: {}", client_ip);
+
+        let error_response = serde_json::json!({
+            "error": "Too Many Requests",
+            "message": format!("Rate limit exceeded; retry in {} seconds", decision.retry_after_secs),
+            "status": 429,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header("Content-Type", &self.json_content_type)
+            .header("Retry-After", decision.retry_after_secs)
+            .header("RateLimit-Limit", decision.limit)
+            .header("RateLimit-Remaining", decision.remaining)
+            .header("RateLimit-Reset", decision.reset_secs)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+}
 
 pub struct Router {
     health_handler: HealthHandler,
-    ttl_controller: Arc<Mutex<TtlController>>,
+    ttl_controller: Arc<TtlController>,
+    stats: Arc<ServerStats>,
+    // Time spent in `route` per request
+    latency: Arc<LatencyHistogram>,
+    access_log: Option<Arc<AccessLog>>,
+    certificate_info: Option<SharedCertificateInfo>,
+    ssl_manager: Option<SharedSslManager>,
+    // Connection tracking, then middlewares in the order they were added
+    middlewares: Vec<Arc<dyn Middleware>>,
+    routes: RouteTable<Route>,
+    custom_handlers: Vec<RouteHandler>,
+    // Serves paths no route matched, when they fall below a proxied prefix
+    proxy: Option<ProxyHandler>,
+    compressor: Compressor,
+    json_content_type: String,
+    // Shared with the config reloader, which swaps in reloaded settings
+    config: SharedAppConfig,
+    // Cumulative (total, expired) counters at each scraper's previous scrape
+    scrape_baselines: StdMutex<HashMap<String, (u64, u64)>>,
+    // Last computed /metrics document per detail level, with when it was built
+    metrics_cache: StdMutex<HashMap<bool, (Instant, serde_json::Value)>>,
 }
 
 impl Router {
-    pub fn new(ttl_controller: Arc<Mutex<TtlController>>) -> Self {
-        Self {
-            health_handler: HealthHandler::new(env!("CARGO_PKG_VERSION").to_string()),
+    pub fn new(ttl_controller: Arc<TtlController>, config: &AppConfig) -> Self {
+        let json_content_type = json_content_type(config.server.json_charset.as_deref());
+        let mut health_handler = HealthHandler::new(env!("CARGO_PKG_VERSION").to_string());
+        health_handler.set_json_content_type(json_content_type.clone());
+        health_handler.set_ttl_controller(ttl_controller.clone());
+        health_handler.set_check_timeout(config.health_check_timeout());
+
+        let stats = Arc::new(ServerStats::new(HandshakeWatchdog::new(
+            config.handshake_failure_window(),
+            config.ssl.handshake_failure_threshold,
+            config.ssl.handshake_failure_min_samples,
+        )));
+        health_handler.set_start_time(stats.start_time());
+
+        let mut routes = RouteTable::new(config.server.max_routes.max(config.reserved_routes()));
+        for (method, path, route) in BUILTIN_ROUTES {
+            routes
+                .insert(method, path, route)
+                .expect("built-in routes are distinct and within max_routes");
+        }
+
+        let rate_limiter = (config.rate_limit.requests_per_sec > 0.0).then(|| {
+            ttl_controller
+                .attach_rate_limiter(config.rate_limit.requests_per_sec, config.rate_limit.burst)
+        });
+
+        let tracking: Arc<dyn Middleware> = Arc::new(ConnectionTracking {
+            ttl_controller: ttl_controller.clone(),
+            rate_limiter,
+            access_control: AccessControl::new(&config.access_control),
+            trusted_proxies: config.server.trusted_proxies.clone(),
+            json_content_type: json_content_type.clone(),
+        });
+        let mut middlewares = vec![tracking];
+        if !config.cors.allowed_origins.is_empty() {
+            middlewares.push(Arc::new(CorsMiddleware::new(&config.cors)));
+        }
+
+        let proxy = match ProxyHandler::new(&config.proxy) {
+            Ok(mut proxy) => {
+                proxy.set_json_content_type(json_content_type.clone());
+                for (prefix, upstream) in &config.proxy.routes {
+                    info!("Proxying {} to {}", prefix, upstream);
+                }
+                (!config.proxy.routes.is_empty()).then_some(proxy)
+            }
+            Err(e) => {
+                warn!("Reverse proxy disabled: proxy.routes: {}", e);
+                None
+            }
+        };
+
+        let mut router = Self {
+            health_handler,
             ttl_controller,
+            stats,
+            latency: Arc::new(LatencyHistogram::new(&config.metrics.latency_buckets_ms)),
+            certificate_info: None,
+            ssl_manager: None,
+            middlewares,
+            routes,
+            custom_handlers: Vec::new(),
+            proxy,
+            compressor: config.compressor(),
+            json_content_type,
+            config: Arc::new(ArcSwap::from_pointee(config.clone())),
+            scrape_baselines: StdMutex::new(HashMap::new()),
+            metrics_cache: StdMutex::new(HashMap::new()),
+            access_log: (config.logging.access_log_format != AccessLogFormat::None)
+                .then(|| Arc::new(AccessLog::stdout(config.logging.access_log_format))),
+        };
+
+        if let (Some(root), Some(route)) = (&config.static_files.root, config.static_files.route())
+        {
+            let mut static_handler = StaticHandler::new(root.clone());
+            static_handler.set_max_file_bytes(config.static_files.max_file_bytes);
+            static_handler.set_json_content_type(router.json_content_type.clone());
+            let static_handler = Arc::new(static_handler);
+            router
+                .register(Method::GET, &route, move |req, _| {
+                    let static_handler = static_handler.clone();
+                    async move {
+                        let params = req
+                            .extensions()
+                            .get::<PathParams>()
+                            .cloned()
+                            .unwrap_or_default();
+                        static_handler.handle(&params, req.headers()).await
+                    }
+                })
+                .expect("the static file route is reserved within max_routes");
+            info!("Serving static files from {} at {}", root.display(), route);
         }
+
+        router
     }
 
+    pub fn set_certificate_info(&mut self, certificate_info: SharedCertificateInfo) {
+        self.health_handler
+            .set_certificate_info(certificate_info.clone());
+        self.certificate_info = Some(certificate_info);
+    }
+
+    /// Sends access lines to `access_log` instead of stdout
+    pub fn set_access_log(&mut self, access_log: AccessLog) {
+        self.access_log = Some(Arc::new(access_log));
+    }
+
+    /// Enables `POST /admin/reload-cert`
+    pub fn set_ssl_manager(&mut self, ssl_manager: SharedSslManager) {
+        self.ssl_manager = Some(ssl_manager);
+    }
+
+    /// Appends `middleware` to the chain run around every route. It sees
+    /// requests after rate limiting and TTL tracking, with the client's
+    /// address in a `ClientIp` extension.
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    /// Serves `method` on `path` with `handler`, alongside the built-in
+    /// routes. `path` may capture `:name` segments; exact paths win over
+    /// patterns. Fails once `server.max_routes` is reached or if the route
+    /// is taken.
+    pub fn register<F, Fut>(
+        &mut self,
+        method: Method,
+        path: &str,
+        handler: F,
+    ) -> std::result::Result<(), RouteTableError>
+    where
+        F: Fn(Request<Incoming>, IpAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<Full<Bytes>>>> + Send + 'static,
+    {
+        let index = self.custom_handlers.len();
+        self.routes.insert(method, path, Route::Custom(index))?;
+        self.custom_handlers.push(Arc::new(move |req, client_ip| {
+            Box::pin(handler(req, client_ip))
+        }));
+        Ok(())
+    }
+
+    /// Adds a dependency check to `/health`; see `HealthHandler::register_check`
+    pub fn register_health_check<F, Fut>(&mut self, name: &str, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HealthCheckResult> + Send + 'static,
+    {
+        self.health_handler.register_check(name, check);
+    }
+
+    fn certificate_expired(&self) -> bool {
+        // The flag is only refreshed periodically, so check the date too
+        self.certificate_info
+            .as_ref()
+            .and_then(|info| {
+                info.read().ok().map(|info| {
+                    info.as_ref()
+                        .is_some_and(|c| c.is_expired || c.not_after <= SystemTime::now())
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// The config requests are served with; hand it to the config reloader
+    /// so reloaded settings reach them
+    pub fn config_handle(&self) -> SharedAppConfig {
+        self.config.clone()
+    }
+
+    pub fn stats(&self) -> Arc<ServerStats> {
+        self.stats.clone()
+    }
+
+    pub fn ttl_controller(&self) -> Arc<TtlController> {
+        self.ttl_controller.clone()
+    }
+
+    fn normalize_method(&self, method: &Method) -> Method {
+        if !self.config.load().server.normalize_methods {
+            return method.clone();
+        }
+
+        // hyper keeps extension methods verbatim, so "get" would never match GET
+        let upper = method.as_str().to_ascii_uppercase();
+        STANDARD_METHODS
+            .iter()
+            .find(|standard| standard.as_str() == upper)
+            .cloned()
+            .unwrap_or_else(|| method.clone())
+    }
+
+    /// Serves `req` from `peer_ip`, the address that connected; requests
+    /// relayed by a trusted proxy are attributed to the forwarded client.
+    /// Everything logged while handling it carries a `request` span with the
+    /// request id, which is echoed as `X-Request-Id`. With the `otlp` feature
+    /// the span joins the trace named by an inbound `traceparent` header.
     pub async fn route(
+        &self,
+        mut req: Request<Incoming>,
+        peer_ip: IpAddr,
+    ) -> Result<Response<ResponseBody>> {
+        let started = Instant::now();
+        let client_ip = forwarded::client_ip(
+            peer_ip,
+            req.headers(),
+            &self.config.load().server.trusted_proxies,
+        );
+        let request_id = request_id(&req);
+        req.headers_mut().insert(X_REQUEST_ID, request_id.clone());
+        let span = info_span!(
+            "request",
+            request_id = %request_id.to_str().unwrap_or_default(),
+            client_ip = %client_ip,
+            method = %req.method(),
+            path = %request_path(req.uri()),
+        );
+        #[cfg(feature = "otlp")]
+        continue_trace(&span, req.headers());
+
+        // Status and size are only known once the handler has run
+        let access_entry = self
+            .access_log
+            .as_ref()
+            .map(|_| AccessLogEntry::new(&req, client_ip));
+        let mut response = self
+            .route_within_deadline(req, peer_ip)
+            .instrument(span)
+            .await;
+        let latency = started.elapsed();
+        self.latency.record(latency);
+
+        if let Ok(response) = &mut response {
+            response.headers_mut().insert(X_REQUEST_ID, request_id);
+        }
+        if let (Some(access_log), Some(mut entry), Ok(response)) =
+            (&self.access_log, access_entry, &response)
+        {
+            entry.complete(response, latency);
+            access_log.record(&entry);
+        }
+        response
+    }
+
+    async fn route_within_deadline(
         &self,
         req: Request<Incoming>,
-        client_ip: IpAddr,
-    ) -> Result<Response<Full<Bytes>>> {
-        // Register/update connection in TTL controller
+        peer_ip: IpAddr,
+    ) -> Result<Response<ResponseBody>> {
+        let Some(deadline) = self.config.load().route_timeout(request_path(req.uri())) else {
+            return self.dispatch(req, peer_ip).await;
+        };
+
+        let path = request_path(req.uri()).to_string();
+        match tokio::time::timeout(deadline, self.dispatch(req, peer_ip)).await {
+            Ok(response) => response,
+            Err(_) => Ok(self
+                .handle_gateway_timeout(&path, deadline)
+                .await?
+                .map(BodyExt::boxed)),
+        }
+    }
+
+    /// Runs the middleware chain, connection tracking first, around the
+    /// matched route
+    async fn dispatch(
+        &self,
+        mut req: Request<Incoming>,
+        peer_ip: IpAddr,
+    ) -> Result<Response<ResponseBody>> {
+        req.extensions_mut().insert(PeerIp(peer_ip));
+        let endpoint =
+            |req: Request<Incoming>| -> MiddlewareFuture<'_> { Box::pin(self.handle_route(req)) };
+        Next::new(&self.middlewares, &endpoint).run(req).await
+    }
+
+    async fn handle_route(&self, req: Request<Incoming>) -> Result<Response<ResponseBody>> {
+        let Some(&ClientIp(client_ip)) = req.extensions().get::<ClientIp>() else {
+            anyhow::bail!("request reached its route without connection tracking");
+        };
+        // One snapshot per request, so a reload never mixes settings
+        let config = self.config.load_full();
+        let accept_encoding = req
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        let method = self.normalize_method(req.method());
+        let path = request_path(req.uri());
+        let authorized = auth::is_authorized(&req, &config.auth);
+
+        // Clients proving a token on a protected route are trusted to stay longer
+        if config.auth.ttl_boost_factor > 1.0
+            && is_protected_path(path)
+            && auth::has_valid_token(&req, &config.auth)
         {
-            let mut ttl_controller = self.ttl_controller.lock().await;
-            ttl_controller.register_connection(client_ip);
+            self.ttl_controller
+                .boost_ttl(client_ip, config.auth.ttl_boost_factor);
         }
 
-        let method = req.method();
-        let path = req.uri().path();
+        // Formatting this line dominates at high request rates
+        if config.logging.log_requests {
+            let alpn = req
+                .extensions()
+                .get::<NegotiatedAlpn>()
+                .map_or("none", |alpn| alpn.0.as_str());
+            info!(
+                "Request: {} {} from {} (alpn: {})",
+                method, path, client_ip, alpn
+            );
+        }
+
+        if config.server.reject_nonstandard_methods && !STANDARD_METHODS.contains(&method) {
+            return Ok(self
+                .handle_not_implemented(&method)
+                .await?
+                .map(BodyExt::boxed));
+        }
 
-        info!("Request: {} {} from {}", method, path, client_ip);
+        let route = self
+            .routes
+            .lookup(&method, path)
+            .map(|(route, params)| (*route, params));
 
-        let response = match (method, path) {
+        // Refuse to keep serving real traffic on an expired certificate, but
+        // let the health probes through so orchestrators can see why
+        let is_probe = matches!(
+            route,
+            Some((Route::Health | Route::HealthReady | Route::HealthLive, _))
+        );
+        if config.ssl.fail_closed_on_expired_cert && !is_probe && self.certificate_expired() {
+            return Ok(self.handle_certificate_expired().await?.map(BodyExt::boxed));
+        }
+
+        if config.ssl.client_auth.require_for_metrics
+            && requires_client_cert(path)
+            && req.extensions().get::<ClientCertInfo>().is_none()
+        {
+            return Ok(self.handle_forbidden(path).await?.map(BodyExt::boxed));
+        }
+
+        // Streams outlive this call, so they skip the post-response bookkeeping
+        if let Some((Route::MetricsStream, _)) = route {
+            return self.handle_metrics_stream(authorized);
+        }
+
+        let response = match route {
             // Health checks
-            (&Method::GET, "/health") => self.health_handler.handle_health_check().await?,
-            (&Method::GET, "/health/ready") => self.health_handler.handle_readiness_check().await?,
-            (&Method::GET, "/health/live") => self.health_handler.handle_liveness_check().await?,
+            Some((Route::Health, _)) => self.health_handler.handle_health_check().await?,
+            Some((Route::HealthReady, _)) => self.health_handler.handle_readiness_check().await?,
+            Some((Route::HealthLive, _)) => self.health_handler.handle_liveness_check().await?,
 
             // SSL status endpoint
-            (&Method::GET, "/ssl-status") => self.handle_ssl_status().await?,
+            Some((Route::SslStatus, _)) => {
+                self.handle_ssl_status(req.extensions().get::<NegotiatedTls>())
+                    .await?
+            }
 
             // TTL metrics endpoint
-            (&Method::GET, "/metrics") => self.handle_metrics().await?,
+            Some((Route::Metrics, _)) => {
+                // Only configured tokens get their own baseline, so arbitrary
+                // bearer values can't grow the map
+                let scraper = auth::has_valid_token(&req, &config.auth)
+                    .then(|| auth::bearer_token(&req))
+                    .flatten()
+                    .unwrap_or_default();
+                self.handle_metrics(authorized, scraper).await?
+            }
+
+            // Same aggregates for Prometheus scrapers
+            Some((Route::MetricsPrometheus, _)) => self.handle_metrics_prometheus().await?,
+
+            // Full connection snapshot for offline analysis
+            Some((Route::ConnectionsCsv, _)) if authorized => self.handle_connections_csv().await?,
+            Some((Route::ConnectionsCsv, _)) => self.handle_unauthorized().await?,
+
+            // Manual certificate rotation always needs a token, even with auth disabled
+            Some((Route::ReloadCertificate, _)) if auth::has_valid_token(&req, &config.auth) => {
+                self.handle_reload_certificate().await?
+            }
+            Some((Route::ReloadCertificate, _)) => self.handle_unauthorized().await?,
+
+            // Connection info for the calling client
+            Some((Route::Whoami, _)) => {
+                let client_cert = req.extensions().get::<ClientCertInfo>();
+                self.handle_whoami(client_ip, client_cert).await?
+            }
 
             // Root endpoint
-            (&Method::GET, "/") => self.handle_root().await?,
+            Some((Route::Root, _)) if config.server.root_page == RootPage::Dashboard => {
+                self.handle_dashboard().await?
+            }
+            Some((Route::Root, _)) => self.handle_root().await?,
+
+            Some((Route::ConnectionDetail, _)) if !authorized => self.handle_unauthorized().await?,
+            Some((Route::ConnectionDetail, params)) => {
+                self.handle_connection_detail(&params).await?
+            }
 
-            // 404 for everything else
-            _ => self.handle_not_found(path).await?,
+            Some((Route::Custom(index), params)) => {
+                let handler = self.custom_handlers[index].clone();
+                let mut req = req;
+                req.extensions_mut().insert(params);
+                handler(req, client_ip).await?
+            }
+
+            // Anything the router doesn't serve itself may belong to a backend
+            None if self.proxy.as_ref().is_some_and(|proxy| proxy.matches(path)) => {
+                let peer_ip = req
+                    .extensions()
+                    .get::<PeerIp>()
+                    .map_or(client_ip, |&PeerIp(ip)| ip);
+                let proxy = self.proxy.as_ref().expect("matched above");
+                proxy.handle(req, client_ip, peer_ip).await?
+            }
+
+            // The path exists, just not for this method
+            Some((Route::MetricsStream, _)) | None => {
+                let allowed = self.routes.allowed_methods(path);
+                if allowed.is_empty() {
+                    self.handle_not_found(path).await?
+                } else {
+                    self.handle_method_not_allowed(&method, path, &allowed)
+                        .await?
+                }
+            }
         };
 
-        // Update connection activity after successful request
+        let response = self
+            .negotiate_compression(accept_encoding.as_deref(), response)
+            .await?;
+        Ok(response.map(BodyExt::boxed))
+    }
+
+    /// Compresses text responses of at least `server.compression_min_bytes`
+    /// for clients whose `Accept-Encoding` allows it
+    async fn negotiate_compression(
+        &self,
+        accept_encoding: Option<&str>,
+        response: Response<Full<Bytes>>,
+    ) -> Result<Response<Full<Bytes>>> {
+        let compressible = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(is_compressible);
+        let config = self.config.load_full();
+        if !config.server.compression_enabled
+            || !compressible
+            || response.headers().contains_key(CONTENT_ENCODING)
         {
-            let ttl_controller = self.ttl_controller.lock().await;
-            ttl_controller.update_connection_activity(client_ip);
+            return Ok(response);
         }
 
-        Ok(response)
+        let (mut parts, body) = response.into_parts();
+        let body = body.collect().await?.to_bytes();
+        if body.len() < config.server.compression_min_bytes {
+            return Ok(Response::from_parts(parts, Full::new(body)));
+        }
+
+        parts
+            .headers
+            .append(VARY, HeaderValue::from_static("Accept-Encoding"));
+        let Some(encoding) = accept_encoding.and_then(ContentEncoding::negotiate) else {
+            return Ok(Response::from_parts(parts, Full::new(body)));
+        };
+
+        let compressed = self.compressor.compress(encoding, &body)?;
+        debug!(
+            "Compressed {} byte response to {} bytes with {}",
+            body.len(),
+            compressed.len(),
+            encoding.as_str()
+        );
+        parts.headers.remove(CONTENT_LENGTH);
+        parts.headers.insert(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.as_str()),
+        );
+        Ok(Response::from_parts(
+            parts,
+            Full::new(Bytes::from(compressed)),
+        ))
     }
 
     async fn handle_root(&self) -> Result<Response<Full<Bytes>>> {
@@ -141,6 +932,14 @@ impl Router {
             <strong><a href="/metrics">/metrics</a></strong> - Connection and TTL metrics
         </div>
         
+        <div class="endpoint">
+            <strong><a href="/metrics/prometheus">/metrics/prometheus</a></strong> - The same metrics for Prometheus
+        </div>
+        
+        <div class="endpoint">
+            <strong><a href="/whoami">/whoami</a></strong> - Your connection details
+        </div>
+        
         <hr style="margin: 30px 0;">
         
         <p><strong>Features:</strong></p>
@@ -169,70 +968,359 @@ impl Router {
         Ok(response)
     }
 
-    async fn handle_ssl_status(&self) -> Result<Response<Full<Bytes>>> {
-        debug!("SSL status endpoint requested");
+    async fn handle_dashboard(&self) -> Result<Response<Full<Bytes>>> {
+        debug!("Dashboard requested");
 
-        // In a real implementation, you would get this from the SSL manager
-        let ssl_status = serde_json::json!({
-            "status": "active",
-            "certificate": {
-                "subject": "tilas.xyz",
-                "issuer": "Let's Encrypt",
-                "valid_from": "2024-01-01T00:00:00Z",
-                "valid_until": "2024-04-01T00:00:00Z",
-                "days_until_expiry": 45,
-                "is_expired": false
-            },
-            "tls_version": "1.3",
-            "cipher_suite": "TLS_AES_256_GCM_SHA384"
-        });
+        let html_content = DASHBOARD_HTML.replace("{version}", env!("CARGO_PKG_VERSION"));
 
         let response = Response::builder()
             .status(StatusCode::OK)
-            .header("Content-Type", "application/json")
+            .header("Content-Type", "text/html; charset=utf-8")
+            .header("Cache-Control", "public, max-age=300")
+            .body(Full::new(Bytes::from(html_content)))?;
+
+        Ok(response)
+    }
+
+    async fn handle_ssl_status(
+        &self,
+        tls: Option<&NegotiatedTls>,
+    ) -> Result<Response<Full<Bytes>>> {
+        debug!("SSL status requested");
+
+        let info = self
+            .certificate_info
+            .as_ref()
+            .and_then(|info| info.read().ok().and_then(|info| info.clone()));
+
+        let (status, ssl_status) = match info {
+            Some(cert) => {
+                // Agrees with fail-closed mode even between monitor ticks
+                let is_expired = self.certificate_expired();
+                let (status, label) = if is_expired {
+                    (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+                } else {
+                    (StatusCode::OK, "active")
+                };
+                (
+                    status,
+                    serde_json::json!({
+                        "status": label,
+                        "certificate": {
+                            "subject": cert.subject,
+                            "issuer": cert.issuer,
+                            "valid_from": rfc3339(cert.not_before),
+                            "valid_until": rfc3339(cert.not_after),
+                            "days_until_expiry": cert.days_until_expiry,
+                            "is_expired": is_expired,
+                            "fingerprint_sha256": cert.fingerprint_sha256,
+                            "dns_names": cert.dns_names
+                        },
+                        // What this request's own connection negotiated;
+                        // null over plaintext
+                        "tls_version": tls.map(|tls| &tls.version),
+                        "cipher_suite": tls.map(|tls| &tls.cipher_suite)
+                    }),
+                )
+            }
+            None => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                serde_json::json!({
+                    "status": "unavailable",
+                    "certificate": null
+                }),
+            ),
+        };
+
+        let response = Response::builder()
+            .status(status)
+            .header("Content-Type", &self.json_content_type)
             .header("Cache-Control", "no-cache")
             .body(Full::new(Bytes::from(ssl_status.to_string())))?;
 
         Ok(response)
     }
 
-    async fn handle_metrics(&self) -> Result<Response<Full<Bytes>>> {
-        debug!("Metrics endpoint requested");
+    async fn handle_reload_certificate(&self) -> Result<Response<Full<Bytes>>> {
+        let Some(ssl_manager) = &self.ssl_manager else {
+            return self.handle_not_found("/admin/reload-cert").await;
+        };
 
-        let ttl_stats = {
-            let ttl_controller = self.ttl_controller.lock().await;
-            ttl_controller.get_stats()
+        // Holding the lock keeps the monitor from checking or reloading concurrently
+        let (status, body) = {
+            let mut ssl_manager = ssl_manager.lock().await;
+            match ssl_manager.reload_certificates().await {
+                Ok(()) => {
+                    info!("Certificate reloaded via admin endpoint");
+                    let certificate = ssl_manager.get_certificate_info().map(|cert| {
+                        serde_json::json!({
+                            "not_before": unix_secs(cert.not_before),
+                            "not_after": unix_secs(cert.not_after),
+                            "days_until_expiry": cert.days_until_expiry,
+                            "is_expired": cert.is_expired
+                        })
+                    });
+                    (
+                        StatusCode::OK,
+                        serde_json::json!({ "status": "reloaded", "certificate": certificate }),
+                    )
+                }
+                Err(e) => {
+                    warn!("Certificate reload via admin endpoint failed: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        serde_json::json!({
+                            "error": "Certificate Reload Failed",
+                            "message": e.to_string(),
+                            "status": 500
+                        }),
+                    )
+                }
+            }
         };
 
-        let connections_snapshot = {
-            let ttl_controller = self.ttl_controller.lock().await;
-            ttl_controller.get_connections_snapshot()
+        let response = Response::builder()
+            .status(status)
+            .header("Content-Type", &self.json_content_type)
+            .header("Cache-Control", "no-cache")
+            .body(Full::new(Bytes::from(body.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_metrics(
+        &self,
+        authorized: bool,
+        scraper: &str,
+    ) -> Result<Response<Full<Bytes>>> {
+        debug!("Metrics endpoint requested (authorized: {})", authorized);
+
+        let mut metrics = self.cached_metrics(authorized).await;
+        if self.config.load().metrics.report_deltas {
+            let total = metrics["ttl_stats"]["total_connections"]
+                .as_u64()
+                .unwrap_or(0);
+            let expired = metrics["ttl_stats"]["expired_connections"]
+                .as_u64()
+                .unwrap_or(0);
+            let (delta_total, delta_expired) = self.scrape_deltas(scraper, total, expired);
+            metrics["deltas"] = serde_json::json!({
+                "total_connections": delta_total,
+                "expired_connections": delta_expired
+            });
+        }
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", &self.json_content_type)
+            .header("Cache-Control", "no-cache")
+            .body(Full::new(Bytes::from(metrics.to_string())))?;
+
+        Ok(response)
+    }
+
+    /// Serves a cached document while it is younger than the configured max
+    /// age, so frequent scrapers don't each walk every connection
+    async fn cached_metrics(&self, detailed: bool) -> serde_json::Value {
+        let max_age = self.config.load().metrics_cache_max_age();
+        if max_age.is_zero() {
+            return self.metrics_source().snapshot(detailed).await;
+        }
+
+        if let Ok(cache) = self.metrics_cache.lock()
+            && let Some((computed_at, metrics)) = cache.get(&detailed)
+            && computed_at.elapsed() < max_age
+        {
+            return metrics.clone();
+        }
+
+        let metrics = self.metrics_source().snapshot(detailed).await;
+        if let Ok(mut cache) = self.metrics_cache.lock() {
+            cache.insert(detailed, (Instant::now(), metrics.clone()));
+        }
+        metrics
+    }
+
+    /// Returns the counter increase since this scraper's previous scrape and
+    /// records the new baseline. A first scrape reports the full counters.
+    fn scrape_deltas(&self, scraper: &str, total: u64, expired: u64) -> (u64, u64) {
+        let Ok(mut baselines) = self.scrape_baselines.lock() else {
+            return (total, expired);
         };
 
-        let detailed_connections: Vec<_> = connections_snapshot
-            .into_iter()
-            .map(|(ip, conn)| {
-                serde_json::json!({
-                    "ip": ip.to_string(),
-                    "connection_id": conn.id.to_string(),
-                    "established_at": conn.established_at.elapsed().as_secs(),
-                    "last_activity": conn.last_activity.elapsed().as_secs(),
-                    "ttl_seconds": conn.ttl.as_secs(),
-                    "time_until_expiry": conn.time_until_expiry().map(|d| d.as_secs()),
-                    "request_count": conn.request_count,
-                    "is_expired": conn.is_expired()
-                })
-            })
-            .collect();
+        let (last_total, last_expired) = baselines
+            .insert(scraper.to_string(), (total, expired))
+            .unwrap_or((0, 0));
+        (
+            total.saturating_sub(last_total),
+            expired.saturating_sub(last_expired),
+        )
+    }
 
-        let metrics = serde_json::json!({
-            "ttl_stats": {
-                "active_connections": ttl_stats.active_connections,
-                "total_connections": ttl_stats.total_connections,
-                "expired_connections": ttl_stats.expired_connections,
-                "average_ttl_seconds": ttl_stats.average_ttl_secs
-            },
-            "active_connections": detailed_connections,
+    fn handle_metrics_stream(&self, authorized: bool) -> Result<Response<ResponseBody>> {
+        debug!("Metrics stream opened (authorized: {})", authorized);
+
+        let source = self.metrics_source();
+        let every = self.config.load().metrics_stream_interval();
+        let (tx, rx) = mpsc::channel(1);
+
+        // Ends once the client disconnects and hyper drops the body
+        tokio::spawn(async move {
+            let mut ticker = interval(every);
+            loop {
+                ticker.tick().await;
+                let mut line = source.snapshot(authorized).await.to_string();
+                line.push('\n');
+                if tx.send(Bytes::from(line)).await.is_err() {
+                    debug!("Metrics stream closed");
+                    return;
+                }
+            }
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/x-ndjson")
+            .header("Cache-Control", "no-cache")
+            .body(ChannelBody::new(rx).boxed())?;
+
+        Ok(response)
+    }
+
+    fn metrics_source(&self) -> MetricsSource {
+        MetricsSource {
+            ttl_controller: self.ttl_controller.clone(),
+            stats: self.stats.clone(),
+            latency: self.latency.clone(),
+            certificate_info: self.certificate_info.clone(),
+        }
+    }
+
+    async fn handle_connection_detail(&self, params: &PathParams) -> Result<Response<Full<Bytes>>> {
+        let ip: IpAddr = match params.parse("ip") {
+            Ok(ip) => ip,
+            Err(e) => return self.handle_bad_request(&e.to_string()).await,
+        };
+        debug!("Connection detail requested for {}", ip);
+
+        let connection = self.ttl_controller.get_connection_info(ip);
+
+        let Some(conn) = connection else {
+            return self.handle_not_found(&format!("/connections/{}", ip)).await;
+        };
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", &self.json_content_type)
+            .header("Cache-Control", "no-cache")
+            .body(Full::new(Bytes::from(connection_json(&conn).to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_metrics_prometheus(&self) -> Result<Response<Full<Bytes>>> {
+        debug!("Prometheus metrics requested");
+
+        let started = Instant::now();
+        let ttl_stats = self.ttl_controller.get_stats();
+        let latency = self.latency.snapshot();
+        let body = prometheus::render(
+            &ttl_stats,
+            &latency,
+            &self.stats.start_time(),
+            started.elapsed(),
+        );
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", prometheus::CONTENT_TYPE)
+            .header("Cache-Control", "no-cache")
+            .body(Full::new(Bytes::from(body)))?;
+
+        Ok(response)
+    }
+
+    async fn handle_connections_csv(&self) -> Result<Response<Full<Bytes>>> {
+        debug!("Connections CSV export requested");
+
+        let connections_snapshot = self.ttl_controller.get_connections_snapshot();
+
+        let mut csv = String::from(
+            "ip,id,established_secs,last_activity_secs,ttl_secs,request_count,expired\n",
+        );
+        for (ip, conn) in connections_snapshot {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                ip,
+                conn.id,
+                conn.established_at.elapsed().as_secs(),
+                conn.last_activity.elapsed().as_secs(),
+                conn.ttl.as_secs(),
+                conn.request_count,
+                conn.is_expired()
+            ));
+        }
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/csv; charset=utf-8")
+            .header(
+                "Content-Disposition",
+                "attachment; filename=\"connections.csv\"",
+            )
+            .header("Cache-Control", "no-cache")
+            .body(Full::new(Bytes::from(csv)))?;
+
+        Ok(response)
+    }
+
+    async fn handle_whoami(
+        &self,
+        client_ip: IpAddr,
+        client_cert: Option<&ClientCertInfo>,
+    ) -> Result<Response<Full<Bytes>>> {
+        debug!("Whoami endpoint requested by {}", client_ip);
+
+        let connection = self.ttl_controller.get_connection_info(client_ip);
+
+        let Some(conn) = connection else {
+            return self.handle_not_found("/whoami").await;
+        };
+
+        // Identity is only valid while the connection lives, so never let
+        // intermediaries cache it past the remaining TTL
+        let remaining_secs = conn.time_until_expiry().map(|d| d.as_secs()).unwrap_or(0);
+
+        let whoami = serde_json::json!({
+            "ip": client_ip.to_string(),
+            "connection_id": conn.id.to_string(),
+            "request_count": json_safe_count(conn.request_count),
+            "ttl_seconds": conn.ttl.as_secs(),
+            "time_until_expiry": remaining_secs,
+            "client_cert": client_cert.map(|cert| serde_json::json!({
+                "subject_cn": cert.subject_cn
+            }))
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", &self.json_content_type)
+            .header(
+                "Cache-Control",
+                format!("private, max-age={}", remaining_secs),
+            )
+            .body(Full::new(Bytes::from(whoami.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_certificate_expired(&self) -> Result<Response<Full<Bytes>>> {
+        warn!("503 Certificate Expired: failing closed");
+
+        let error_response = serde_json::json!({
+            "error": "Certificate Expired",
+            "message": "The server certificate has expired; requests are refused until it is renewed",
+            "status": 503,
             "timestamp": std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
@@ -240,10 +1328,152 @@ impl Router {
         });
 
         let response = Response::builder()
-            .status(StatusCode::OK)
-            .header("Content-Type", "application/json")
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header("Content-Type", &self.json_content_type)
             .header("Cache-Control", "no-cache")
-            .body(Full::new(Bytes::from(metrics.to_string())))?;
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_gateway_timeout(
+        &self,
+        path: &str,
+        deadline: Duration,
+    ) -> Result<Response<Full<Bytes>>> {
+        warn!("504 Gateway Timeout: {} exceeded {:?}", path, deadline);
+
+        let error_response = serde_json::json!({
+            "error": "Gateway Timeout",
+            "message": format!("The handler for '{}' did not respond within {}ms", path, deadline.as_millis()),
+            "status": 504,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::GATEWAY_TIMEOUT)
+            .header("Content-Type", &self.json_content_type)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_bad_request(&self, message: &str) -> Result<Response<Full<Bytes>>> {
+        warn!("400 Bad Request: {}", message);
+
+        let error_response = serde_json::json!({
+            "error": "Bad Request",
+            "message": message,
+            "status": 400,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", &self.json_content_type)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_forbidden(&self, path: &str) -> Result<Response<Full<Bytes>>> {
+        warn!("403 Forbidden: {} without a client certificate", path);
+
+        let error_response = serde_json::json!({
+            "error": "Forbidden",
+            "message": "A verified client certificate is required for this endpoint",
+            "status": 403,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Content-Type", &self.json_content_type)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_unauthorized(&self) -> Result<Response<Full<Bytes>>> {
+        warn!("401 Unauthorized");
+
+        let error_response = serde_json::json!({
+            "error": "Unauthorized",
+            "message": "A valid bearer token is required for this endpoint",
+            "status": 401,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("Content-Type", &self.json_content_type)
+            .header("WWW-Authenticate", "Bearer")
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_not_implemented(&self, method: &Method) -> Result<Response<Full<Bytes>>> {
+        warn!("501 Not Implemented: {}", method);
+
+        let error_response = serde_json::json!({
+            "error": "Not Implemented",
+            "message": format!("The request method '{}' is not supported by this server", method),
+            "status": 501,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::NOT_IMPLEMENTED)
+            .header("Content-Type", &self.json_content_type)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
+
+        Ok(response)
+    }
+
+    async fn handle_method_not_allowed(
+        &self,
+        method: &Method,
+        path: &str,
+        allowed: &[Method],
+    ) -> Result<Response<Full<Bytes>>> {
+        warn!("405 Method Not Allowed: {} {}", method, path);
+
+        let allow = allowed
+            .iter()
+            .map(Method::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let error_response = serde_json::json!({
+            "error": "Method Not Allowed",
+            "message": format!("The path '{}' does not support '{}'; allowed: {}", path, method, allow),
+            "status": 405,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header("Content-Type", &self.json_content_type)
+            .header("Allow", allow)
+            .body(Full::new(Bytes::from(error_response.to_string())))?;
 
         Ok(response)
     }
@@ -263,7 +1493,7 @@ impl Router {
 
         let response = Response::builder()
             .status(StatusCode::NOT_FOUND)
-            .header("Content-Type", "application/json")
+            .header("Content-Type", &self.json_content_type)
             .body(Full::new(Bytes::from(error_response.to_string())))?;
 
         Ok(response)
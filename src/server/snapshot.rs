@@ -0,0 +1,117 @@
+use crate::server::TtlController;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// A tracked connection as written to disk. `Instant`s don't survive a
+/// restart, so ages are stored relative to `ConnectionSnapshot::saved_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionRecord {
+    pub ip: IpAddr,
+    pub id: Uuid,
+    pub request_count: u64,
+    pub ttl_secs: u64,
+    pub age_secs: u64,
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionSnapshot {
+    pub saved_at: u64, // unix seconds
+    pub connections: Vec<ConnectionRecord>,
+}
+
+impl ConnectionSnapshot {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read(path)?;
+        serde_json::from_slice(&contents).map_err(io::Error::other)
+    }
+
+    pub fn write_atomic(&self, path: &Path) -> io::Result<()> {
+        write_json_atomic(self, path)
+    }
+}
+
+/// The controller's cumulative counters, kept across restarts so they stay
+/// monotonic for dashboards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub saved_at: u64, // unix seconds
+    pub total_connections: u64,
+    // Absent from files written before the split; those resume from zero
+    #[serde(default)]
+    pub total_connections_v4: u64,
+    #[serde(default)]
+    pub total_connections_v6: u64,
+    pub expired_connections: u64,
+}
+
+impl StatsSnapshot {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read(path)?;
+        serde_json::from_slice(&contents).map_err(io::Error::other)
+    }
+
+    pub fn write_atomic(&self, path: &Path) -> io::Result<()> {
+        write_json_atomic(self, path)
+    }
+}
+
+/// Writes to a sibling temp file and renames it over `path`, so readers
+/// (and a restart after a crash) see either the old or the new contents
+fn write_json_atomic<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    let contents = serde_json::to_vec(value).map_err(io::Error::other)?;
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Periodically persists the controller's connections to `path`
+pub fn spawn_snapshot_task(
+    ttl_controller: Arc<TtlController>,
+    path: PathBuf,
+    every: Duration,
+) -> JoinHandle<()> {
+    info!(
+        "Snapshotting connection state to {} every {:?}",
+        path.display(),
+        every
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = interval(every);
+        loop {
+            ticker.tick().await;
+
+            let snapshot = ttl_controller.snapshot();
+            let count = snapshot.connections.len();
+            let target = path.clone();
+            match tokio::task::spawn_blocking(move || snapshot.write_atomic(&target)).await {
+                Ok(Ok(())) => debug!("Wrote snapshot of {} connections", count),
+                Ok(Err(e)) => warn!("Failed to write snapshot {}: {}", path.display(), e),
+                Err(e) => warn!("Snapshot writer panicked: {}", e),
+            }
+        }
+    })
+}
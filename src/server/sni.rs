@@ -0,0 +1,79 @@
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Certificates keyed by the hostname clients request
+pub type SniCertificateMap = HashMap<String, Arc<CertifiedKey>>;
+
+#[derive(Debug)]
+struct SniCertificates {
+    default: Arc<CertifiedKey>,
+    by_name: SniCertificateMap,
+}
+
+/// Picks the certificate matching the ClientHello's server name, falling
+/// back to the default certificate for unknown names and SNI-less clients.
+/// The certificate set can be swapped in place, so acceptors already built
+/// from a config holding this resolver serve reloaded certificates.
+#[derive(Debug)]
+pub struct SniResolver {
+    certificates: RwLock<SniCertificates>,
+}
+
+impl SniResolver {
+    pub fn new(default: Arc<CertifiedKey>, by_name: SniCertificateMap) -> Self {
+        Self {
+            certificates: RwLock::new(SniCertificates {
+                default,
+                by_name: normalize_names(by_name),
+            }),
+        }
+    }
+
+    /// Replaces every certificate at once; in-flight handshakes keep the
+    /// certificate they already resolved
+    pub fn replace(&self, default: Arc<CertifiedKey>, by_name: SniCertificateMap) {
+        if let Ok(mut certificates) = self.certificates.write() {
+            *certificates = SniCertificates {
+                default,
+                by_name: normalize_names(by_name),
+            };
+        }
+    }
+
+    pub fn hostnames(&self) -> Vec<String> {
+        self.certificates
+            .read()
+            .map(|certificates| certificates.by_name.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn lookup(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let certificates = self.certificates.read().ok()?;
+        let matched = server_name.and_then(|name| certificates.by_name.get(name));
+        if matched.is_none() {
+            debug!(
+                "No certificate for SNI {:?}; serving the default",
+                server_name
+            );
+        }
+        Some(matched.unwrap_or(&certificates.default).clone())
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        // rustls hands over server names already lowercased
+        self.lookup(client_hello.server_name())
+    }
+}
+
+// DNS names compare case-insensitively and may carry a trailing root dot
+fn normalize_names(by_name: SniCertificateMap) -> SniCertificateMap {
+    by_name
+        .into_iter()
+        .map(|(name, key)| (name.trim_end_matches('.').to_ascii_lowercase(), key))
+        .collect()
+}
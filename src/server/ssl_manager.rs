@@ -1,14 +1,32 @@
-use rustls::ServerConfig;
+use crate::server::sni::{SniCertificateMap, SniResolver};
+use crate::utils::config::{
+    AcmeConfig, ClientAuthConfig, ClientAuthMode, SslConfig, StartupRetryConfig,
+};
+use arc_swap::ArcSwap;
+use rustls::crypto::CryptoProvider;
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::{Acceptor, ServerConnection, WebPkiClientVerifier};
+use rustls::sign::{CertifiedKey, SingleCertAndKey};
+use rustls::{
+    InconsistentKeys, ProtocolVersion, RootCertStore, ServerConfig, SupportedCipherSuite,
+};
 use rustls_pemfile::{certs, private_key};
+use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::{self, BufReader};
 use std::path::Path;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tokio::time::{Interval, interval};
-use tracing::{error, info, warn};
+use tokio_rustls::LazyConfigAcceptor;
+use tokio_rustls::server::TlsStream;
+use tracing::{debug, error, info, warn};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
 
 #[derive(Error, Debug)]
 pub enum SslError {
@@ -22,43 +40,238 @@ pub enum SslError {
     PrivateKeyNotFound { key_path: String },
     #[error("No valid certificates found in file")]
     NoCertificatesFound,
+    #[error("Certificate could not be parsed: {0}")]
+    InvalidCertificate(String),
     #[error("No valid private keys found in file")]
     NoPrivateKeysFound,
+    #[error("Client authentication misconfigured: {0}")]
+    ClientAuth(String),
+    #[error("ACME certificate provisioning failed: {0}")]
+    Acme(String),
+    #[error("Unknown cipher suite {name}; valid options: {valid}")]
+    UnknownCipherSuite { name: String, valid: String },
+    #[error("max_fragment_size {0} is outside the allowed range 32..=16389")]
+    InvalidMaxFragmentSize(usize),
+    #[error("Private key {key_path} does not belong to certificate {cert_path}")]
+    KeyCertMismatch { cert_path: String, key_path: String },
 }
 
+impl SslError {
+    /// Whether the certificate or key file doesn't exist (yet), as opposed
+    /// to existing but being unusable
+    pub fn is_missing_file(&self) -> bool {
+        matches!(
+            self,
+            Self::CertificateNotFound { .. } | Self::PrivateKeyNotFound { .. }
+        )
+    }
+}
+
+// Bounds rustls enforces on `ServerConfig::max_fragment_size`
+const MIN_FRAGMENT_SIZE: usize = 32;
+const MAX_FRAGMENT_SIZE: usize = 16389;
+
+#[cfg(not(any(feature = "aws-lc-rs", feature = "ring")))]
+compile_error!("enable the \"aws-lc-rs\" or \"ring\" feature to select a rustls crypto provider");
+
+/// Name of the rustls crypto backend selected at build time
+#[cfg(feature = "aws-lc-rs")]
+pub const CRYPTO_PROVIDER: &str = "aws-lc-rs";
+#[cfg(all(feature = "ring", not(feature = "aws-lc-rs")))]
+pub const CRYPTO_PROVIDER: &str = "ring";
+
+/// Builds the crypto provider selected by cargo features
+pub fn selected_crypto_provider() -> CryptoProvider {
+    #[cfg(feature = "aws-lc-rs")]
+    {
+        rustls::crypto::aws_lc_rs::default_provider()
+    }
+    #[cfg(all(feature = "ring", not(feature = "aws-lc-rs")))]
+    {
+        rustls::crypto::ring::default_provider()
+    }
+}
+
+/// Installs the selected provider as the process default unless one is
+/// already installed, and returns whichever is in effect
+pub fn install_crypto_provider() -> Arc<CryptoProvider> {
+    if let Some(provider) = CryptoProvider::get_default() {
+        return provider.clone();
+    }
+
+    // Losing a race with another thread installing the same provider is fine
+    if selected_crypto_provider().install_default().is_ok() {
+        info!("Installed {} rustls crypto provider", CRYPTO_PROVIDER);
+    }
+    CryptoProvider::get_default()
+        .cloned()
+        .expect("a crypto provider was just installed")
+}
+
+/// Looks up `names` (IANA names such as `TLS13_AES_256_GCM_SHA384`) among
+/// the provider's cipher suites, keeping the configured order
+pub fn select_cipher_suites(
+    provider: &CryptoProvider,
+    names: &[String],
+) -> Result<Vec<SupportedCipherSuite>, SslError> {
+    names
+        .iter()
+        .map(|name| {
+            provider
+                .cipher_suites
+                .iter()
+                .find(|suite| suite.suite().as_str() == Some(name.as_str()))
+                .copied()
+                .ok_or_else(|| SslError::UnknownCipherSuite {
+                    name: name.clone(),
+                    valid: provider
+                        .cipher_suites
+                        .iter()
+                        .filter_map(|suite| suite.suite().as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                })
+        })
+        .collect()
+}
+
+/// Drops repeated certificates and any self-signed root after the leaf.
+/// Clients must already trust the root, so sending it only wastes handshake
+/// bytes; with `strip` disabled the problems are only logged.
+pub fn sanitize_chain(
+    chain: Vec<CertificateDer<'static>>,
+    strip: bool,
+) -> Vec<CertificateDer<'static>> {
+    let mut sanitized: Vec<CertificateDer<'static>> = Vec::with_capacity(chain.len());
+
+    for (index, cert) in chain.into_iter().enumerate() {
+        if sanitized.contains(&cert) {
+            warn!("Certificate chain repeats certificate #{}", index);
+            if strip {
+                continue;
+            }
+        } else if index > 0 && is_self_signed(&cert) {
+            warn!(
+                "Certificate chain includes a self-signed root at position #{}",
+                index
+            );
+            if strip {
+                continue;
+            }
+        }
+        sanitized.push(cert);
+    }
+
+    sanitized
+}
+
+fn key_format(key: &PrivateKeyDer<'_>) -> &'static str {
+    match key {
+        PrivateKeyDer::Pkcs8(_) => "PKCS#8",
+        PrivateKeyDer::Pkcs1(_) => "PKCS#1 RSA",
+        PrivateKeyDer::Sec1(_) => "SEC1 EC",
+        _ => "unknown",
+    }
+}
+
+fn system_time_from_unix(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+fn is_self_signed(cert: &CertificateDer<'_>) -> bool {
+    X509Certificate::from_der(cert.as_ref())
+        .map(|(_, parsed)| parsed.subject().as_raw() == parsed.issuer().as_raw())
+        .unwrap_or(false)
+}
+
+/// SSL manager shared between the certificate monitor and admin endpoints;
+/// the lock serializes checks and reloads
+pub type SharedSslManager = Arc<Mutex<SslManager>>;
+
+/// Runs `check_certificate` every `period`, taking the lock only for the
+/// check itself so manual reloads are never blocked on the timer
+pub fn spawn_certificate_monitor(
+    ssl_manager: SharedSslManager,
+    period: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Starting certificate monitoring");
+        let mut check_interval = interval(period);
+        loop {
+            check_interval.tick().await;
+            ssl_manager.lock().await.check_certificate().await;
+        }
+    })
+}
+
+/// Latest certificate state, shared with request handlers
+pub type SharedCertificateInfo = Arc<RwLock<Option<CertificateInfo>>>;
+
 #[derive(Debug, Clone)]
 pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
     pub not_before: SystemTime,
     pub not_after: SystemTime,
     pub is_expired: bool,
     pub days_until_expiry: i64,
+    /// SHA-256 of the leaf certificate's DER bytes, as colon-separated hex
+    pub fingerprint_sha256: String,
+    /// DNS names from the subject alternative name extension
+    pub dns_names: Vec<String>,
 }
 
+impl CertificateInfo {
+    /// Whether one of the DNS names matches `host`; a `*.` wildcard covers
+    /// exactly one leftmost label
+    pub fn covers(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.');
+        self.dns_names
+            .iter()
+            .any(|name| match name.strip_prefix("*.") {
+                Some(parent) => host.split_once('.').is_some_and(|(label, rest)| {
+                    !label.is_empty() && rest.eq_ignore_ascii_case(parent)
+                }),
+                None => name.eq_ignore_ascii_case(host),
+            })
+    }
+}
+
+/// Server config that reloads swap in place, so acceptors already handed out
+/// serve the new certificates to subsequent handshakes
+pub type SharedServerConfig = Arc<ArcSwap<ServerConfig>>;
+
 pub struct SslManager {
-    config: Arc<ServerConfig>,
-    cert_path: std::path::PathBuf,
-    key_path: std::path::PathBuf,
-    cert_info: Option<CertificateInfo>,
+    config: SharedServerConfig,
+    ssl_config: SslConfig,
+    cert_info: SharedCertificateInfo,
+    sni_resolver: Option<Arc<SniResolver>>,
+    reload_count: u64,
     check_interval: Interval,
 }
 
 impl SslManager {
-    pub fn new(
-        cert_path: impl AsRef<Path>,
-        key_path: impl AsRef<Path>,
-        check_interval: Duration,
-    ) -> Result<Self, SslError> {
-        let cert_path = cert_path.as_ref().to_path_buf();
-        let key_path = key_path.as_ref().to_path_buf();
-
+    pub fn new(ssl_config: &SslConfig) -> Result<Self, SslError> {
         info!(
             "Loading SSL certificates from: {} and {}",
-            cert_path.display(),
-            key_path.display()
+            ssl_config.cert_path.display(),
+            ssl_config.key_path.display()
         );
 
-        let config = Self::load_certificates(&cert_path, &key_path)?;
-        let cert_info = Self::extract_certificate_info(&cert_path)?;
+        // Several hostnames share one listener through a swappable resolver
+        let sni_resolver = if ssl_config.sni_certs.is_empty() {
+            None
+        } else {
+            let (default, by_name) = Self::load_sni_certificates(ssl_config)?;
+            Some(Arc::new(SniResolver::new(default, by_name)))
+        };
+
+        let config = Self::load_certificates(ssl_config, sni_resolver.clone())?;
+        let cert_info = Self::extract_certificate_info(&ssl_config.cert_path)?;
 
         info!(
             "SSL certificates loaded successfully. Expires: {:?}",
@@ -66,15 +279,177 @@ impl SslManager {
         );
 
         Ok(Self {
-            config: Arc::new(config),
-            cert_path,
-            key_path,
-            cert_info: Some(cert_info),
-            check_interval: interval(check_interval),
+            config: Arc::new(ArcSwap::from_pointee(config)),
+            ssl_config: ssl_config.clone(),
+            cert_info: Arc::new(RwLock::new(Some(cert_info))),
+            sni_resolver,
+            reload_count: 0,
+            check_interval: interval(Duration::from_secs(ssl_config.cert_check_interval_secs)),
         })
     }
 
-    fn load_certificates(cert_path: &Path, key_path: &Path) -> Result<ServerConfig, SslError> {
+    /// Like `new`, but first orders a certificate when `ssl.acme` is set and
+    /// the one at `cert_path` is missing or due for renewal
+    pub async fn provision(ssl_config: &SslConfig) -> Result<Self, SslError> {
+        if let Some(acme) = &ssl_config.acme {
+            match Self::extract_certificate_info(&ssl_config.cert_path) {
+                Ok(cert_info) if cert_info.days_until_expiry >= acme.renew_before_days => {
+                    info!(
+                        "Cached ACME certificate expires in {} days",
+                        cert_info.days_until_expiry
+                    );
+                }
+                Ok(_) => Self::obtain_acme_certificate(ssl_config, acme).await?,
+                Err(e) => {
+                    info!("No usable ACME certificate cached ({})", e);
+                    Self::obtain_acme_certificate(ssl_config, acme).await?;
+                }
+            }
+        }
+        match &ssl_config.startup_retry {
+            Some(retry) => Self::new_with_retry(ssl_config, retry).await,
+            None => Self::new(ssl_config),
+        }
+    }
+
+    /// Like `new`, but waits out missing certificate or key files with
+    /// exponential backoff. Any other error fails at once.
+    async fn new_with_retry(
+        ssl_config: &SslConfig,
+        retry: &StartupRetryConfig,
+    ) -> Result<Self, SslError> {
+        let mut attempt = 1;
+        loop {
+            match Self::new(ssl_config) {
+                Err(e) if e.is_missing_file() && attempt < retry.max_attempts => {
+                    let delay = retry.delay(attempt);
+                    warn!(
+                        "{} (attempt {}/{}); retrying in {:?}",
+                        e, attempt, retry.max_attempts, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[cfg(feature = "acme")]
+    async fn obtain_acme_certificate(
+        ssl_config: &SslConfig,
+        acme: &AcmeConfig,
+    ) -> Result<(), SslError> {
+        crate::server::acme::obtain_certificate(ssl_config, acme).await
+    }
+
+    #[cfg(not(feature = "acme"))]
+    async fn obtain_acme_certificate(
+        _ssl_config: &SslConfig,
+        _acme: &AcmeConfig,
+    ) -> Result<(), SslError> {
+        Err(SslError::Acme(
+            "rebuild with the \"acme\" feature to order certificates".to_string(),
+        ))
+    }
+
+    fn load_certificates(
+        ssl_config: &SslConfig,
+        sni_resolver: Option<Arc<SniResolver>>,
+    ) -> Result<ServerConfig, SslError> {
+        // Configure TLS with modern defaults, narrowed to the configured
+        // cipher suites when there are any
+        let mut provider = install_crypto_provider();
+        if !ssl_config.cipher_suites.is_empty() {
+            let cipher_suites = select_cipher_suites(&provider, &ssl_config.cipher_suites)?;
+            info!("Restricting TLS to cipher suites: {:?}", cipher_suites);
+            provider = Arc::new(CryptoProvider {
+                cipher_suites,
+                ..(*provider).clone()
+            });
+        }
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+        let builder = match Self::client_verifier(&ssl_config.client_auth, provider)? {
+            Some(verifier) => builder.with_client_cert_verifier(verifier),
+            None => builder.with_no_client_auth(),
+        };
+        let mut config = match sni_resolver {
+            Some(resolver) => builder.with_cert_resolver(resolver),
+            None => {
+                let certified_key = Self::load_certified_key(
+                    &ssl_config.cert_path,
+                    &ssl_config.key_path,
+                    ssl_config,
+                )?;
+                builder.with_cert_resolver(Arc::new(SingleCertAndKey::from(certified_key)))
+            }
+        };
+
+        config.alpn_protocols = ssl_config
+            .alpn
+            .iter()
+            .map(|protocol| protocol.as_bytes().to_vec())
+            .collect();
+
+        // Smaller records shrink per-connection buffers at the cost of more
+        // framing overhead on large responses
+        if let Some(size) = ssl_config.max_fragment_size {
+            if !(MIN_FRAGMENT_SIZE..=MAX_FRAGMENT_SIZE).contains(&size) {
+                return Err(SslError::InvalidMaxFragmentSize(size));
+            }
+            config.max_fragment_size = Some(size);
+        }
+
+        Ok(config)
+    }
+
+    fn client_verifier(
+        client_auth: &ClientAuthConfig,
+        provider: Arc<CryptoProvider>,
+    ) -> Result<Option<Arc<dyn ClientCertVerifier>>, SslError> {
+        // Gating metrics on a certificate needs clients to be able to offer
+        // one, even when the listener doesn't otherwise ask for it
+        let mode = match client_auth.mode {
+            ClientAuthMode::None if client_auth.require_for_metrics => ClientAuthMode::Optional,
+            mode => mode,
+        };
+        if mode == ClientAuthMode::None {
+            return Ok(None);
+        }
+
+        let ca_path = client_auth.ca_path.as_ref().ok_or_else(|| {
+            SslError::ClientAuth("ssl.client_auth.ca_path is required".to_string())
+        })?;
+        let ca_file = File::open(ca_path).map_err(|_| SslError::CertificateNotFound {
+            cert_path: ca_path.display().to_string(),
+        })?;
+        let mut roots = RootCertStore::empty();
+        for cert in certs(&mut BufReader::new(ca_file)) {
+            roots.add(cert?)?;
+        }
+
+        let mut builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+        if mode == ClientAuthMode::Optional {
+            builder = builder.allow_unauthenticated();
+        }
+        info!(
+            "Client certificate verification {:?} against {}",
+            mode,
+            ca_path.display()
+        );
+
+        builder
+            .build()
+            .map(Some)
+            .map_err(|e| SslError::ClientAuth(e.to_string()))
+    }
+
+    fn load_key_pair(
+        cert_path: &Path,
+        key_path: &Path,
+        ssl_config: &SslConfig,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), SslError> {
         // Load certificate chain
         let cert_file = File::open(cert_path).map_err(|_| SslError::CertificateNotFound {
             cert_path: cert_path.display().to_string(),
@@ -86,56 +461,139 @@ impl SslManager {
         if cert_chain.is_empty() {
             return Err(SslError::NoCertificatesFound);
         }
+        let cert_chain = sanitize_chain(cert_chain, ssl_config.strip_redundant_chain_certs);
 
         // Load private key
         let key_file = File::open(key_path).map_err(|_| SslError::PrivateKeyNotFound {
             key_path: key_path.display().to_string(),
         })?;
         let mut key_reader = BufReader::new(key_file);
+        // The first key in the file wins, whichever PEM encoding it uses
         let private_key: PrivateKeyDer =
             private_key(&mut key_reader)?.ok_or(SslError::NoPrivateKeysFound)?;
+        debug!(
+            "Loaded {} private key from {}",
+            key_format(&private_key),
+            key_path.display()
+        );
 
-        // Configure TLS with modern defaults
-        let config = ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(cert_chain, private_key)?;
+        Ok((cert_chain, private_key))
+    }
 
-        Ok(config)
+    /// Loads a certificate chain and its key, refusing a key that belongs to
+    /// another certificate rather than failing handshakes later
+    fn load_certified_key(
+        cert_path: &Path,
+        key_path: &Path,
+        ssl_config: &SslConfig,
+    ) -> Result<CertifiedKey, SslError> {
+        let (cert_chain, private_key) = Self::load_key_pair(cert_path, key_path, ssl_config)?;
+        let provider = install_crypto_provider();
+        let signing_key = provider.key_provider.load_private_key(private_key)?;
+        let certified_key = CertifiedKey::new(cert_chain, signing_key);
+        match certified_key.keys_match() {
+            // Providers that can't expose the public key get the benefit of the doubt
+            Ok(()) | Err(rustls::Error::InconsistentKeys(InconsistentKeys::Unknown)) => {
+                Ok(certified_key)
+            }
+            Err(rustls::Error::InconsistentKeys(InconsistentKeys::KeyMismatch)) => {
+                Err(SslError::KeyCertMismatch {
+                    cert_path: cert_path.display().to_string(),
+                    key_path: key_path.display().to_string(),
+                })
+            }
+            Err(e) => Err(e.into()),
+        }
     }
 
-    fn extract_certificate_info(cert_path: &Path) -> Result<CertificateInfo, SslError> {
-        // This is a simplified version - in production you'd parse the X.509 certificate
-        // For now, we'll just check file modification time as a proxy
-        let metadata = std::fs::metadata(cert_path)?;
-        let modified = metadata.modified()?;
+    /// Loads the fallback certificate and every `ssl.sni_certs` entry
+    fn load_sni_certificates(
+        ssl_config: &SslConfig,
+    ) -> Result<(Arc<CertifiedKey>, SniCertificateMap), SslError> {
+        let default = Arc::new(Self::load_certified_key(
+            &ssl_config.cert_path,
+            &ssl_config.key_path,
+            ssl_config,
+        )?);
 
-        // Let's Encrypt certificates are valid for 90 days
-        let expires_in = Duration::from_secs(90 * 24 * 60 * 60);
-        let not_after = modified + expires_in;
+        let mut by_name = SniCertificateMap::new();
+        for entry in &ssl_config.sni_certs {
+            info!(
+                "Loading SNI certificate for {} from {}",
+                entry.hostname,
+                entry.cert_path.display()
+            );
+            let key = Self::load_certified_key(&entry.cert_path, &entry.key_path, ssl_config)?;
+            by_name.insert(entry.hostname.clone(), Arc::new(key));
+        }
 
-        let now = SystemTime::now();
-        let is_expired = now > not_after;
+        Ok((default, by_name))
+    }
 
-        let days_until_expiry = if let Ok(duration) = not_after.duration_since(now) {
-            duration.as_secs() as i64 / (24 * 60 * 60)
-        } else {
-            -1 // Expired
-        };
+    fn extract_certificate_info(cert_path: &Path) -> Result<CertificateInfo, SslError> {
+        let mut cert_reader = BufReader::new(File::open(cert_path)?);
+        let leaf = certs(&mut cert_reader)
+            .next()
+            .ok_or(SslError::NoCertificatesFound)??;
+
+        let (_, cert) = X509Certificate::from_der(leaf.as_ref())
+            .map_err(|e| SslError::InvalidCertificate(e.to_string()))?;
+        let validity = cert.validity();
+        let not_before = validity.not_before.timestamp();
+        let not_after = validity.not_after.timestamp();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
 
         Ok(CertificateInfo {
-            not_before: modified,
-            not_after,
-            is_expired,
-            days_until_expiry,
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            not_before: system_time_from_unix(not_before),
+            not_after: system_time_from_unix(not_after),
+            is_expired: now > not_after,
+            // Rounds down, so a certificate that lapsed an hour ago reports -1
+            days_until_expiry: (not_after - now).div_euclid(24 * 60 * 60),
+            fingerprint_sha256: fingerprint_sha256(leaf.as_ref()),
+            dns_names: dns_names(&cert),
         })
     }
 
+    pub fn sni_resolver(&self) -> Option<Arc<SniResolver>> {
+        self.sni_resolver.clone()
+    }
+
     pub fn get_config(&self) -> Arc<ServerConfig> {
-        self.config.clone()
+        self.config.load_full()
+    }
+
+    /// Successful reloads since startup
+    pub fn reload_count(&self) -> u64 {
+        self.reload_count
     }
 
-    pub fn get_certificate_info(&self) -> Option<&CertificateInfo> {
-        self.cert_info.as_ref()
+    /// Builds an acceptor that applies the per-connection TLS buffer limit and
+    /// SNI requirement
+    pub fn acceptor(&self) -> TlsAcceptor {
+        let mut acceptor =
+            TlsAcceptor::from_shared(self.config.clone(), self.ssl_config.buffer_limit_bytes);
+        acceptor.set_require_sni(self.ssl_config.require_sni);
+        acceptor
+    }
+
+    pub fn get_certificate_info(&self) -> Option<CertificateInfo> {
+        self.cert_info.read().ok().and_then(|info| info.clone())
+    }
+
+    pub fn certificate_info_handle(&self) -> SharedCertificateInfo {
+        self.cert_info.clone()
+    }
+
+    fn store_certificate_info(&self, cert_info: CertificateInfo) {
+        if let Ok(mut current) = self.cert_info.write() {
+            *current = Some(cert_info);
+        }
     }
 
     pub async fn start_certificate_monitoring(&mut self) {
@@ -143,42 +601,248 @@ impl SslManager {
 
         loop {
             self.check_interval.tick().await;
+            self.check_certificate().await;
+        }
+    }
 
-            match Self::extract_certificate_info(&self.cert_path) {
-                Ok(cert_info) => {
-                    if cert_info.is_expired {
-                        error!("Certificate has expired!");
-                    } else if cert_info.days_until_expiry <= 7 {
-                        warn!(
-                            "Certificate expires in {} days",
-                            cert_info.days_until_expiry
-                        );
-                    } else {
-                        info!(
-                            "Certificate is valid, expires in {} days",
-                            cert_info.days_until_expiry
-                        );
-                    }
-
-                    self.cert_info = Some(cert_info);
+    /// One monitoring pass: refresh the certificate info and reload if the
+    /// served certificate has expired
+    pub async fn check_certificate(&mut self) {
+        match Self::extract_certificate_info(&self.ssl_config.cert_path) {
+            Ok(cert_info) => {
+                if cert_info.is_expired {
+                    error!("Certificate has expired!");
+                } else if cert_info.days_until_expiry <= 7 {
+                    warn!(
+                        "Certificate expires in {} days",
+                        cert_info.days_until_expiry
+                    );
+                } else {
+                    info!(
+                        "Certificate is valid, expires in {} days",
+                        cert_info.days_until_expiry
+                    );
                 }
-                Err(e) => {
-                    error!("Failed to check certificate: {}", e);
+
+                let expired = cert_info.is_expired;
+                let renew = self
+                    .ssl_config
+                    .acme
+                    .as_ref()
+                    .is_some_and(|acme| cert_info.days_until_expiry < acme.renew_before_days);
+                self.store_certificate_info(cert_info);
+
+                if renew {
+                    if let Err(e) = self.renew_acme_certificate().await {
+                        error!("Failed to renew ACME certificate: {}", e);
+                    }
+                } else if expired && let Err(e) = self.reload_certificates().await {
+                    // A renewed certificate may already be on disk
+                    error!("Failed to reload expired certificate: {}", e);
                 }
             }
+            Err(e) => {
+                error!("Failed to check certificate: {}", e);
+            }
         }
     }
 
+    /// Orders a fresh certificate from the ACME CA and serves it
+    pub async fn renew_acme_certificate(&mut self) -> Result<(), SslError> {
+        let acme = self
+            .ssl_config
+            .acme
+            .clone()
+            .ok_or_else(|| SslError::Acme("ssl.acme is not configured".to_string()))?;
+        Self::obtain_acme_certificate(&self.ssl_config, &acme).await?;
+        self.reload_certificates().await
+    }
+
     pub async fn reload_certificates(&mut self) -> Result<(), SslError> {
         info!("Reloading SSL certificates");
 
-        let new_config = Self::load_certificates(&self.cert_path, &self.key_path)?;
-        let new_cert_info = Self::extract_certificate_info(&self.cert_path)?;
+        // Load everything before swapping anything, so a bad file leaves the
+        // current certificates in place
+        let sni_certificates = match &self.sni_resolver {
+            Some(_) => Some(Self::load_sni_certificates(&self.ssl_config)?),
+            None => None,
+        };
+        let new_config = Self::load_certificates(&self.ssl_config, self.sni_resolver.clone())?;
+        let new_cert_info = Self::extract_certificate_info(&self.ssl_config.cert_path)?;
 
-        self.config = Arc::new(new_config);
-        self.cert_info = Some(new_cert_info);
+        if let (Some(resolver), Some((default, by_name))) = (&self.sni_resolver, sni_certificates) {
+            resolver.replace(default, by_name);
+        }
+
+        if new_cert_info.is_expired {
+            warn!("Reloaded certificate is still expired");
+        }
+
+        // Lets operators confirm a rotation really changed the certificate
+        match self.get_certificate_info() {
+            Some(old) if old.fingerprint_sha256 == new_cert_info.fingerprint_sha256 => info!(
+                "Reloaded certificate is unchanged (SHA-256 {})",
+                new_cert_info.fingerprint_sha256
+            ),
+            _ => info!(
+                "Now serving certificate with SHA-256 fingerprint {}",
+                new_cert_info.fingerprint_sha256
+            ),
+        }
+
+        self.config.store(Arc::new(new_config));
+        self.reload_count += 1;
+        self.store_certificate_info(new_cert_info);
 
         info!("SSL certificates reloaded successfully");
         Ok(())
     }
 }
+
+fn dns_names(cert: &X509Certificate) -> Vec<String> {
+    let Ok(Some(san)) = cert.subject_alternative_name() else {
+        return Vec::new();
+    };
+    san.value
+        .general_names
+        .iter()
+        .filter_map(|name| match name {
+            GeneralName::DNSName(name) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Uppercase hex bytes joined by colons, as `openssl x509 -fingerprint` prints
+fn fingerprint_sha256(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Identity from a verified client certificate, attached to each request's
+/// extensions when mutual TLS is enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertInfo {
+    pub subject_cn: Option<String>,
+}
+
+impl ClientCertInfo {
+    /// Reads the leaf certificate the client presented, if any
+    pub fn from_connection(conn: &ServerConnection) -> Option<Self> {
+        let leaf = conn.peer_certificates()?.first()?;
+        let (_, cert) = X509Certificate::from_der(leaf.as_ref()).ok()?;
+        let subject_cn = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+        Some(Self { subject_cn })
+    }
+}
+
+/// Application protocol agreed through ALPN, attached to each request's
+/// extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedAlpn(pub String);
+
+impl NegotiatedAlpn {
+    pub fn from_connection(conn: &ServerConnection) -> Option<Self> {
+        conn.alpn_protocol()
+            .map(|protocol| Self(String::from_utf8_lossy(protocol).into_owned()))
+    }
+}
+
+/// Protocol version and cipher suite the handshake settled on, attached to
+/// each request's extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedTls {
+    pub version: String,
+    pub cipher_suite: String,
+}
+
+impl NegotiatedTls {
+    pub fn from_connection(conn: &ServerConnection) -> Option<Self> {
+        let version = match conn.protocol_version()? {
+            ProtocolVersion::TLSv1_3 => "1.3".to_string(),
+            ProtocolVersion::TLSv1_2 => "1.2".to_string(),
+            other => format!("{other:?}"),
+        };
+        let suite = conn.negotiated_cipher_suite()?.suite();
+        Some(Self {
+            version,
+            cipher_suite: suite
+                .as_str()
+                .map_or_else(|| format!("{suite:?}"), str::to_string),
+        })
+    }
+}
+
+/// Handshake refused because the ClientHello carried no SNI while
+/// `ssl.require_sni` is set
+#[derive(Error, Debug)]
+#[error("client did not send SNI")]
+pub struct MissingSni;
+
+/// `tokio_rustls::TlsAcceptor` plus the connection tuning rustls only exposes
+/// per connection
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    config: SharedServerConfig,
+    buffer_limit: Option<usize>,
+    require_sni: bool,
+}
+
+impl TlsAcceptor {
+    pub fn new(config: Arc<ServerConfig>, buffer_limit: Option<usize>) -> Self {
+        Self::from_shared(Arc::new(ArcSwap::new(config)), buffer_limit)
+    }
+
+    /// Acceptor that picks up whatever config is current at each handshake
+    pub fn from_shared(config: SharedServerConfig, buffer_limit: Option<usize>) -> Self {
+        Self {
+            config,
+            buffer_limit,
+            require_sni: false,
+        }
+    }
+
+    pub fn set_require_sni(&mut self, required: bool) {
+        self.require_sni = required;
+    }
+
+    pub fn buffer_limit(&self) -> Option<usize> {
+        self.buffer_limit
+    }
+
+    pub async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let buffer_limit = self.buffer_limit;
+        // rustls defaults to 64 KiB; lower limits trade throughput for
+        // memory when many connections sit idle
+        let tune = move |conn: &mut ServerConnection| {
+            if let Some(limit) = buffer_limit {
+                conn.set_buffer_limit(Some(limit));
+            }
+        };
+
+        let config = self.config.load_full();
+        if !self.require_sni {
+            return tokio_rustls::TlsAcceptor::from(config)
+                .accept_with(stream, tune)
+                .await;
+        }
+
+        // Peek at the ClientHello before committing to a handshake
+        let start = LazyConfigAcceptor::new(Acceptor::default(), stream).await?;
+        if start.client_hello().server_name().is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, MissingSni));
+        }
+        start.into_stream_with(config, tune).await
+    }
+}
@@ -0,0 +1,119 @@
+use crate::server::watchdog::HandshakeWatchdog;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long each startup phase took, for diagnosing slow boots
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StartupTimings {
+    pub config_load: Duration,
+    pub cert_load: Duration,
+    pub listener_bind: Duration,
+}
+
+impl StartupTimings {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "config_load_seconds": self.config_load.as_secs_f64(),
+            "cert_load_seconds": self.cert_load.as_secs_f64(),
+            "listener_bind_seconds": self.listener_bind.as_secs_f64(),
+        })
+    }
+}
+
+/// When the server started. Uptime is measured on the monotonic clock, so
+/// wall-clock jumps (NTP steps, manual changes) don't skew it.
+#[derive(Debug, Clone, Copy)]
+pub struct StartTime {
+    started_at: SystemTime,
+    started: Instant,
+}
+
+impl StartTime {
+    pub fn now() -> Self {
+        Self {
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Wall-clock start time, for display and `process_start_time_seconds`
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Seconds since the Unix epoch at startup
+    pub fn unix_seconds(&self) -> f64 {
+        self.started_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+}
+
+impl Default for StartTime {
+    fn default() -> Self {
+        Self::now()
+    }
+}
+
+/// Listener-level counters shared between the accept loop and `/metrics`
+#[derive(Debug, Default)]
+pub struct ServerStats {
+    pub rejected_connections: AtomicU64,
+    pub slow_client_drops: AtomicU64,
+    pub handshake_failures: AtomicU64,
+    pub sni_rejections: AtomicU64,
+    // TLS clients that ended the session with close_notify vs. just hung up
+    pub clean_close: AtomicU64,
+    pub abrupt_close: AtomicU64,
+    // Gauges for connection cap saturation
+    pub connection_permits_in_use: AtomicUsize,
+    pub max_connections: AtomicUsize,
+    pub handshake_watchdog: HandshakeWatchdog,
+    startup: OnceLock<StartupTimings>,
+    start_time: StartTime,
+}
+
+impl ServerStats {
+    pub fn new(handshake_watchdog: HandshakeWatchdog) -> Self {
+        Self {
+            handshake_watchdog,
+            ..Default::default()
+        }
+    }
+
+    /// Records the startup phase durations; only the first call takes effect
+    pub fn record_startup(&self, timings: StartupTimings) {
+        let _ = self.startup.set(timings);
+    }
+
+    pub fn startup(&self) -> Option<StartupTimings> {
+        self.startup.get().copied()
+    }
+
+    pub fn start_time(&self) -> StartTime {
+        self.start_time
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let (failure_rate, alert) = self.handshake_watchdog.snapshot();
+        serde_json::json!({
+            "rejected_connections": self.rejected_connections.load(Ordering::Relaxed),
+            "slow_client_drops": self.slow_client_drops.load(Ordering::Relaxed),
+            "handshake_failures": self.handshake_failures.load(Ordering::Relaxed),
+            "sni_rejections": self.sni_rejections.load(Ordering::Relaxed),
+            "clean_close": self.clean_close.load(Ordering::Relaxed),
+            "abrupt_close": self.abrupt_close.load(Ordering::Relaxed),
+            "connection_permits_in_use": self.connection_permits_in_use.load(Ordering::Relaxed),
+            "max_connections": self.max_connections.load(Ordering::Relaxed),
+            "handshake_failure_rate": failure_rate,
+            "handshake_failure_alert": alert,
+            "startup": self.startup().map(|timings| timings.to_json()),
+        })
+    }
+}
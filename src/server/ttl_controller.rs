@@ -1,8 +1,20 @@
+use crate::server::rate_limit::RateLimiter;
+use crate::server::snapshot::{ConnectionRecord, ConnectionSnapshot, StatsSnapshot, unix_now};
+use crate::server::ttl_strategy::{AdaptiveTtlStrategy, TtlContext, TtlStrategy};
+use crate::utils::cidr::IpCidr;
+use crate::utils::config::TtlBand;
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
+use hyper::Version;
+use std::collections::{HashSet, VecDeque};
+use std::io;
 use std::net::IpAddr;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::time::{Interval, interval};
+use tokio::sync::mpsc;
+use tokio::time::{interval, interval_at};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
@@ -10,32 +22,58 @@ use uuid::Uuid;
 pub struct ConnectionInfo {
     pub id: Uuid,
     pub ip: IpAddr,
+    pub peer_ip: IpAddr,
     pub established_at: Instant,
     pub last_activity: Instant,
     pub ttl: Duration,
     pub request_count: u64,
+    pub protocol: Version,
+    /// Timestamps of the most recent requests, oldest first
+    pub history: VecDeque<Instant>,
+    history_limit: usize,
 }
 
+/// Requests remembered per connection unless configured otherwise
+pub const DEFAULT_HISTORY_LIMIT: usize = 32;
+
+/// Bytes one remembered request occupies in a history buffer
+pub const HISTORY_ENTRY_BYTES: usize = std::mem::size_of::<Instant>();
+
 impl ConnectionInfo {
     pub fn new(ip: IpAddr, ttl: Duration) -> Self {
         let now = Instant::now();
         Self {
             id: Uuid::new_v4(),
             ip,
+            peer_ip: ip,
             established_at: now,
             last_activity: now,
             ttl,
             request_count: 1,
+            protocol: Version::HTTP_11,
+            history: VecDeque::from([now]),
+            history_limit: DEFAULT_HISTORY_LIMIT,
         }
     }
 
+    pub fn history_limit(&self) -> usize {
+        self.history_limit
+    }
+
     pub fn is_expired(&self) -> bool {
         self.last_activity.elapsed() > self.ttl
     }
 
     pub fn update_activity(&mut self) {
-        self.last_activity = Instant::now();
-        self.request_count += 1;
+        let now = Instant::now();
+        self.last_activity = now;
+        self.request_count = self.request_count.saturating_add(1);
+
+        // History stays bounded no matter how large request_count grows
+        while self.history.len() >= self.history_limit.max(1) {
+            self.history.pop_front();
+        }
+        self.history.push_back(now);
     }
 
     pub fn time_until_expiry(&self) -> Option<Duration> {
@@ -48,137 +86,640 @@ impl ConnectionInfo {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    Registered,
+    Expired,
+    ForceRemoved,
+}
+
+impl ConnectionEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionEventKind::Registered => "registered",
+            ConnectionEventKind::Expired => "expired",
+            ConnectionEventKind::ForceRemoved => "force_removed",
+        }
+    }
+}
+
+/// Lifecycle notification for integrations such as audit logs
+#[derive(Debug, Clone)]
+pub struct ConnectionEvent {
+    pub kind: ConnectionEventKind,
+    pub ip: IpAddr,
+    pub connection_id: Uuid,
+    pub request_count: u64,
+    pub lifetime: Duration,
+}
+
+impl ConnectionEvent {
+    fn from_connection(kind: ConnectionEventKind, connection: &ConnectionInfo) -> Self {
+        Self {
+            kind,
+            ip: connection.ip,
+            connection_id: connection.id,
+            request_count: connection.request_count,
+            lifetime: connection.established_at.elapsed(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TtlStats {
     pub active_connections: usize,
+    /// Distinct peer addresses behind the active connections; lower than
+    /// `active_connections` when forwarded clients share a proxy
+    pub distinct_ips: usize,
     pub total_connections: u64,
     pub expired_connections: u64,
     pub average_ttl_secs: u64,
+    pub http1_connections: usize,
+    pub http2_connections: usize,
+    /// `active_connections` and `total_connections` by client address family
+    pub active_connections_v4: usize,
+    pub active_connections_v6: usize,
+    pub total_connections_v4: u64,
+    pub total_connections_v6: u64,
+}
+
+/// The TTL settings a config reload can change on a running controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlLimits {
+    pub default_ttl: Duration,
+    pub max_ttl: Duration,
+    pub cleanup_interval: Duration,
 }
 
 pub struct TtlController {
     connections: Arc<DashMap<IpAddr, ConnectionInfo>>,
-    default_ttl: Duration,
-    max_ttl: Duration,
-    total_connections: u64,
-    expired_connections: u64,
-    cleanup_interval: Interval,
+    // Swapped whole so a reload never pairs an old default with a new max
+    limits: ArcSwap<TtlLimits>,
+    total_connections: AtomicU64,
+    total_connections_v4: AtomicU64,
+    total_connections_v6: AtomicU64,
+    expired_connections: AtomicU64,
+    // Cap on tracked connections; 0 is unlimited
+    max_connections: AtomicUsize,
+    // Held while a new IP evicts and inserts, so two can't both take the
+    // last free slot
+    admission: Mutex<()>,
+    event_sender: Option<mpsc::Sender<ConnectionEvent>>,
+    history_limit: usize,
+    // Cap on history entries across all connections; 0 is unlimited
+    history_budget: usize,
+    history_entries: AtomicUsize,
+    ttl_strategy: Arc<dyn TtlStrategy>,
+    ttl_bands: Vec<TtlBand>,
+    // Networks whose connections are never expired
+    never_expire: Vec<IpCidr>,
+    reconnect_grace: Duration,
+    // When each IP's last served connection closed, kept for the grace window
+    recently_closed: DashMap<IpAddr, Instant>,
+    // Per-IP request buckets, dropped along with the client's connection
+    rate_limiter: OnceLock<Arc<RateLimiter>>,
+    // Where the cumulative counters are persisted, if anywhere
+    stats_path: Option<PathBuf>,
+    // When the cleanup task last woke up; None until it first does
+    last_cleanup_tick: Mutex<Option<Instant>>,
 }
 
 impl TtlController {
-    pub fn new(default_ttl: Duration, max_ttl: Duration, cleanup_interval: Duration) -> Self {
+    /// Once `max_connections` IPs are tracked, registering a new one evicts
+    /// the least recently active; 0 tracks without bound
+    pub fn new(
+        default_ttl: Duration,
+        max_ttl: Duration,
+        cleanup_interval: Duration,
+        max_connections: usize,
+    ) -> Self {
         info!(
-            "Initializing TTL controller with default TTL: {:?}, max TTL: {:?}",
-            default_ttl, max_ttl
+            "Initializing TTL controller with default TTL: {:?}, max TTL: {:?}, max connections: {}",
+            default_ttl, max_ttl, max_connections
         );
 
         Self {
             connections: Arc::new(DashMap::new()),
-            default_ttl,
-            max_ttl,
-            total_connections: 0,
-            expired_connections: 0,
-            cleanup_interval: interval(cleanup_interval),
+            limits: ArcSwap::from_pointee(TtlLimits {
+                default_ttl,
+                max_ttl,
+                cleanup_interval,
+            }),
+            total_connections: AtomicU64::new(0),
+            total_connections_v4: AtomicU64::new(0),
+            total_connections_v6: AtomicU64::new(0),
+            expired_connections: AtomicU64::new(0),
+            max_connections: AtomicUsize::new(max_connections),
+            admission: Mutex::new(()),
+            event_sender: None,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            history_budget: 0,
+            history_entries: AtomicUsize::new(0),
+            ttl_strategy: Arc::new(AdaptiveTtlStrategy::default()),
+            ttl_bands: Vec::new(),
+            never_expire: Vec::new(),
+            reconnect_grace: Duration::ZERO,
+            recently_closed: DashMap::new(),
+            rate_limiter: OnceLock::new(),
+            stats_path: None,
+            last_cleanup_tick: Mutex::new(None),
         }
     }
 
-    pub fn register_connection(&mut self, ip: IpAddr) -> Uuid {
+    pub fn limits(&self) -> TtlLimits {
+        **self.limits.load()
+    }
+
+    /// Applies new TTL bounds to connections registered from now on;
+    /// tracked connections keep the TTL they were given. A new cleanup
+    /// interval takes effect after the next tick.
+    pub fn set_limits(&self, limits: TtlLimits) {
+        self.limits.store(Arc::new(limits));
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.max_connections.load(Ordering::Relaxed)
+    }
+
+    /// Changes the cap, e.g. on config reload. Tracked connections over a
+    /// lowered cap are evicted as new IPs register.
+    pub fn set_max_connections(&self, max: usize) {
+        self.max_connections.store(max, Ordering::Relaxed);
+    }
+
+    /// Bands are checked in order; the first one containing an IP applies
+    pub fn set_ttl_bands(&mut self, bands: Vec<TtlBand>) {
+        self.ttl_bands = bands;
+    }
+
+    /// Connections from these networks outlive their TTL until removed by
+    /// hand, e.g. for monitoring hosts on the access control allowlist
+    pub fn set_never_expire(&mut self, networks: Vec<IpCidr>) {
+        self.never_expire = networks;
+    }
+
+    /// TTL cleanup leaves an IP tracked until `grace` after its last served
+    /// connection closed, so a quick reconnect resumes the same entry
+    /// instead of counting a new one. Zero disables.
+    pub fn set_reconnect_grace(&mut self, grace: Duration) {
+        self.reconnect_grace = grace;
+    }
+
+    /// Resumes `total_connections` and `expired_connections` from `path` and
+    /// keeps it updated on every cleanup tick. A missing or unreadable file
+    /// starts the counters from zero.
+    pub fn set_stats_path(&mut self, path: PathBuf) {
+        match StatsSnapshot::load(&path) {
+            Ok(stats) => {
+                self.total_connections
+                    .store(stats.total_connections, Ordering::Relaxed);
+                self.total_connections_v4
+                    .store(stats.total_connections_v4, Ordering::Relaxed);
+                self.total_connections_v6
+                    .store(stats.total_connections_v6, Ordering::Relaxed);
+                self.expired_connections
+                    .store(stats.expired_connections, Ordering::Relaxed);
+                info!(
+                    "Resumed TTL stats from {}: {} total, {} expired",
+                    path.display(),
+                    stats.total_connections,
+                    stats.expired_connections
+                );
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!("No TTL stats at {}; counting from zero", path.display());
+            }
+            Err(e) => warn!(
+                "Ignoring unreadable TTL stats {}, counting from zero: {}",
+                path.display(),
+                e
+            ),
+        }
+        self.stats_path = Some(path);
+    }
+
+    pub fn stats_snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            saved_at: unix_now(),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            total_connections_v4: self.total_connections_v4.load(Ordering::Relaxed),
+            total_connections_v6: self.total_connections_v6.load(Ordering::Relaxed),
+            expired_connections: self.expired_connections.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Writes the counters to the configured stats path; a no-op without one
+    pub fn persist_stats(&self) -> io::Result<()> {
+        match &self.stats_path {
+            Some(path) => self.stats_snapshot().write_atomic(path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_ttl_strategy(&mut self, strategy: Arc<dyn TtlStrategy>) {
+        self.ttl_strategy = strategy;
+    }
+
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+    }
+
+    /// Caps the memory held by all history buffers together. Once exceeded,
+    /// the histories of the longest-established connections are dropped.
+    /// Zero disables the cap.
+    pub fn set_history_budget_bytes(&mut self, bytes: usize) {
+        self.history_budget = bytes / HISTORY_ENTRY_BYTES;
+    }
+
+    /// Memory currently held by history buffers across all connections
+    pub fn history_bytes(&self) -> usize {
+        self.history_entries.load(Ordering::Relaxed) * HISTORY_ENTRY_BYTES
+    }
+
+    fn track_history(&self, before: usize, after: usize) {
+        if after >= before {
+            self.history_entries
+                .fetch_add(after - before, Ordering::Relaxed);
+        } else {
+            self.history_entries
+                .fetch_sub(before - after, Ordering::Relaxed);
+        }
+    }
+
+    /// Clears the oldest connections' histories, other than `current`'s,
+    /// until the total is back under the budget. Must not be called while
+    /// holding a map entry.
+    fn enforce_history_budget(&self, current: IpAddr) {
+        if self.history_budget == 0
+            || self.history_entries.load(Ordering::Relaxed) <= self.history_budget
+        {
+            return;
+        }
+
+        // Evict to a tenth below the budget so steady traffic doesn't pay
+        // for a sort on every request
+        let target = self.history_budget - self.history_budget / 10;
+        let mut oldest: Vec<(Instant, IpAddr)> = self
+            .connections
+            .iter()
+            .filter(|entry| *entry.key() != current && !entry.history.is_empty())
+            .map(|entry| (entry.established_at, *entry.key()))
+            .collect();
+        oldest.sort_unstable();
+
+        let mut evicted = 0;
+        for (_, ip) in oldest {
+            if self.history_entries.load(Ordering::Relaxed) <= target {
+                break;
+            }
+            if let Some(mut connection) = self.connections.get_mut(&ip) {
+                self.track_history(connection.history.len(), 0);
+                connection.history = VecDeque::new();
+                evicted += 1;
+            }
+        }
+        debug!(
+            "History budget exceeded; dropped {} connection histories",
+            evicted
+        );
+    }
+
+    /// Keeps rate limit buckets next to the connections, so a client's
+    /// bucket goes when its connection expires. Only the first call's
+    /// limits take effect; later callers share that limiter.
+    pub fn attach_rate_limiter(&self, requests_per_sec: f64, burst: u32) -> Arc<RateLimiter> {
+        self.rate_limiter
+            .get_or_init(|| Arc::new(RateLimiter::new(requests_per_sec, burst)))
+            .clone()
+    }
+
+    /// The limiter attached by `attach_rate_limiter`, if any
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.rate_limiter.get().cloned()
+    }
+
+    fn forget_rate_limit(&self, ip: IpAddr) {
+        if let Some(rate_limiter) = self.rate_limiter.get() {
+            rate_limiter.forget(ip);
+        }
+    }
+
+    /// Streams registrations and removals (expiry, eviction, forced) to an
+    /// integration. Events are dropped with a warning while the channel is
+    /// full; without a sender none are built.
+    pub fn set_event_sender(&mut self, sender: mpsc::Sender<ConnectionEvent>) {
+        self.event_sender = Some(sender);
+    }
+
+    fn emit_event(&self, kind: ConnectionEventKind, connection: &ConnectionInfo) {
+        let Some(sender) = &self.event_sender else {
+            return;
+        };
+
+        // Never stall connection bookkeeping on a slow consumer
+        if let Err(e) = sender.try_send(ConnectionEvent::from_connection(kind, connection)) {
+            warn!(
+                "Dropped {} event for IP: {}: {}",
+                kind.as_str(),
+                connection.ip,
+                e
+            );
+        }
+    }
+
+    pub fn register_connection(&self, ip: IpAddr) -> Uuid {
+        self.register_forwarded_connection(ip, ip)
+    }
+
+    /// Registers `ip` as seen through `peer_ip`, the address that actually
+    /// connected (a proxy when the client was forwarded)
+    pub fn register_forwarded_connection(&self, ip: IpAddr, peer_ip: IpAddr) -> Uuid {
         // Calculate adaptive TTL based on existing connection patterns
         let ttl = self.calculate_adaptive_ttl(ip);
 
-        let connection = ConnectionInfo::new(ip, ttl);
+        let mut connection = ConnectionInfo::new(ip, ttl);
+        connection.history_limit = self.history_limit;
+        connection.peer_ip = peer_ip;
         let connection_id = connection.id;
 
+        // Before taking the entry: scanning while holding its shard deadlocks
+        let max_connections = self.max_connections();
+        let admission = (max_connections > 0 && !self.connections.contains_key(&ip)).then(|| {
+            let admission = self.admission.lock().unwrap_or_else(|e| e.into_inner());
+            self.evict_lru_if_full();
+            admission
+        });
+
         // Update existing connection or insert new one
-        match self.connections.entry(ip) {
+        let connection_id = match self.connections.entry(ip) {
             dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                let before = entry.get().history.len();
                 entry.get_mut().update_activity();
+                entry.get_mut().peer_ip = peer_ip;
+                // A busy client earns the strategy's longer TTL, but a
+                // larger one (e.g. an auth boost) is kept
+                if ttl > entry.get().ttl {
+                    entry.get_mut().ttl = ttl;
+                }
+                self.track_history(before, entry.get().history.len());
                 debug!(
                     "Updated existing connection for IP: {}, ID: {}",
                     ip, connection_id
                 );
+                connection_id
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry)
+                if max_connections > 0 && admission.is_none() =>
+            {
+                // Removed since it was checked; register again as a new IP
+                drop(entry);
+                return self.register_forwarded_connection(ip, peer_ip);
             }
             dashmap::mapref::entry::Entry::Vacant(entry) => {
+                self.emit_event(ConnectionEventKind::Registered, &connection);
+                self.track_history(0, connection.history.len());
                 entry.insert(connection);
-                self.total_connections += 1;
+                self.total_connections.fetch_add(1, Ordering::Relaxed);
+                self.family_total(ip).fetch_add(1, Ordering::Relaxed);
                 info!(
                     "New connection registered for IP: {}, ID: {}, TTL: {:?}",
                     ip, connection_id, ttl
                 );
+                connection_id
             }
-        }
+        };
+
+        drop(admission);
 
+        self.enforce_history_budget(ip);
         connection_id
     }
 
+    /// Makes room for one more connection by removing those idle longest.
+    /// Never-expire networks are exempt, so the map may still exceed the cap
+    /// when they fill it.
+    fn evict_lru_if_full(&self) {
+        loop {
+            let max_connections = self.max_connections();
+            if max_connections == 0 || self.connections.len() < max_connections {
+                return;
+            }
+            if !self.evict_lru(max_connections) {
+                return;
+            }
+        }
+    }
+
+    fn evict_lru(&self, max_connections: usize) -> bool {
+        let oldest = self
+            .connections
+            .iter()
+            .filter(|entry| {
+                !self
+                    .never_expire
+                    .iter()
+                    .any(|cidr| cidr.contains(*entry.key()))
+            })
+            .min_by_key(|entry| entry.last_activity)
+            .map(|entry| *entry.key());
+        let Some(ip) = oldest else {
+            return false;
+        };
+
+        // A concurrent removal of the same IP made room just the same
+        if let Some((_, connection)) = self.connections.remove(&ip) {
+            self.track_history(connection.history.len(), 0);
+            self.expired_connections.fetch_add(1, Ordering::Relaxed);
+            self.forget_rate_limit(ip);
+            self.emit_event(ConnectionEventKind::Expired, &connection);
+            debug!(
+                "Evicted least recently active connection for IP: {}, ID: {} (max_connections {} reached)",
+                ip, connection.id, max_connections
+            );
+        }
+        true
+    }
+
+    /// Called when a served connection from `ip`, the address that
+    /// connected, ends; starts its reconnect grace window
+    pub fn connection_closed(&self, ip: IpAddr) {
+        if !self.reconnect_grace.is_zero() {
+            self.recently_closed.insert(ip, Instant::now());
+        }
+    }
+
+    fn closed_within_grace(&self, ip: IpAddr) -> bool {
+        self.recently_closed
+            .get(&ip)
+            .is_some_and(|closed_at| closed_at.elapsed() <= self.reconnect_grace)
+    }
+
     fn calculate_adaptive_ttl(&self, ip: IpAddr) -> Duration {
         // Check if this IP has had recent connections
-        if let Some(existing) = self.connections.get(&ip) {
-            // If the connection is active and has high request count, extend TTL
-            if existing.request_count > 10 && !existing.is_expired() {
-                let extended_ttl = self.default_ttl.mul_f32(1.5);
-                if extended_ttl <= self.max_ttl {
-                    return extended_ttl;
-                }
+        let existing = self.connections.get(&ip);
+        let limits = self.limits();
+        let ctx = TtlContext {
+            ip,
+            existing: existing.as_deref(),
+            default_ttl: limits.default_ttl,
+            max_ttl: limits.max_ttl,
+        };
+
+        self.clamp_ttl(ip, self.ttl_strategy.compute(&ctx), limits.max_ttl)
+    }
+
+    /// Applies the IP's band and then the global max_ttl
+    fn clamp_ttl(&self, ip: IpAddr, mut ttl: Duration, max_ttl: Duration) -> Duration {
+        if let Some(band) = self.ttl_bands.iter().find(|band| band.cidr.contains(ip)) {
+            if let Some(floor) = band.floor_secs {
+                ttl = ttl.max(Duration::from_secs(floor));
+            }
+            if let Some(ceiling) = band.ceiling_secs {
+                ttl = ttl.min(Duration::from_secs(ceiling));
             }
         }
 
-        self.default_ttl
+        ttl.min(max_ttl)
     }
 
     pub fn update_connection_activity(&self, ip: IpAddr) -> bool {
         if let Some(mut connection) = self.connections.get_mut(&ip) {
+            let before = connection.history.len();
             connection.update_activity();
+            self.track_history(before, connection.history.len());
             debug!("Updated activity for IP: {}", ip);
-            true
         } else {
             warn!("Attempted to update non-existent connection for IP: {}", ip);
-            false
+            return false;
         }
+
+        self.enforce_history_budget(ip);
+        true
     }
 
     pub fn get_connection_info(&self, ip: IpAddr) -> Option<ConnectionInfo> {
         self.connections.get(&ip).map(|entry| entry.clone())
     }
 
+    /// Extends a connection's TTL by `factor`, never beyond `max_ttl` and
+    /// never shrinking it. Returns the resulting TTL.
+    pub fn boost_ttl(&self, ip: IpAddr, factor: f64) -> Option<Duration> {
+        let mut connection = self.connections.get_mut(&ip)?;
+        let limits = self.limits();
+        // A factor too large for a Duration just means max_ttl
+        let boosted =
+            Duration::try_from_secs_f64(limits.default_ttl.as_secs_f64() * factor.max(1.0))
+                .unwrap_or(limits.max_ttl);
+        let boosted = self.clamp_ttl(ip, boosted, limits.max_ttl);
+        if boosted > connection.ttl {
+            debug!("Boosted TTL for IP: {} to {:?}", ip, boosted);
+            connection.ttl = boosted;
+        }
+        Some(connection.ttl)
+    }
+
+    /// Records the HTTP version the client most recently spoke
+    pub fn record_protocol(&self, ip: IpAddr, protocol: Version) {
+        if let Some(mut connection) = self.connections.get_mut(&ip) {
+            connection.protocol = protocol;
+        }
+    }
+
+    fn family_total(&self, ip: IpAddr) -> &AtomicU64 {
+        match ip {
+            IpAddr::V4(_) => &self.total_connections_v4,
+            IpAddr::V6(_) => &self.total_connections_v6,
+        }
+    }
+
     pub fn get_stats(&self) -> TtlStats {
-        let active_connections = self.connections.len();
-        let total_ttl_secs: u64 = self
-            .connections
-            .iter()
-            .map(|entry| entry.ttl.as_secs())
-            .sum();
+        // Everything is counted in one pass rather than taken from `len()`,
+        // since the map can change under us while we iterate
+        let mut active_connections = 0;
+        let mut total_ttl_secs: u64 = 0;
+        let mut http1_connections = 0;
+        let mut http2_connections = 0;
+        let mut active_connections_v4 = 0;
+        let mut active_connections_v6 = 0;
+        let mut peers = HashSet::new();
+        for entry in self.connections.iter() {
+            active_connections += 1;
+            total_ttl_secs += entry.ttl.as_secs();
+            peers.insert(entry.peer_ip);
+            if entry.protocol == Version::HTTP_2 {
+                http2_connections += 1;
+            } else {
+                http1_connections += 1;
+            }
+            match entry.key() {
+                IpAddr::V4(_) => active_connections_v4 += 1,
+                IpAddr::V6(_) => active_connections_v6 += 1,
+            }
+        }
 
         let average_ttl_secs = if active_connections > 0 {
             total_ttl_secs / active_connections as u64
         } else {
-            self.default_ttl.as_secs()
+            self.limits().default_ttl.as_secs()
         };
 
         TtlStats {
             active_connections,
-            total_connections: self.total_connections,
-            expired_connections: self.expired_connections,
+            distinct_ips: peers.len(),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            expired_connections: self.expired_connections.load(Ordering::Relaxed),
             average_ttl_secs,
+            http1_connections,
+            http2_connections,
+            active_connections_v4,
+            active_connections_v6,
+            total_connections_v4: self.total_connections_v4.load(Ordering::Relaxed),
+            total_connections_v6: self.total_connections_v6.load(Ordering::Relaxed),
         }
     }
 
-    pub async fn start_cleanup_task(&mut self) {
+    pub async fn start_cleanup_task(&self) {
         info!("Starting TTL cleanup task");
 
+        let mut period = self.limits().cleanup_interval;
+        let mut cleanup_interval = interval(period);
         loop {
-            self.cleanup_interval.tick().await;
+            cleanup_interval.tick().await;
+            if let Ok(mut last_tick) = self.last_cleanup_tick.lock() {
+                *last_tick = Some(Instant::now());
+            }
             self.cleanup_expired_connections().await;
+
+            // Pick up an interval changed by a config reload
+            let reloaded = self.limits().cleanup_interval;
+            if reloaded != period {
+                period = reloaded;
+                cleanup_interval = interval_at(tokio::time::Instant::now() + period, period);
+            }
         }
     }
 
-    async fn cleanup_expired_connections(&mut self) {
+    /// Whether `start_cleanup_task` is running and ticking on schedule. A
+    /// tick may run up to one interval late before the task counts as stalled.
+    pub fn cleanup_running(&self) -> bool {
+        self.last_cleanup_tick
+            .lock()
+            .ok()
+            .and_then(|last_tick| *last_tick)
+            .is_some_and(|last_tick| last_tick.elapsed() <= self.limits().cleanup_interval * 2)
+    }
+
+    pub async fn cleanup_expired_connections(&self) {
         let mut expired_ips = Vec::new();
 
         // Find expired connections
         for entry in self.connections.iter() {
-            if entry.is_expired() {
-                expired_ips.push(*entry.key());
+            let ip = *entry.key();
+            if entry.is_expired()
+                && !self.never_expire.iter().any(|cidr| cidr.contains(ip))
+                && !self.closed_within_grace(ip)
+            {
+                expired_ips.push(ip);
             }
         }
 
@@ -186,8 +727,11 @@ impl TtlController {
         let mut cleaned_count = 0;
         for ip in expired_ips {
             if let Some((_, connection)) = self.connections.remove(&ip) {
+                self.track_history(connection.history.len(), 0);
                 cleaned_count += 1;
-                self.expired_connections += 1;
+                self.expired_connections.fetch_add(1, Ordering::Relaxed);
+                self.forget_rate_limit(ip);
+                self.emit_event(ConnectionEventKind::Expired, &connection);
                 debug!(
                     "Cleaned up expired connection for IP: {}, ID: {}, Duration: {:?}",
                     ip,
@@ -197,10 +741,23 @@ impl TtlController {
             }
         }
 
+        let grace = self.reconnect_grace;
+        self.recently_closed
+            .retain(|_, closed_at| closed_at.elapsed() <= grace);
+
         if cleaned_count > 0 {
             info!("Cleaned up {} expired connections", cleaned_count);
         }
 
+        if let Some(path) = self.stats_path.clone() {
+            let stats = self.stats_snapshot();
+            match tokio::task::spawn_blocking(move || stats.write_atomic(&path)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Failed to persist TTL stats: {}", e),
+                Err(e) => warn!("TTL stats writer panicked: {}", e),
+            }
+        }
+
         // Log periodic stats
         let stats = self.get_stats();
         debug!(
@@ -219,9 +776,66 @@ impl TtlController {
             .collect()
     }
 
-    pub fn force_cleanup_connection(&mut self, ip: IpAddr) -> bool {
+    pub fn snapshot(&self) -> ConnectionSnapshot {
+        let connections = self
+            .connections
+            .iter()
+            .map(|entry| ConnectionRecord {
+                ip: entry.ip,
+                id: entry.id,
+                request_count: entry.request_count,
+                ttl_secs: entry.ttl.as_secs(),
+                age_secs: entry.established_at.elapsed().as_secs(),
+                idle_secs: entry.last_activity.elapsed().as_secs(),
+            })
+            .collect();
+
+        ConnectionSnapshot {
+            saved_at: unix_now(),
+            connections,
+        }
+    }
+
+    /// Re-tracks connections from a snapshot, counting the downtime since it
+    /// was saved as idle time. Entries that would already have expired, or
+    /// IPs tracked since startup, are skipped. Returns how many were restored.
+    pub fn restore_snapshot(&self, snapshot: &ConnectionSnapshot) -> usize {
+        let downtime = unix_now().saturating_sub(snapshot.saved_at);
+        let max_ttl = self.limits().max_ttl;
+        let now = Instant::now();
+        let mut restored = 0;
+
+        for record in &snapshot.connections {
+            let ttl = Duration::from_secs(record.ttl_secs).min(max_ttl);
+            let idle = Duration::from_secs(record.idle_secs.saturating_add(downtime));
+            if idle >= ttl || self.connections.contains_key(&record.ip) {
+                continue;
+            }
+
+            let age = Duration::from_secs(record.age_secs.saturating_add(downtime));
+            let last_activity = now.checked_sub(idle).unwrap_or(now);
+            let mut connection = ConnectionInfo::new(record.ip, ttl);
+            connection.id = record.id;
+            connection.request_count = record.request_count;
+            connection.established_at = now.checked_sub(age).unwrap_or(last_activity);
+            connection.last_activity = last_activity;
+            connection.history = VecDeque::new();
+            connection.history_limit = self.history_limit;
+
+            self.connections.insert(record.ip, connection);
+            restored += 1;
+        }
+
+        info!("Restored {} connections from snapshot", restored);
+        restored
+    }
+
+    pub fn force_cleanup_connection(&self, ip: IpAddr) -> bool {
         if let Some((_, connection)) = self.connections.remove(&ip) {
-            self.expired_connections += 1;
+            self.track_history(connection.history.len(), 0);
+            self.expired_connections.fetch_add(1, Ordering::Relaxed);
+            self.forget_rate_limit(ip);
+            self.emit_event(ConnectionEventKind::ForceRemoved, &connection);
             info!(
                 "Force cleaned connection for IP: {}, ID: {}",
                 ip, connection.id
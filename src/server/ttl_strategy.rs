@@ -0,0 +1,47 @@
+use crate::server::ttl_controller::ConnectionInfo;
+use crate::utils::config::AdaptiveTtlConfig;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Everything a strategy may consider when picking a connection's TTL
+pub struct TtlContext<'a> {
+    pub ip: IpAddr,
+    pub existing: Option<&'a ConnectionInfo>,
+    pub default_ttl: Duration,
+    pub max_ttl: Duration,
+}
+
+/// Computes the TTL for a registering connection. The controller clamps the
+/// result to `max_ttl`.
+pub trait TtlStrategy: Send + Sync {
+    fn compute(&self, ctx: &TtlContext) -> Duration;
+}
+
+/// Extends the TTL of busy, still-active clients
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveTtlStrategy {
+    config: AdaptiveTtlConfig,
+}
+
+impl AdaptiveTtlStrategy {
+    pub fn new(config: AdaptiveTtlConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TtlStrategy for AdaptiveTtlStrategy {
+    fn compute(&self, ctx: &TtlContext) -> Duration {
+        // If the connection is active and has high request count, extend TTL
+        if self.config.enabled
+            && let Some(existing) = ctx.existing
+            && existing.request_count > self.config.request_threshold
+            && !existing.is_expired()
+        {
+            let multiplier = self.config.extension_multiplier.max(1.0);
+            return Duration::try_from_secs_f64(ctx.default_ttl.as_secs_f64() * multiplier)
+                .map_or(ctx.max_ttl, |extended_ttl| extended_ttl.min(ctx.max_ttl));
+        }
+
+        ctx.default_ttl
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Tracks the TLS handshake failure rate over a sliding window and raises an
+/// alert when it crosses a threshold, which usually means scanning or an attack.
+/// Samples are aggregated per second so memory stays bounded by the window.
+#[derive(Debug)]
+pub struct HandshakeWatchdog {
+    window: Duration,
+    threshold: f64,
+    min_samples: u64,
+    started: Instant,
+    state: Mutex<WatchdogState>,
+}
+
+#[derive(Debug, Default)]
+struct WatchdogState {
+    // (second since start, successes, failures), oldest first
+    buckets: VecDeque<(u64, u64, u64)>,
+    alerting: bool,
+}
+
+impl WatchdogState {
+    fn prune(&mut self, now_secs: u64, window_secs: u64) {
+        while let Some(&(second, _, _)) = self.buckets.front() {
+            if now_secs.saturating_sub(second) < window_secs {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    fn totals(&self) -> (u64, u64) {
+        self.buckets
+            .iter()
+            .fold((0, 0), |(ok, failed), (_, o, f)| (ok + o, failed + f))
+    }
+}
+
+impl HandshakeWatchdog {
+    pub fn new(window: Duration, threshold: f64, min_samples: u64) -> Self {
+        Self {
+            window: window.max(Duration::from_secs(1)),
+            threshold,
+            min_samples,
+            started: Instant::now(),
+            state: Mutex::new(WatchdogState::default()),
+        }
+    }
+
+    pub fn record_success(&self) {
+        self.record(false);
+    }
+
+    pub fn record_failure(&self) {
+        self.record(true);
+    }
+
+    fn record(&self, failed: bool) {
+        let now_secs = self.started.elapsed().as_secs();
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+
+        state.prune(now_secs, self.window.as_secs());
+        match state.buckets.back_mut() {
+            Some((second, ok, failures)) if *second == now_secs => {
+                if failed {
+                    *failures += 1;
+                } else {
+                    *ok += 1;
+                }
+            }
+            _ => state
+                .buckets
+                .push_back((now_secs, u64::from(!failed), u64::from(failed))),
+        }
+
+        let (ok, failures) = state.totals();
+        let alerting = self.exceeds_threshold(ok, failures);
+        if alerting != state.alerting {
+            state.alerting = alerting;
+            let rate = failure_rate(ok, failures);
+            if alerting {
+                warn!(
+                    alert = "handshake_failure_rate",
+                    failure_rate = rate,
+                    failures,
+                    handshakes = ok + failures,
+                    window_secs = self.window.as_secs(),
+                    "TLS handshake failure rate crossed {:.0}%",
+                    self.threshold * 100.0
+                );
+            } else {
+                info!(
+                    alert = "handshake_failure_rate",
+                    failure_rate = rate,
+                    "TLS handshake failure rate back below threshold"
+                );
+            }
+        }
+    }
+
+    fn exceeds_threshold(&self, ok: u64, failures: u64) -> bool {
+        ok + failures >= self.min_samples.max(1) && failure_rate(ok, failures) >= self.threshold
+    }
+
+    /// Failure rate over the current window, recomputed so the alert clears
+    /// once failures age out even without new handshakes
+    pub fn snapshot(&self) -> (f64, bool) {
+        let Ok(mut state) = self.state.lock() else {
+            return (0.0, false);
+        };
+
+        state.prune(self.started.elapsed().as_secs(), self.window.as_secs());
+        let (ok, failures) = state.totals();
+        (
+            failure_rate(ok, failures),
+            self.exceeds_threshold(ok, failures),
+        )
+    }
+}
+
+impl Default for HandshakeWatchdog {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60), 0.5, 20)
+    }
+}
+
+fn failure_rate(ok: u64, failures: u64) -> f64 {
+    let total = ok + failures;
+    if total == 0 {
+        0.0
+    } else {
+        failures as f64 / total as f64
+    }
+}
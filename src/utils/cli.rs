@@ -0,0 +1,86 @@
+use crate::utils::AppConfig;
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+/// Formats `--dump-default-config` can write, all readable as config files
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub fn render(self, config: &AppConfig) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Toml => toml::to_string_pretty(config)?,
+            Self::Json => serde_json::to_string_pretty(config)? + "\n",
+        })
+    }
+}
+
+/// Command-line flags. Each one given overrides the matching setting from
+/// config files and `RUSTY_SSL_*` environment variables.
+#[derive(Parser, Debug, Default)]
+#[command(name = "rusty-ssl", version, about)]
+pub struct CliArgs {
+    /// Config file to read instead of RUSTY_SSL_CONFIG_PATH or configs/default
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<String>,
+    /// Address to listen on (server.host)
+    #[arg(long)]
+    pub host: Option<String>,
+    /// Port to listen on (server.port)
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Certificate chain PEM (ssl.cert_path)
+    #[arg(long, value_name = "PATH")]
+    pub cert: Option<PathBuf>,
+    /// Private key PEM (ssl.key_path)
+    #[arg(long, value_name = "PATH")]
+    pub key: Option<PathBuf>,
+    /// Log level or filter directives (logging.level)
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+    /// Print the built-in defaults as a config file template and exit
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "toml"
+    )]
+    pub dump_default_config: Option<ConfigFormat>,
+}
+
+impl CliArgs {
+    /// Loads the configuration from `--config` when given, or as
+    /// `AppConfig::load` does otherwise, then applies the other flags
+    pub fn load_config(&self) -> Result<AppConfig, config::ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => AppConfig::load_from(path)?,
+            None => AppConfig::load()?,
+        };
+        self.apply(&mut config);
+        Ok(config)
+    }
+
+    pub fn apply(&self, config: &mut AppConfig) {
+        if let Some(host) = &self.host {
+            config.server.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(cert) = &self.cert {
+            config.ssl.cert_path = cert.clone();
+        }
+        if let Some(key) = &self.key {
+            config.ssl.key_path = key.clone();
+        }
+        if let Some(level) = &self.log_level {
+            config.logging.level = level.clone();
+        }
+    }
+}
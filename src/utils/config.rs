@@ -1,7 +1,36 @@
+use crate::handlers::ProxyHandler;
+use crate::server::compression::Compressor;
+use crate::server::latency::DEFAULT_BUCKETS_MS;
+use crate::server::path_params::match_path;
+use crate::server::router::BUILTIN_ROUTE_COUNT;
+use crate::server::ssl_manager::{select_cipher_suites, selected_crypto_provider};
+use crate::utils::cidr::IpCidr;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+
+pub const CONFIG_PATH_ENV: &str = "RUSTY_SSL_CONFIG_PATH";
+
+// Extensions an explicitly named config file may have, and their formats
+const CONFIG_FORMATS: [(&str, config::FileFormat); 4] = [
+    ("toml", config::FileFormat::Toml),
+    ("yaml", config::FileFormat::Yaml),
+    ("yml", config::FileFormat::Yaml),
+    ("json", config::FileFormat::Json),
+];
+
+/// A setting `AppConfig::validate` rejected
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{field}: {message}")]
+pub struct ConfigValidationError {
+    pub field: String, // e.g. "ttl.default_ttl_secs"
+    pub message: String,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -9,14 +38,60 @@ pub struct AppConfig {
     pub ssl: SslConfig,
     pub ttl: TtlConfig,
     pub logging: LoggingConfig,
+    pub audit: AuditConfig,
+    pub auth: AuthConfig,
+    pub metrics: MetricsConfig,
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub access_control: AccessControlConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub static_files: StaticFilesConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    #[serde(default)]
+    pub listen: Vec<SocketAddr>, // one listener per address; empty listens on host:port
+    pub unix_socket: Option<PathBuf>, // listen here instead of on TCP (unix only)
+    pub unix_socket_force: bool,      // replace a file already at unix_socket
+    pub unix_socket_tls: bool,        // false serves plaintext HTTP over the socket
+    pub http_redirect_port: Option<u16>, // plaintext port on host answering 301s to HTTPS
+    pub tcp_nodelay: bool,            // disable Nagle's algorithm on accepted connections
+    pub so_keepalive_secs: u64,       // idle time before TCP keepalive probes; 0 disables
+    pub listen_backlog: u32,          // pending connections the kernel queues per listener
     pub max_connections: usize,
+    pub wait_for_connection_slot: bool, // false drops connections beyond the cap
     pub request_timeout_secs: u64,
+    pub shutdown_grace_secs: u64, // how long in-flight connections get to finish on shutdown
+    pub normalize_methods: bool,
+    pub reject_nonstandard_methods: bool,
+    pub header_read_timeout_ms: u64,
+    pub ready_file: Option<PathBuf>,
+    pub json_charset: Option<String>,
+    pub write_timeout_ms: u64,
+    pub root_page: RootPage,
+    pub metrics_stream_interval_ms: u64,
+    pub http2_enabled: bool, // also requires "h2" in ssl.alpn for clients that negotiate it
+    pub http2_max_concurrent_streams: u32,
+    #[serde(default)]
+    pub route_timeouts_ms: BTreeMap<String, u64>, // route pattern -> handler deadline
+    pub max_routes: usize, // size cap on the route table, built-in routes included
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpCidr>, // peers whose X-Forwarded-For names the real client
+    pub proxy_protocol: bool, // expect a PROXY protocol v1/v2 header before the TLS handshake
+    pub compression_enabled: bool, // gzip/brotli for clients that accept it
+    pub compression_min_bytes: usize, // smaller bodies go out uncompressed
+    pub compression_gzip_level: u32, // 1 (fastest) ..= 9 (smallest)
+    pub compression_brotli_quality: u32, // 0 (fastest) ..= 11 (smallest)
+    pub health_check_timeout_ms: u64, // per registered /health check; slower ones count as failed
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +99,106 @@ pub struct SslConfig {
     pub cert_path: PathBuf,
     pub key_path: PathBuf,
     pub cert_check_interval_secs: u64,
+    pub buffer_limit_bytes: Option<usize>,
+    pub max_fragment_size: Option<usize>,
+    pub fail_closed_on_expired_cert: bool,
+    pub strip_redundant_chain_certs: bool,
+    pub handshake_failure_window_secs: u64,
+    pub handshake_failure_threshold: f64, // fraction of handshakes failing, 0.0..=1.0
+    pub handshake_failure_min_samples: u64,
+    pub require_sni: bool, // refuse handshakes whose ClientHello carries no server name
+    #[serde(default)]
+    pub sni_certs: Vec<SniCert>, // per-hostname certificates; cert_path/key_path is the fallback
+    pub client_auth: ClientAuthConfig,
+    pub watch_files: bool, // reload as soon as certificate or key files change on disk
+    pub reload_debounce_ms: u64,
+    pub acme: Option<AcmeConfig>, // obtain and renew cert_path/key_path from an ACME CA
+    pub startup_retry: Option<StartupRetryConfig>, // wait for missing cert/key files at startup
+    #[serde(default)]
+    pub cipher_suites: Vec<String>, // IANA suite names to allow; empty keeps rustls' safe defaults
+    pub alpn: Vec<String>,        // protocols advertised via ALPN, most preferred first
+}
+
+/// How long startup waits for certificate or key files that don't exist
+/// yet, e.g. while certbot is still writing them. Files that exist but
+/// don't parse fail startup right away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupRetryConfig {
+    pub max_attempts: u32,     // loads tried in total before giving up
+    pub initial_delay_ms: u64, // doubled after each failed attempt
+    pub max_delay_ms: u64,
+}
+
+impl Default for StartupRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl StartupRetryConfig {
+    /// Wait after the `attempt`th failed load (counting from 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+}
+
+/// Certificates ordered from an ACME CA such as Let's Encrypt, answering
+/// HTTP-01 challenges on a temporary plain-HTTP listener
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AcmeConfig {
+    pub directory_url: String,
+    pub contact_email: Option<String>,
+    pub domains: Vec<String>,
+    pub http_challenge_addr: String, // must be reachable as port 80 of every domain
+    pub renew_before_days: i64,
+}
+
+impl Default for AcmeConfig {
+    fn default() -> Self {
+        Self {
+            directory_url: "https://acme-v02.api.letsencrypt.org/directory".to_string(),
+            contact_email: None,
+            domains: Vec::new(),
+            http_challenge_addr: "0.0.0.0:80".to_string(),
+            renew_before_days: 30,
+        }
+    }
+}
+
+/// Whether clients must present a certificate signed by `ca_path`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuthMode {
+    #[default]
+    None,
+    Optional,
+    Required,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientAuthConfig {
+    pub mode: ClientAuthMode,
+    pub ca_path: Option<PathBuf>, // PEM bundle of trusted client CAs; needed unless mode is none
+    pub require_for_metrics: bool, // /metrics and /admin/* need a verified client cert (403 otherwise)
+}
+
+/// Certificate served to clients requesting `hostname` via SNI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SniCert {
+    pub hostname: String,
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,12 +206,252 @@ pub struct TtlConfig {
     pub default_ttl_secs: u64,
     pub max_ttl_secs: u64,
     pub cleanup_interval_secs: u64,
+    pub history_size: usize,
+    pub history_budget_bytes: usize, // across all connections; 0 leaves only history_size
+    pub snapshot_path: Option<PathBuf>,
+    pub snapshot_interval_secs: u64,
+    #[serde(default)]
+    pub stats_path: Option<PathBuf>, // cumulative counters, kept across restarts
+    #[serde(default)]
+    pub bands: Vec<TtlBand>,
+    pub reconnect_grace_ms: u64, // cleanup spares an IP this long after its connection closes; 0 disables
+    #[serde(default)]
+    pub adaptive: AdaptiveTtlConfig,
+}
+
+/// Longer TTLs for busy clients, applied before the max_ttl clamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveTtlConfig {
+    pub enabled: bool,             // false gives every connection default_ttl_secs
+    pub request_threshold: u64,    // requests a live connection needs before it is extended
+    pub extension_multiplier: f64, // applied to default_ttl_secs
+}
+
+impl Default for AdaptiveTtlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            request_threshold: 10,
+            extension_multiplier: 1.5,
+        }
+    }
+}
+
+/// Per-network TTL bounds, applied on top of the global max_ttl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtlBand {
+    pub cidr: IpCidr,
+    pub floor_secs: Option<u64>,
+    pub ceiling_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
     pub level: String,
-    pub format: String, // "json" or "pretty"
+    pub format: String,     // "json" or "pretty"
+    pub log_requests: bool, // disable for benchmarking; errors are still logged
+    #[serde(default)]
+    pub static_fields: BTreeMap<String, String>, // e.g. service, environment, region
+    #[serde(default)]
+    pub access_log_format: AccessLogFormat,
+    pub access_log_path: Option<PathBuf>, // access lines go to stdout when unset
+    pub file: Option<FileLogConfig>,      // also (or only) log to a rotated file
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileLogConfig {
+    pub path: PathBuf, // daily/hourly files get a date suffix; size rotation writes here
+    pub rotation: LogRotation,
+    pub max_files: usize,    // rotated files kept, the current one included
+    pub max_size_bytes: u64, // rotation point when rotation is "size"
+    pub stdout: bool,        // keep logging to stdout as well
+}
+
+impl Default for FileLogConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("logs/rusty-ssl.log"),
+            rotation: LogRotation::Daily,
+            max_files: 7,
+            max_size_bytes: 100 * 1024 * 1024,
+            stdout: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    #[default]
+    Daily,
+    Hourly,
+    Size,
+}
+
+/// What `/` serves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RootPage {
+    // The static page listing the endpoints; "endpoints" is the old name
+    #[default]
+    #[serde(alias = "endpoints")]
+    Static,
+    Dashboard, // live view polling /metrics
+}
+
+/// Apache-style access log written once per completed request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    #[default]
+    None,
+    Common,
+    Combined, // common plus the Referer and User-Agent
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<String>, // bearer tokens; empty disables authentication
+    pub ttl_boost_factor: f64, // TTL multiplier for authenticated clients, capped at max_ttl
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            tokens: Vec::new(),
+            ttl_boost_factor: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub report_deltas: bool, // add per-scraper deltas since the previous /metrics scrape
+    pub cache_max_age_ms: u64, // reuse a computed /metrics document this long; 0 disables
+    pub latency_buckets_ms: Vec<f64>, // ascending upper bounds of the request latency histogram
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            report_deltas: false,
+            cache_max_age_ms: 0,
+            latency_buckets_ms: DEFAULT_BUCKETS_MS.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub requests_per_sec: f64, // per client IP; 0 disables rate limiting
+    pub burst: u32,            // requests a client may make at once after being idle
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_sec: 0.0,
+            burst: 20,
+        }
+    }
+}
+
+/// Networks to refuse or always serve; an allow entry overrides a deny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessControlConfig {
+    pub allow: Vec<IpCidr>, // never refused, rate limited or expired
+    pub deny: Vec<IpCidr>,  // connections are dropped before the TLS handshake
+}
+
+/// Cross-origin access for browser dashboards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>, // exact origins or "*"; empty disables CORS
+    pub allowed_methods: Vec<String>, // answered to preflight requests
+    pub allow_credentials: bool,      // echoes the origin even when "*" is allowed
+    pub max_age_secs: u64,            // how long browsers may cache a preflight
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allow_credentials: false,
+            max_age_secs: 600,
+        }
+    }
+}
+
+/// Files served as-is below a URL prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StaticFilesConfig {
+    pub root: Option<PathBuf>, // directory to serve; unset disables static files
+    pub prefix: String,        // e.g. "/static" serves root/app.js at /static/app.js
+    pub max_file_bytes: u64,   // files are read whole into memory; larger ones are refused
+}
+
+impl Default for StaticFilesConfig {
+    fn default() -> Self {
+        Self {
+            root: None,
+            prefix: "/static".to_string(),
+            max_file_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl StaticFilesConfig {
+    /// Route pattern capturing the file path below the prefix
+    pub fn route(&self) -> Option<String> {
+        self.root.as_ref()?;
+        Some(format!("{}/*path", self.prefix.trim_end_matches('/')))
+    }
+}
+
+/// Path prefixes forwarded to backend HTTP services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub routes: BTreeMap<String, String>, // path prefix -> upstream URL, e.g. "/api" -> "http://127.0.0.1:8080"
+    pub max_response_bytes: usize,        // larger upstream responses are answered with 502
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            routes: BTreeMap::new(),
+            max_response_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Distributed tracing export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>, // OTLP/HTTP collector, e.g. "http://localhost:4318"; requires the "otlp" feature
+    pub service_name: String,          // service.name attached to exported spans
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: env!("CARGO_PKG_NAME").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditConfig {
+    pub sqlite_path: Option<PathBuf>, // requires the "audit-sqlite" feature
 }
 
 impl Default for AppConfig {
@@ -45,35 +460,147 @@ impl Default for AppConfig {
             server: ServerConfig {
                 host: "0.0.0.0".to_string(),
                 port: 8443,
+                listen: Vec::new(),
+                unix_socket: None,
+                unix_socket_force: false,
+                unix_socket_tls: true,
+                http_redirect_port: None,
+                tcp_nodelay: true,
+                so_keepalive_secs: 0,
+                listen_backlog: 1024,
                 max_connections: 1000,
+                wait_for_connection_slot: false,
                 request_timeout_secs: 30,
+                shutdown_grace_secs: 30,
+                normalize_methods: true,
+                reject_nonstandard_methods: false,
+                header_read_timeout_ms: 10_000,
+                ready_file: None,
+                json_charset: None,
+                write_timeout_ms: 30_000,
+                root_page: RootPage::Static,
+                metrics_stream_interval_ms: 1_000,
+                http2_enabled: true,
+                http2_max_concurrent_streams: 100,
+                route_timeouts_ms: BTreeMap::new(),
+                max_routes: 256,
+                trusted_proxies: Vec::new(),
+                proxy_protocol: false,
+                compression_enabled: true,
+                compression_min_bytes: 1024,
+                compression_gzip_level: 6,
+                compression_brotli_quality: 4,
+                health_check_timeout_ms: 2_000,
             },
             ssl: SslConfig {
                 cert_path: PathBuf::from("test-certs/cert.pem"), // Changed for testing
                 key_path: PathBuf::from("test-certs/key.pem"),   // Changed for testing
                 cert_check_interval_secs: 3600,                  // Check every hour
+                buffer_limit_bytes: None,
+                max_fragment_size: None,
+                fail_closed_on_expired_cert: false,
+                strip_redundant_chain_certs: true,
+                handshake_failure_window_secs: 60,
+                handshake_failure_threshold: 0.5,
+                handshake_failure_min_samples: 20,
+                require_sni: false,
+                sni_certs: Vec::new(),
+                client_auth: ClientAuthConfig::default(),
+                watch_files: true,
+                reload_debounce_ms: 500,
+                acme: None,
+                startup_retry: None,
+                cipher_suites: Vec::new(),
+                alpn: vec!["http/1.1".to_string()],
             },
             ttl: TtlConfig {
                 default_ttl_secs: 300,     // 5 minutes
                 max_ttl_secs: 3600,        // 1 hour
                 cleanup_interval_secs: 60, // Cleanup every minute
+                history_size: 32,          // Recent requests remembered per IP
+                history_budget_bytes: 0,
+                snapshot_path: None,
+                snapshot_interval_secs: 60,
+                stats_path: None,
+                bands: Vec::new(),
+                reconnect_grace_ms: 0,
+                adaptive: AdaptiveTtlConfig::default(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 format: "pretty".to_string(),
+                log_requests: true,
+                static_fields: BTreeMap::new(),
+                access_log_format: AccessLogFormat::None,
+                access_log_path: None,
+                file: None,
             },
+            audit: AuditConfig::default(),
+            auth: AuthConfig::default(),
+            metrics: MetricsConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            access_control: AccessControlConfig::default(),
+            cors: CorsConfig::default(),
+            static_files: StaticFilesConfig::default(),
+            proxy: ProxyConfig::default(),
+            telemetry: TelemetryConfig::default(),
         }
     }
 }
 
 impl AppConfig {
+    /// Reads the layered configuration; `validate` the result before using it
     pub fn load() -> Result<Self, config::ConfigError> {
+        // Check for custom config path from environment
+        let Ok(config_path) = std::env::var(CONFIG_PATH_ENV) else {
+            return Self::load_layers(None);
+        };
+        if !Path::new(&config_path).is_file() {
+            return Err(config::ConfigError::Message(format!(
+                "configuration file '{}' does not exist (set via {}); \
+                 point {} at an existing file or unset it to use configs/default.toml",
+                config_path, CONFIG_PATH_ENV, CONFIG_PATH_ENV
+            )));
+        }
+        Self::load_layers(Some(&config_path))
+    }
+
+    /// Like `load`, reading `config_path` in place of the file named by
+    /// `RUSTY_SSL_CONFIG_PATH`
+    pub fn load_from(config_path: &str) -> Result<Self, config::ConfigError> {
+        if !Path::new(config_path).is_file() {
+            return Err(config::ConfigError::Message(format!(
+                "configuration file '{}' does not exist",
+                config_path
+            )));
+        }
+        Self::load_layers(Some(config_path))
+    }
+
+    /// Reads just `path` over the built-in defaults, ignoring the default
+    /// config files and `RUSTY_SSL_*` environment variables. Meant for tests
+    /// and embedders; `validate` the result before using it.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, config::ConfigError> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(config::ConfigError::Message(format!(
+                "configuration file '{}' does not exist",
+                path.display()
+            )));
+        }
+        config::Config::builder()
+            .add_source(config::Config::try_from(&AppConfig::default())?)
+            .add_source(config_file(path)?)
+            .build()?
+            .try_deserialize()
+    }
+
+    fn load_layers(config_path: Option<&str>) -> Result<Self, config::ConfigError> {
         let mut builder =
             config::Config::builder().add_source(config::Config::try_from(&AppConfig::default())?);
 
-        // Check for custom config path from environment
-        if let Ok(config_path) = std::env::var("RUSTY_SSL_CONFIG_PATH") {
-            builder = builder.add_source(config::File::with_name(&config_path).required(true));
+        if let Some(config_path) = config_path {
+            builder = builder.add_source(config_file(Path::new(config_path))?);
         } else {
             // Use default config files
             builder = builder
@@ -88,14 +615,275 @@ impl AppConfig {
         settings.try_deserialize()
     }
 
+    /// Checks settings whose values serde alone can't vet, reporting every
+    /// problem found rather than just the first
+    pub fn validate(&self) -> Result<(), Vec<ConfigValidationError>> {
+        let mut errors = Vec::new();
+        let mut fail = |field: &str, message: String| {
+            errors.push(ConfigValidationError {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if self.server.port == 0
+            && self.server.listen.is_empty()
+            && self.server.unix_socket.is_none()
+        {
+            fail("server.port", "must not be 0".to_string());
+        }
+        // Every slot would be taken, refusing (or stalling) all connections
+        if self.server.max_connections == 0 {
+            fail("server.max_connections", "must be at least 1".to_string());
+        }
+        let reserved = self.reserved_routes();
+        if self.server.max_routes < reserved {
+            fail(
+                "server.max_routes",
+                format!("must be at least {reserved}, the built-in routes"),
+            );
+        }
+        // request_timeout_secs drops the whole request, so a route deadline
+        // at or past it would never get to answer 504
+        let request_timeout_ms = self.server.request_timeout_secs.saturating_mul(1000);
+        for (route, ms) in &self.server.route_timeouts_ms {
+            if *ms >= request_timeout_ms {
+                fail(
+                    "server.route_timeouts_ms",
+                    format!(
+                        "'{route}' ({ms} ms) must be shorter than server.request_timeout_secs ({}s)",
+                        self.server.request_timeout_secs
+                    ),
+                );
+            }
+        }
+        if cfg!(not(unix)) && self.server.unix_socket.is_some() {
+            fail(
+                "server.unix_socket",
+                "is only supported on unix platforms".to_string(),
+            );
+        }
+
+        // ACME obtains the certificate and key, and startup retries wait
+        // for them, so they needn't exist yet
+        if self.ssl.acme.is_none() && self.ssl.startup_retry.is_none() {
+            for (field, path) in [
+                ("ssl.cert_path", &self.ssl.cert_path),
+                ("ssl.key_path", &self.ssl.key_path),
+            ] {
+                if !path.is_file() {
+                    fail(field, format!("'{}' does not exist", path.display()));
+                }
+            }
+        }
+        if let Some(retry) = &self.ssl.startup_retry {
+            if retry.max_attempts == 0 {
+                fail(
+                    "ssl.startup_retry.max_attempts",
+                    "must be at least 1".to_string(),
+                );
+            }
+            if retry.initial_delay_ms > retry.max_delay_ms {
+                fail(
+                    "ssl.startup_retry.initial_delay_ms",
+                    format!(
+                        "must not exceed ssl.startup_retry.max_delay_ms ({})",
+                        retry.max_delay_ms
+                    ),
+                );
+            }
+        }
+        let provider = selected_crypto_provider();
+        if let Err(e) = select_cipher_suites(&provider, &self.ssl.cipher_suites) {
+            fail("ssl.cipher_suites", e.to_string());
+        }
+        if !self.server.http2_enabled && self.ssl.alpn.iter().any(|protocol| protocol == "h2") {
+            fail(
+                "ssl.alpn",
+                "offers h2 but server.http2_enabled is false".to_string(),
+            );
+        }
+
+        if self.ttl.default_ttl_secs > self.ttl.max_ttl_secs {
+            fail(
+                "ttl.default_ttl_secs",
+                format!(
+                    "must not exceed ttl.max_ttl_secs ({})",
+                    self.ttl.max_ttl_secs
+                ),
+            );
+        }
+        if self.ttl.cleanup_interval_secs == 0 {
+            fail(
+                "ttl.cleanup_interval_secs",
+                "must be at least 1".to_string(),
+            );
+        }
+        let multiplier = self.ttl.adaptive.extension_multiplier;
+        if !multiplier.is_finite() || multiplier < 1.0 {
+            fail(
+                "ttl.adaptive.extension_multiplier",
+                "must be at least 1.0".to_string(),
+            );
+        }
+        let boost = self.auth.ttl_boost_factor;
+        if !boost.is_finite() || boost < 1.0 {
+            fail("auth.ttl_boost_factor", "must be at least 1.0".to_string());
+        }
+
+        if !is_valid_log_level(&self.logging.level) {
+            fail(
+                "logging.level",
+                format!("'{}' is not a valid log level", self.logging.level),
+            );
+        }
+        if !matches!(self.logging.format.as_str(), "json" | "pretty") {
+            fail(
+                "logging.format",
+                format!("'{}' must be \"json\" or \"pretty\"", self.logging.format),
+            );
+        }
+        if let Some(file) = &self.logging.file {
+            if file.max_files == 0 {
+                fail("logging.file.max_files", "must be at least 1".to_string());
+            }
+            if file.rotation == LogRotation::Size && file.max_size_bytes == 0 {
+                fail(
+                    "logging.file.max_size_bytes",
+                    "must be positive for size rotation".to_string(),
+                );
+            }
+            if file.path.file_name().is_none() {
+                fail(
+                    "logging.file.path",
+                    format!("'{}' does not name a file", file.path.display()),
+                );
+            }
+        }
+
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint {
+            let uri = endpoint.parse::<hyper::Uri>();
+            if !uri.is_ok_and(|uri| {
+                matches!(uri.scheme_str(), Some("http" | "https")) && uri.host().is_some()
+            }) {
+                fail(
+                    "telemetry.otlp_endpoint",
+                    format!("'{endpoint}' is not an http(s) URL"),
+                );
+            }
+        }
+
+        let buckets = &self.metrics.latency_buckets_ms;
+        if buckets.is_empty()
+            || buckets[0] <= 0.0
+            || buckets.windows(2).any(|pair| pair[0] >= pair[1])
+        {
+            fail(
+                "metrics.latency_buckets_ms",
+                "must be positive and strictly ascending".to_string(),
+            );
+        }
+        let prefix = &self.static_files.prefix;
+        if !prefix.starts_with('/') || prefix.contains([':', '*']) {
+            fail(
+                "static_files.prefix",
+                format!("must be a plain path starting with '/', got '{prefix}'"),
+            );
+        }
+        if let Err(e) = ProxyHandler::new(&self.proxy) {
+            fail("proxy.routes", e.to_string());
+        }
+        for method in &self.cors.allowed_methods {
+            if method.parse::<hyper::Method>().is_err() {
+                fail(
+                    "cors.allowed_methods",
+                    format!("'{method}' is not an HTTP method"),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Routes every router registers itself: the built-in endpoints plus the
+    /// static file route when enabled
+    pub fn reserved_routes(&self) -> usize {
+        BUILTIN_ROUTE_COUNT + usize::from(self.static_files.route().is_some())
+    }
+
     pub fn server_addr(&self) -> Result<SocketAddr, std::net::AddrParseError> {
         format!("{}:{}", self.server.host, self.server.port).parse()
     }
 
+    /// Addresses to accept connections on: `server.listen` when set,
+    /// otherwise `host:port`
+    pub fn listen_addrs(&self) -> Result<Vec<SocketAddr>, std::net::AddrParseError> {
+        if self.server.listen.is_empty() {
+            return Ok(vec![self.server_addr()?]);
+        }
+        Ok(self.server.listen.clone())
+    }
+
+    /// Where the plaintext HTTPS redirect listener binds, when enabled
+    pub fn http_redirect_addr(&self) -> Option<Result<SocketAddr, std::net::AddrParseError>> {
+        let port = self.server.http_redirect_port?;
+        Some(format!("{}:{}", self.server.host, port).parse())
+    }
+
+    /// The port redirected clients are sent to: the first listen address's
+    pub fn https_port(&self) -> u16 {
+        self.server
+            .listen
+            .first()
+            .map_or(self.server.port, SocketAddr::port)
+    }
+
+    pub fn so_keepalive(&self) -> Option<Duration> {
+        (self.server.so_keepalive_secs > 0)
+            .then(|| Duration::from_secs(self.server.so_keepalive_secs))
+    }
+
     pub fn request_timeout(&self) -> Duration {
         Duration::from_secs(self.server.request_timeout_secs)
     }
 
+    pub fn compressor(&self) -> Compressor {
+        Compressor::new(
+            self.server.compression_gzip_level,
+            self.server.compression_brotli_quality,
+        )
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_millis(self.server.health_check_timeout_ms)
+    }
+
+    pub fn header_read_timeout(&self) -> Duration {
+        Duration::from_millis(self.server.header_read_timeout_ms)
+    }
+
+    /// Deadline for the route `path` is served by; patterns may use `:name`
+    /// segments, and routes without an entry have none
+    pub fn route_timeout(&self, path: &str) -> Option<Duration> {
+        self.server
+            .route_timeouts_ms
+            .iter()
+            .find(|(pattern, _)| match_path(pattern, path).is_some())
+            .map(|(_, ms)| Duration::from_millis(*ms))
+    }
+
+    pub fn shutdown_grace(&self) -> Duration {
+        Duration::from_secs(self.server.shutdown_grace_secs)
+    }
+
+    pub fn write_timeout(&self) -> Duration {
+        Duration::from_millis(self.server.write_timeout_ms)
+    }
+
     pub fn default_ttl(&self) -> Duration {
         Duration::from_secs(self.ttl.default_ttl_secs)
     }
@@ -108,7 +896,64 @@ impl AppConfig {
         Duration::from_secs(self.ttl.cleanup_interval_secs)
     }
 
+    pub fn handshake_failure_window(&self) -> Duration {
+        Duration::from_secs(self.ssl.handshake_failure_window_secs)
+    }
+
+    pub fn metrics_cache_max_age(&self) -> Duration {
+        Duration::from_millis(self.metrics.cache_max_age_ms)
+    }
+
+    pub fn metrics_stream_interval(&self) -> Duration {
+        Duration::from_millis(self.server.metrics_stream_interval_ms.max(1))
+    }
+
+    pub fn reconnect_grace(&self) -> Duration {
+        Duration::from_millis(self.ttl.reconnect_grace_ms)
+    }
+
+    pub fn snapshot_interval(&self) -> Duration {
+        Duration::from_secs(self.ttl.snapshot_interval_secs.max(1))
+    }
+
+    pub fn reload_debounce(&self) -> Duration {
+        Duration::from_millis(self.ssl.reload_debounce_ms)
+    }
+
     pub fn cert_check_interval(&self) -> Duration {
         Duration::from_secs(self.ssl.cert_check_interval_secs)
     }
 }
+
+/// Accepts a bare level ("debug") or `EnvFilter` directives that each end
+/// in one ("info,hyper=warn"). `EnvFilter` alone would take "verbose" as a
+/// target name and silently log nothing.
+fn is_valid_log_level(level: &str) -> bool {
+    let levels_valid = level.split(',').all(|directive| {
+        let level = directive
+            .rsplit_once('=')
+            .map_or(directive, |(_, level)| level);
+        level.trim().parse::<LevelFilter>().is_ok()
+    });
+    levels_valid && EnvFilter::try_new(level).is_ok()
+}
+
+/// `path` as a config source in the format its extension names, rather
+/// than one the `config` crate guesses
+fn config_file(
+    path: &Path,
+) -> Result<config::File<config::FileSourceFile, config::FileFormat>, config::ConfigError> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let Some((_, format)) = CONFIG_FORMATS
+        .iter()
+        .find(|(known, _)| extension.eq_ignore_ascii_case(known))
+    else {
+        return Err(config::ConfigError::Message(format!(
+            "configuration file '{}' has unsupported extension '{}'; \
+             use .toml, .yaml, .yml or .json",
+            path.display(),
+            extension
+        )));
+    };
+    Ok(config::File::new(&path.to_string_lossy(), *format).required(true))
+}
@@ -0,0 +1,43 @@
+use http_body_util::combinators::BoxBody;
+use hyper::body::{Body, Bytes, Frame};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Body type for every response, so buffered and streaming handlers can share
+/// a router
+pub type ResponseBody = BoxBody<Bytes, Infallible>;
+
+/// Builds the `Content-Type` value used for every JSON response
+pub fn json_content_type(charset: Option<&str>) -> String {
+    match charset {
+        Some(charset) if !charset.is_empty() => format!("application/json; charset={}", charset),
+        _ => "application/json".to_string(),
+    }
+}
+
+/// Streams chunks from a channel; the body ends when every sender is dropped
+pub struct ChannelBody {
+    receiver: mpsc::Receiver<Bytes>,
+}
+
+impl ChannelBody {
+    pub fn new(receiver: mpsc::Receiver<Bytes>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl Body for ChannelBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.receiver
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|data| Ok(Frame::data(data))))
+    }
+}
@@ -1,26 +1,226 @@
-use crate::utils::config::LoggingConfig;
+use crate::utils::config::{LoggingConfig, TelemetryConfig};
+use crate::utils::log_file::open_log_file;
+#[cfg(feature = "otlp")]
+use crate::utils::telemetry::otlp_layer;
 use anyhow::Result;
-use tracing_subscriber::{filter::EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::{Event, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::registry::{LookupSpan, Registry};
+use tracing_subscriber::reload;
+use tracing_subscriber::{Layer, filter::EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-pub fn init_logging(config: &LoggingConfig) -> Result<()> {
+type SpanExporter = Option<Box<dyn Layer<Registry> + Send + Sync>>;
+
+/// Keeps the log file's background writer and the span exporter running.
+/// Hold it until the process exits; dropping it flushes whatever is still
+/// buffered.
+#[must_use = "dropping the guard stops file logging and span export"]
+#[derive(Default)]
+pub struct LoggingGuard {
+    _worker: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    _tracer: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+    log_level: Option<LogLevelHandle>,
+}
+
+impl LoggingGuard {
+    /// Changes the installed subscriber's level, e.g. on config reload
+    pub fn log_level(&self) -> Option<LogLevelHandle> {
+        self.log_level.clone()
+    }
+}
+
+/// Swaps the level filter of a subscriber built here while it is installed
+#[derive(Clone)]
+pub struct LogLevelHandle(reload::Handle<EnvFilter, Layered<SpanExporter, Registry>>);
+
+impl LogLevelHandle {
+    /// `level` takes the same directives as `logging.level`
+    pub fn set_level(&self, level: &str) -> Result<()> {
+        self.0.reload(EnvFilter::try_new(level)?)?;
+        Ok(())
+    }
+}
+
+pub fn init_logging(config: &LoggingConfig, telemetry: &TelemetryConfig) -> Result<LoggingGuard> {
+    let (subscriber, guard) = configured_subscriber(config, telemetry)?;
+    subscriber.try_init()?;
+
+    tracing::info!("Logger initialized with level: {}", config.level);
+    Ok(guard)
+}
+
+/// Builds the subscriber for stdout and, when `logging.file` is set, the
+/// log file, without installing it. Spans are also exported when
+/// `telemetry.otlp_endpoint` is set and the `otlp` feature is enabled.
+#[cfg_attr(not(feature = "otlp"), allow(unused_variables))]
+pub fn configured_subscriber(
+    config: &LoggingConfig,
+    telemetry: &TelemetryConfig,
+) -> Result<(Box<dyn Subscriber + Send + Sync>, LoggingGuard)> {
+    let mut guard = LoggingGuard::default();
+    #[cfg(feature = "otlp")]
+    let span_exporter = otlp_layer(telemetry)?.map(|(layer, provider)| {
+        guard._tracer = Some(provider);
+        layer
+    });
+    #[cfg(not(feature = "otlp"))]
+    let span_exporter = None;
+
+    let Some(file) = &config.file else {
+        let (subscriber, log_level) =
+            build_subscriber(config, std::io::stdout, true, span_exporter)?;
+        guard.log_level = Some(log_level);
+        return Ok((subscriber, guard));
+    };
+
+    let (file_writer, worker) = tracing_appender::non_blocking(open_log_file(file)?);
+    guard._worker = Some(worker);
+    let writer = if file.stdout {
+        BoxMakeWriter::new(std::io::stdout.and(file_writer))
+    } else {
+        BoxMakeWriter::new(file_writer)
+    };
+    // Color codes would end up in the file
+    let (subscriber, log_level) = build_subscriber(config, writer, false, span_exporter)?;
+    guard.log_level = Some(log_level);
+    Ok((subscriber, guard))
+}
+
+/// Builds the configured subscriber without installing it, writing to `writer`
+pub fn logging_subscriber<W>(
+    config: &LoggingConfig,
+    writer: W,
+) -> Result<Box<dyn Subscriber + Send + Sync>>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let (subscriber, _) = build_subscriber(config, writer, true, None)?;
+    Ok(subscriber)
+}
+
+fn build_subscriber<W>(
+    config: &LoggingConfig,
+    writer: W,
+    ansi: bool,
+    span_exporter: SpanExporter,
+) -> Result<(Box<dyn Subscriber + Send + Sync>, LogLevelHandle)>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
     let filter =
         EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.level))?;
+    let (filter, log_level) = reload::Layer::new(filter);
 
-    let subscriber = tracing_subscriber::registry().with(filter);
+    // The filter applies globally, so filtered-out spans aren't exported either
+    let subscriber = tracing_subscriber::registry()
+        .with(span_exporter)
+        .with(filter);
 
-    match config.format.as_str() {
+    let subscriber: Box<dyn Subscriber + Send + Sync> = match config.format.as_str() {
         "json" => {
-            subscriber
-                .with(tracing_subscriber::fmt::layer().json())
-                .try_init()?;
+            let format = tracing_subscriber::fmt::format().json();
+            Box::new(
+                subscriber.with(
+                    tracing_subscriber::fmt::layer()
+                        .json()
+                        .with_ansi(ansi)
+                        .event_format(StaticFields::json(format, &config.static_fields))
+                        .with_writer(writer),
+                ),
+            )
         }
         _ => {
-            subscriber
-                .with(tracing_subscriber::fmt::layer().pretty())
-                .try_init()?;
+            let format = tracing_subscriber::fmt::format().pretty();
+            Box::new(
+                subscriber.with(
+                    tracing_subscriber::fmt::layer()
+                        .pretty()
+                        .with_ansi(ansi)
+                        .event_format(StaticFields::text(format, &config.static_fields))
+                        .with_writer(writer),
+                ),
+            )
+        }
+    };
+
+    Ok((subscriber, LogLevelHandle(log_level)))
+}
+
+/// Adds fixed service metadata (name, environment, region, ...) to every
+/// event written by the wrapped formatter
+struct StaticFields<F> {
+    inner: F,
+    rendered: String,
+    json: bool,
+}
+
+impl<F> StaticFields<F> {
+    fn json(inner: F, fields: &BTreeMap<String, String>) -> Self {
+        // Spliced in right after the opening brace of each JSON line
+        let rendered = fields
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}:{},",
+                    serde_json::Value::from(key.as_str()),
+                    serde_json::Value::from(value.as_str())
+                )
+            })
+            .collect();
+        Self {
+            inner,
+            rendered,
+            json: true,
         }
     }
 
-    tracing::info!("Logger initialized with level: {}", config.level);
-    Ok(())
+    fn text(inner: F, fields: &BTreeMap<String, String>) -> Self {
+        let rendered = fields
+            .iter()
+            .map(|(key, value)| format!("{}={} ", key, value))
+            .collect();
+        Self {
+            inner,
+            rendered,
+            json: false,
+        }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for StaticFields<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        if self.rendered.is_empty() {
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        if !self.json {
+            writer.write_str(&self.rendered)?;
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        let mut line = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut line), event)?;
+        match line.strip_prefix('{') {
+            Some(rest) => write!(writer, "{{{}{}", self.rendered, rest),
+            None => writer.write_str(&line),
+        }
+    }
 }
@@ -1,5 +1,13 @@
+pub mod cidr;
+pub mod cli;
 pub mod config;
+pub mod http;
+pub mod log_file;
 pub mod logger;
+pub mod net;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
 
+pub use cli::CliArgs;
 pub use config::AppConfig;
 pub use logger::init_logging;
@@ -0,0 +1,71 @@
+use crate::utils::AppConfig;
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+/// Rewrites listener bind failures into something actionable. The error kind
+/// is preserved so callers can still match on it.
+pub fn bind_error(addr: SocketAddr, err: io::Error) -> io::Error {
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => io::Error::new(
+            err.kind(),
+            format!(
+                "permission denied binding to {}: ports below 1024 require root or the \
+                 CAP_NET_BIND_SERVICE capability (e.g. `setcap cap_net_bind_service=+ep <binary>`), \
+                 or configure server.port to 1024 or higher",
+                addr
+            ),
+        ),
+        io::ErrorKind::AddrInUse => {
+            io::Error::new(err.kind(), format!("{} is already in use", addr))
+        }
+        _ => io::Error::new(err.kind(), format!("failed to bind to {}: {}", addr, err)),
+    }
+}
+
+/// Binds a TCP listener with the socket options from `server.tcp_nodelay`,
+/// `server.so_keepalive_secs` and `server.listen_backlog`. Accepted
+/// connections inherit the first two from the listening socket.
+pub fn bind_tcp(addr: SocketAddr, config: &AppConfig) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_tcp_nodelay(config.server.tcp_nodelay)?;
+    if let Some(idle) = config.so_keepalive() {
+        socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(idle))?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(config.server.listen_backlog.min(i32::MAX as u32) as i32)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// Binds a Unix socket at `path`. An existing file there is refused, since
+/// another instance may still be serving on it, unless `force` is set, in
+/// which case it is removed first.
+#[cfg(unix)]
+pub fn bind_unix(path: &Path, force: bool) -> io::Result<UnixListener> {
+    if path.symlink_metadata().is_ok() {
+        if !force {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists; remove it or set server.unix_socket_force",
+                    path.display()
+                ),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("failed to bind to {}: {}", path.display(), e),
+        )
+    })
+}
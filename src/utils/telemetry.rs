@@ -0,0 +1,75 @@
+use crate::utils::config::TelemetryConfig;
+use anyhow::Result;
+use hyper::HeaderMap;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+pub type OtlpLayer<S> = (Box<dyn Layer<S> + Send + Sync>, SdkTracerProvider);
+
+// Appended to `telemetry.otlp_endpoint`, the collector's base URL
+const TRACES_PATH: &str = "/v1/traces";
+
+/// The layer bridging `tracing` spans to the OTLP/HTTP collector at
+/// `telemetry.otlp_endpoint`, and the provider exporting them, which flushes
+/// pending spans when the last handle to it is dropped. `None` when no
+/// endpoint is configured.
+pub fn otlp_layer<S>(config: &TelemetryConfig) -> Result<Option<OtlpLayer<S>>>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer).boxed();
+    Ok(Some((layer, provider)))
+}
+
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}{TRACES_PATH}")
+    }
+}
+
+/// Makes `span` a child of the trace named by the W3C `traceparent` header
+/// in `headers`, if there is one
+pub fn continue_trace(span: &Span, headers: &HeaderMap) {
+    let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    // Fails only when no OpenTelemetry layer is installed
+    let _ = span.set_parent(context);
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
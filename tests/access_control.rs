@@ -0,0 +1,113 @@
+mod common;
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusty_ssl::server::{AcceptPolicy, AccessControl, Decision};
+use rusty_ssl::utils::config::AccessControlConfig;
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, TtlController};
+
+fn access_control(allow: &[&str], deny: &[&str]) -> AccessControl {
+    AccessControl::new(&AccessControlConfig {
+        allow: allow.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+        deny: deny.iter().map(|cidr| cidr.parse().unwrap()).collect(),
+    })
+}
+
+fn ip(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+}
+
+#[test]
+fn test_denied_networks_are_refused_in_and_out_of_range() {
+    let access = access_control(&[], &["203.0.113.0/24", "2001:db8::/32"]);
+
+    assert!(!access.permits(ip("203.0.113.7")));
+    assert!(!access.permits(ip("2001:db8::1")));
+    assert!(access.permits(ip("203.0.114.7")));
+    assert!(access.permits(ip("2001:db9::1")));
+
+    let peer: SocketAddr = "203.0.113.7:4433".parse().unwrap();
+    assert_eq!(access.decide(peer), Decision::Reject);
+    let peer: SocketAddr = "[2001:db9::1]:4433".parse().unwrap();
+    assert_eq!(access.decide(peer), Decision::Accept);
+}
+
+#[test]
+fn test_allow_entry_overrides_deny_across_address_families() {
+    // Deny everything, except the monitoring subnets
+    let access = access_control(&["10.1.0.0/16", "fd00:1::/32"], &["0.0.0.0/0", "::/0"]);
+
+    assert!(access.permits(ip("10.1.2.3")));
+    assert!(access.permits(ip("fd00:1::5")));
+    assert!(!access.permits(ip("10.2.0.1")));
+    assert!(!access.permits(ip("fd00:2::5")));
+
+    // An IPv4-mapped IPv6 client matches the IPv4 entries
+    assert!(access.permits(ip("::ffff:10.1.2.3")));
+    assert!(!access.permits(ip("::ffff:10.2.0.1")));
+
+    // A v4 range never covers v6 clients and vice versa
+    let v4_only = access_control(&[], &["0.0.0.0/0"]);
+    assert!(v4_only.permits(ip("2001:db8::1")));
+    assert!(!v4_only.is_allowlisted(ip("10.1.2.3")));
+}
+
+#[tokio::test]
+async fn test_denied_peer_is_dropped_before_being_served() {
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let mut handler = ConnectionHandler::new(router, &config);
+    handler.set_accept_policy(Arc::new(access_control(&[], &["127.0.0.0/8"])));
+    let addr = common::spawn_handler(handler).await;
+    assert_eq!(common::get(addr, "/health/live").await.status, 0);
+
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let mut handler = ConnectionHandler::new(router, &config);
+    handler.set_accept_policy(Arc::new(access_control(&["127.0.0.1"], &["127.0.0.0/8"])));
+    let addr = common::spawn_handler(handler).await;
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+}
+
+#[tokio::test]
+async fn test_allowlisted_client_bypasses_rate_limit() {
+    let mut config = AppConfig::default();
+    config.rate_limit.requests_per_sec = 0.1;
+    config.rate_limit.burst = 1;
+    config.access_control.allow = vec!["127.0.0.0/8".parse().unwrap()];
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    for _ in 0..5 {
+        assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    }
+}
+
+#[tokio::test]
+async fn test_allowlisted_connections_never_expire() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(20),
+        Duration::from_millis(20),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_never_expire(vec!["192.0.2.0/24".parse().unwrap()]);
+    ttl_controller.register_connection(ip("192.0.2.10"));
+    ttl_controller.register_connection(ip("198.51.100.10"));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    ttl_controller.cleanup_expired_connections().await;
+
+    assert!(
+        ttl_controller
+            .get_connection_info(ip("192.0.2.10"))
+            .is_some()
+    );
+    assert!(
+        ttl_controller
+            .get_connection_info(ip("198.51.100.10"))
+            .is_none()
+    );
+    assert!(ttl_controller.force_cleanup_connection(ip("192.0.2.10")));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::TimeZone;
+use hyper::{Method, Version};
+use rusty_ssl::server::{AccessLog, AccessLogEntry};
+use rusty_ssl::utils::config::AccessLogFormat;
+use rusty_ssl::{AppConfig, Router};
+
+fn entry() -> AccessLogEntry {
+    AccessLogEntry {
+        client_ip: IpAddr::from([203, 0, 113, 7]),
+        method: Method::GET,
+        target: "/metrics?detail=true".to_string(),
+        version: Version::HTTP_11,
+        status: 200,
+        bytes: Some(512),
+        referer: None,
+        user_agent: Some("curl/8.5.0".to_string()),
+        time: chrono::Utc
+            .with_ymd_and_hms(2025, 10, 10, 13, 55, 36)
+            .unwrap(),
+        latency: Duration::from_micros(1500),
+    }
+}
+
+#[test]
+fn test_common_and_combined_lines() {
+    let entry = entry();
+    assert_eq!(
+        entry.format(AccessLogFormat::Common).unwrap(),
+        "203.0.113.7 - - [10/Oct/2025:13:55:36 +0000] \"GET /metrics?detail=true HTTP/1.1\" 200 512 1.500"
+    );
+    assert_eq!(
+        entry.format(AccessLogFormat::Combined).unwrap(),
+        "203.0.113.7 - - [10/Oct/2025:13:55:36 +0000] \"GET /metrics?detail=true HTTP/1.1\" 200 512 \"-\" \"curl/8.5.0\" 1.500"
+    );
+    assert_eq!(entry.format(AccessLogFormat::None), None);
+}
+
+#[test]
+fn test_unknown_size_and_quotes_are_escaped() {
+    let mut entry = entry();
+    entry.bytes = None;
+    entry.user_agent = Some("evil\" 200 0\nforged".to_string());
+    let line = entry.format(AccessLogFormat::Combined).unwrap();
+    assert!(line.contains("\" 200 - \"-\""), "{line}");
+    assert!(line.contains("\"evil\\\" 200 0\\x0aforged\""), "{line}");
+}
+
+#[tokio::test]
+async fn test_router_writes_one_line_per_request() {
+    let mut config = AppConfig::default();
+    config.logging.access_log_format = AccessLogFormat::Combined;
+    let buffer = common::LogBuffer::default();
+    let mut router = Router::new(common::ttl_controller(), &config);
+    router.set_access_log(AccessLog::new(
+        AccessLogFormat::Combined,
+        Box::new(buffer.clone()),
+    ));
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::send_raw(
+        addr,
+        "GET /nope?x=1 HTTP/1.1\r\nHost: localhost\r\nReferer: https://example.com/\r\n\
+         User-Agent: probe/1.0\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 404);
+
+    let contents = buffer.contents();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 1, "{contents}");
+    let line = lines[0];
+    assert!(line.starts_with("127.0.0.1 - - ["), "{line}");
+    let expected = format!(
+        "\"GET /nope?x=1 HTTP/1.1\" 404 {} \"https://example.com/\" \"probe/1.0\" ",
+        response.body.len()
+    );
+    assert!(line.contains(&expected), "{line}");
+}
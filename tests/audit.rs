@@ -0,0 +1,53 @@
+#![cfg(feature = "audit-sqlite")]
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use rusty_ssl::TtlController;
+use rusty_ssl::server::audit::SqliteAuditLog;
+
+#[tokio::test]
+async fn test_audit_log_records_registration_and_expiry() {
+    let db_path = std::env::temp_dir().join(format!("rusty-ssl-audit-{}.db", uuid::Uuid::new_v4()));
+    let audit_log = SqliteAuditLog::open(&db_path).unwrap();
+
+    let (event_tx, event_rx) = tokio::sync::mpsc::channel(16);
+    let writer = audit_log.spawn(event_rx);
+
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(1),
+        Duration::from_secs(1),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_event_sender(event_tx);
+
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+    ttl_controller.register_connection(ip);
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    ttl_controller.cleanup_expired_connections().await;
+
+    // Dropping the controller closes the channel and lets the writer finish
+    drop(ttl_controller);
+    writer.await.unwrap();
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let mut stmt = conn
+        .prepare("SELECT event, ip FROM connection_events ORDER BY id")
+        .unwrap();
+    let rows: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![
+            ("registered".to_string(), ip.to_string()),
+            ("expired".to_string(), ip.to_string()),
+        ]
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
@@ -0,0 +1,35 @@
+use std::net::IpAddr;
+
+use rusty_ssl::utils::cidr::{CidrError, IpCidr};
+
+#[test]
+fn test_cidr_contains() {
+    let cidr: IpCidr = "192.168.1.77/24".parse().unwrap();
+    assert_eq!(cidr.to_string(), "192.168.1.0/24");
+    assert!(cidr.contains("192.168.1.200".parse().unwrap()));
+    assert!(!cidr.contains("192.168.2.1".parse().unwrap()));
+    assert!(cidr.contains("::ffff:192.168.1.5".parse().unwrap()));
+
+    let v6: IpCidr = "2001:db8::/32".parse().unwrap();
+    assert!(v6.contains("2001:db8:ffff::1".parse().unwrap()));
+    assert!(!v6.contains("192.168.1.1".parse().unwrap()));
+
+    let host: IpCidr = "10.0.0.1".parse().unwrap();
+    assert_eq!(host.prefix(), 32);
+    assert!(!host.contains(IpAddr::from([10, 0, 0, 2])));
+
+    let everything: IpCidr = "0.0.0.0/0".parse().unwrap();
+    assert!(everything.contains("203.0.113.9".parse().unwrap()));
+}
+
+#[test]
+fn test_cidr_rejects_malformed() {
+    assert_eq!(
+        "10.0.0.0/33".parse::<IpCidr>(),
+        Err(CidrError::InvalidPrefix("10.0.0.0/33".to_string()))
+    );
+    assert_eq!(
+        "10.0.0/8".parse::<IpCidr>(),
+        Err(CidrError::InvalidAddress("10.0.0/8".to_string()))
+    );
+}
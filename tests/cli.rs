@@ -0,0 +1,114 @@
+use clap::Parser;
+use rusty_ssl::AppConfig;
+use rusty_ssl::utils::CliArgs;
+use rusty_ssl::utils::cli::ConfigFormat;
+
+fn temp_config(contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("rusty-ssl-{}.toml", uuid::Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_flags_override_config() {
+    let args = CliArgs::try_parse_from([
+        "rusty-ssl",
+        "--host",
+        "127.0.0.1",
+        "--port",
+        "9443",
+        "--cert",
+        "/etc/rusty-ssl/cert.pem",
+        "--key",
+        "/etc/rusty-ssl/key.pem",
+        "--log-level",
+        "debug",
+    ])
+    .unwrap();
+    let mut config = AppConfig::default();
+    args.apply(&mut config);
+
+    assert_eq!(config.server_addr().unwrap().to_string(), "127.0.0.1:9443");
+    assert_eq!(
+        config.ssl.cert_path.to_str(),
+        Some("/etc/rusty-ssl/cert.pem")
+    );
+    assert_eq!(config.ssl.key_path.to_str(), Some("/etc/rusty-ssl/key.pem"));
+    assert_eq!(config.logging.level, "debug");
+}
+
+#[test]
+fn test_no_flags_leave_config_untouched() {
+    let args = CliArgs::try_parse_from(["rusty-ssl"]).unwrap();
+    assert!(!args.print_config);
+    let mut config = AppConfig::default();
+    args.apply(&mut config);
+    assert_eq!(config.server_addr(), AppConfig::default().server_addr());
+    assert_eq!(config.logging.level, "info");
+}
+
+#[test]
+fn test_config_flag_reads_file_beneath_other_flags() {
+    let path = temp_config("[server]\nhost = \"127.0.0.1\"\nport = 7443\n");
+    let args = CliArgs::try_parse_from(["rusty-ssl", "--config", path.as_str(), "--port", "9443"])
+        .unwrap();
+    let config = args.load_config().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(config.server_addr().unwrap().to_string(), "127.0.0.1:9443");
+}
+
+#[test]
+fn test_missing_config_flag_file_is_an_error() {
+    let missing = "/nonexistent/rusty-ssl/cli-config.toml";
+    let args = CliArgs::try_parse_from(["rusty-ssl", "--config", missing]).unwrap();
+    let message = args.load_config().unwrap_err().to_string();
+    assert!(message.contains(missing), "{message}");
+}
+
+#[test]
+fn test_printed_config_loads_back() {
+    let mut config = AppConfig::default();
+    config.server.port = 9443;
+    let path = temp_config(&toml::to_string_pretty(&config).unwrap());
+    let loaded = AppConfig::load_from(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.server.port, 9443);
+}
+
+#[test]
+fn test_dumped_default_config_round_trips() {
+    let expected = serde_json::to_value(AppConfig::default()).unwrap();
+    for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+        let extension = format!("{format:?}").to_lowercase();
+        let path =
+            std::env::temp_dir().join(format!("rusty-ssl-{}.{extension}", uuid::Uuid::new_v4()));
+        let rendered = format.render(&AppConfig::default()).unwrap();
+        std::fs::write(&path, &rendered).unwrap();
+        let loaded = AppConfig::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = serde_json::to_value(loaded.unwrap()).unwrap();
+        assert_eq!(loaded, expected, "{format:?}");
+
+        // Complete on its own, without the defaults underneath
+        let parsed: AppConfig = match format {
+            ConfigFormat::Toml => toml::from_str(&rendered).unwrap(),
+            ConfigFormat::Json => serde_json::from_str(&rendered).unwrap(),
+        };
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            expected,
+            "{format:?}"
+        );
+    }
+}
+
+#[test]
+fn test_dump_default_config_format_defaults_to_toml() {
+    let args = CliArgs::try_parse_from(["rusty-ssl", "--dump-default-config"]).unwrap();
+    assert_eq!(args.dump_default_config, Some(ConfigFormat::Toml));
+    let args = CliArgs::try_parse_from(["rusty-ssl", "--dump-default-config", "json"]).unwrap();
+    assert_eq!(args.dump_default_config, Some(ConfigFormat::Json));
+}
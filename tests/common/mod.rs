@@ -0,0 +1,384 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use rusty_ssl::server::ssl_manager::install_crypto_provider;
+use rusty_ssl::server::{SharedSslManager, TlsAcceptor};
+use rusty_ssl::utils::config::SslConfig;
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, SslManager, TtlController};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RawResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).expect("response body is not valid JSON")
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl LogBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl std::io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captures log output on the current thread. Pair with the default
+/// current-thread `#[tokio::test]` runtime so spawned tasks are captured too.
+pub fn capture_logs() -> (tracing::subscriber::DefaultGuard, LogBuffer) {
+    let buffer = LogBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    (tracing::subscriber::set_default(subscriber), buffer)
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// SSL settings pointing at the long-lived localhost fixture certificate
+pub fn fixture_ssl_config() -> SslConfig {
+    let mut config = AppConfig::default().ssl;
+    config.cert_path = fixture_path("cert.pem");
+    config.key_path = fixture_path("key.pem");
+    config
+}
+
+/// Writes the named fixture PEMs, concatenated in order, to a temp file
+pub fn fixture_chain(names: &[&str]) -> PathBuf {
+    let pem: String = names
+        .iter()
+        .map(|name| std::fs::read_to_string(fixture_path(name)).unwrap())
+        .collect();
+    let path = std::env::temp_dir().join(format!("rusty-ssl-chain-{}.pem", uuid::Uuid::new_v4()));
+    std::fs::write(&path, pem).unwrap();
+    path
+}
+
+/// SSL config serving a certificate that expired on 2020-12-31
+pub fn expired_ssl_config() -> SslConfig {
+    let mut config = fixture_ssl_config();
+    config.cert_path = fixture_path("expired-cert.pem");
+    config
+}
+
+pub fn ttl_controller() -> Arc<TtlController> {
+    Arc::new(TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    ))
+}
+
+/// Token accepted by routers built with `stalling_router`
+pub const ADMIN_TOKEN: &str = "secret";
+
+/// Request for the handler `stalling_router` can hold up
+pub const RELOAD_CERT_REQUEST: &str = "POST /admin/reload-cert HTTP/1.1\r\nHost: localhost\r\n\
+     Authorization: Bearer secret\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Router whose `POST /admin/reload-cert` handler is stuck for as long as
+/// the returned SSL manager stays locked
+pub fn stalling_router(
+    ttl_controller: Arc<TtlController>,
+    mut config: AppConfig,
+) -> (Router, SharedSslManager) {
+    config.auth.tokens = vec![ADMIN_TOKEN.to_string()];
+    let ssl_manager = Arc::new(tokio::sync::Mutex::new(
+        SslManager::new(&fixture_ssl_config()).unwrap(),
+    ));
+    let mut router = Router::new(ttl_controller, &config);
+    router.set_ssl_manager(ssl_manager.clone());
+    (router, ssl_manager)
+}
+
+/// Serves the router over plaintext HTTP/1.1 on an ephemeral port
+pub async fn spawn_router(router: Arc<Router>) -> SocketAddr {
+    spawn_handler(ConnectionHandler::new(router, &AppConfig::default())).await
+}
+
+pub async fn spawn_handler(handler: ConnectionHandler) -> SocketAddr {
+    spawn_shared_handler(Arc::new(handler)).await
+}
+
+/// Like `spawn_handler`, keeping a handle so tests can adjust it while serving
+pub async fn spawn_shared_handler(handler: Arc<ConnectionHandler>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, remote_addr)) = listener.accept().await else {
+                return;
+            };
+            let Some(permit) = handler.admit(remote_addr).await else {
+                continue;
+            };
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let _permit = permit;
+                let _ = handler.serve(stream, remote_addr.ip()).await;
+            });
+        }
+    });
+
+    addr
+}
+
+/// Writes a raw request and reads the response until the server closes
+pub async fn send_raw(addr: SocketAddr, raw: &str) -> RawResponse {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    // A rejected connection may be reset mid-exchange; report whatever arrived
+    let _ = stream.write_all(raw.as_bytes()).await;
+
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf).await;
+    parse_response(&String::from_utf8_lossy(&buf))
+}
+
+pub async fn get(addr: SocketAddr, path: &str) -> RawResponse {
+    send_raw(
+        addr,
+        &format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"),
+    )
+    .await
+}
+
+pub fn parse_response(raw: &str) -> RawResponse {
+    let (head, body) = raw.split_once("\r\n\r\n").unwrap_or((raw, ""));
+    let mut lines = head.lines();
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .unwrap_or(0);
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    RawResponse {
+        status,
+        headers,
+        body: body.to_string(),
+    }
+}
+
+/// Accepts any server certificate (the fixture is a self-signed CA cert,
+/// which webpki refuses as an end entity) while still checking signatures
+#[derive(Debug)]
+struct TrustAnyServer(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for TrustAnyServer {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+pub fn tls_client_config() -> ClientConfig {
+    let provider = install_crypto_provider();
+    ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(TrustAnyServer(provider)))
+        .with_no_client_auth()
+}
+
+/// Client config presenting the fixture client certificate for mutual TLS
+pub fn tls_client_config_with_cert(cert: &str, key: &str) -> ClientConfig {
+    let provider = install_crypto_provider();
+    let chain = rustls_pemfile::certs(&mut std::io::BufReader::new(
+        std::fs::File::open(fixture_path(cert)).unwrap(),
+    ))
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+    let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(
+        std::fs::File::open(fixture_path(key)).unwrap(),
+    ))
+    .unwrap()
+    .unwrap();
+
+    ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(TrustAnyServer(provider)))
+        .with_client_auth_cert(chain, key)
+        .unwrap()
+}
+
+/// Serves the handler over TLS on an ephemeral port
+pub async fn spawn_tls_handler(acceptor: TlsAcceptor, handler: ConnectionHandler) -> SocketAddr {
+    let handler = Arc::new(handler);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, remote_addr)) = listener.accept().await else {
+                return;
+            };
+            let acceptor = acceptor.clone();
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let mut stream = stream;
+                let Some(client_ip) = handler.resolve_peer(&mut stream, remote_addr).await else {
+                    return;
+                };
+                if let Some(tls_stream) = handler.accept_tls(&acceptor, stream, client_ip).await {
+                    let _ = handler.serve_tls(tls_stream, client_ip).await;
+                }
+            });
+        }
+    });
+
+    addr
+}
+
+pub async fn tls_connect(
+    addr: SocketAddr,
+    config: ClientConfig,
+) -> std::io::Result<TlsStream<TcpStream>> {
+    tls_connect_as(addr, config, "localhost").await
+}
+
+/// Connects over TLS, sending `server_name` as SNI
+pub async fn tls_connect_as(
+    addr: SocketAddr,
+    config: ClientConfig,
+    server_name: &str,
+) -> std::io::Result<TlsStream<TcpStream>> {
+    let stream = TcpStream::connect(addr).await?;
+    tls_handshake(stream, config, server_name).await
+}
+
+/// Runs the client side of a TLS handshake over an open stream, e.g. one
+/// that already carried a PROXY protocol header
+pub async fn tls_handshake(
+    stream: TcpStream,
+    config: ClientConfig,
+    server_name: &str,
+) -> std::io::Result<TlsStream<TcpStream>> {
+    let server_name = ServerName::try_from(server_name.to_string()).unwrap();
+    TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+}
+
+/// Sends GET `path` over an HTTP/2 connection negotiated via ALPN,
+/// returning the status and the protocol the response came back on
+pub async fn h2_tls_get(addr: SocketAddr, path: &str) -> hyper::Result<(u16, hyper::Version)> {
+    let mut config = tls_client_config();
+    config.alpn_protocols = vec![b"h2".to_vec()];
+    let stream = tls_connect(addr, config).await.unwrap();
+
+    let (mut sender, connection) =
+        hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await?;
+    tokio::spawn(connection);
+
+    let request = hyper::Request::get(format!("https://localhost{path}"))
+        .body(http_body_util::Empty::<hyper::body::Bytes>::new())
+        .unwrap();
+    let response = sender.send_request(request).await?;
+    Ok((response.status().as_u16(), response.version()))
+}
+
+/// Writes a raw request over any stream and reads until the server closes
+pub async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, raw: &str) -> RawResponse {
+    let _ = stream.write_all(raw.as_bytes()).await;
+
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf).await;
+    parse_response(&String::from_utf8_lossy(&buf))
+}
+
+pub async fn tls_get(addr: SocketAddr, path: &str) -> RawResponse {
+    let mut stream = tls_connect(addr, tls_client_config()).await.unwrap();
+    exchange(
+        &mut stream,
+        &format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"),
+    )
+    .await
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use flate2::read::GzDecoder;
+use rusty_ssl::server::{Compressor, ContentEncoding};
+use rusty_ssl::{AppConfig, Router};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Repetitive JSON resembling a /metrics document
+fn sample_body() -> Vec<u8> {
+    (0..2000)
+        .map(|i| {
+            format!(
+                "{{\"ip\":\"203.0.113.{}\",\"requests\":{}}},",
+                i % 250,
+                i * 7
+            )
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[test]
+fn test_higher_gzip_level_is_no_larger() {
+    let body = sample_body();
+    let fast = Compressor::new(1, 4)
+        .compress(ContentEncoding::Gzip, &body)
+        .unwrap();
+    let small = Compressor::new(9, 4)
+        .compress(ContentEncoding::Gzip, &body)
+        .unwrap();
+    assert!(
+        small.len() <= fast.len(),
+        "{} > {}",
+        small.len(),
+        fast.len()
+    );
+
+    let mut decoded = Vec::new();
+    GzDecoder::new(&small[..])
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, body);
+}
+
+#[test]
+fn test_higher_brotli_quality_is_no_larger() {
+    let body = sample_body();
+    let fast = Compressor::new(6, 1)
+        .compress(ContentEncoding::Brotli, &body)
+        .unwrap();
+    let small = Compressor::new(6, 11)
+        .compress(ContentEncoding::Brotli, &body)
+        .unwrap();
+    assert!(
+        small.len() <= fast.len(),
+        "{} > {}",
+        small.len(),
+        fast.len()
+    );
+}
+
+#[test]
+fn test_compression_levels_come_from_config_and_are_clamped() {
+    let mut config = AppConfig::default();
+    config.server.compression_gzip_level = 42;
+    config.server.compression_brotli_quality = 9;
+
+    let compressor = config.compressor();
+    assert_eq!(compressor.gzip_level(), 9);
+    assert_eq!(compressor.brotli_quality(), 9);
+}
+
+#[test]
+fn test_accept_encoding_negotiation() {
+    use ContentEncoding::{Brotli, Gzip};
+
+    assert_eq!(ContentEncoding::negotiate("gzip"), Some(Gzip));
+    assert_eq!(
+        ContentEncoding::negotiate("gzip, deflate, br"),
+        Some(Brotli)
+    );
+    assert_eq!(
+        ContentEncoding::negotiate("br;q=0.5, gzip;q=0.8"),
+        Some(Gzip)
+    );
+    assert_eq!(ContentEncoding::negotiate("*"), Some(Brotli));
+    assert_eq!(ContentEncoding::negotiate("br;q=0, *;q=0.1"), Some(Gzip));
+    assert_eq!(ContentEncoding::negotiate("identity"), None);
+    assert_eq!(ContentEncoding::negotiate("gzip;q=0"), None);
+    assert_eq!(ContentEncoding::negotiate(""), None);
+}
+
+/// Sends GET `path` with `Accept-Encoding`, returning the raw head and the
+/// undecoded body bytes
+async fn get_encoded(addr: SocketAddr, path: &str, accept_encoding: &str) -> (String, Vec<u8>) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(
+            format!(
+                "GET {path} HTTP/1.1\r\nHost: localhost\r\n\
+                 Accept-Encoding: {accept_encoding}\r\nConnection: close\r\n\r\n"
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).await.unwrap();
+
+    let split = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8(raw[..split].to_vec()).unwrap();
+    (head, raw[split + 4..].to_vec())
+}
+
+async fn spawn_router(config: &AppConfig) -> SocketAddr {
+    common::spawn_router(Arc::new(Router::new(common::ttl_controller(), config))).await
+}
+
+#[tokio::test]
+async fn test_router_compresses_negotiated_responses() {
+    let addr = spawn_router(&AppConfig::default()).await;
+    let plain = common::get(addr, "/").await;
+    assert_eq!(plain.header("Content-Encoding"), None);
+    assert_eq!(plain.header("Vary"), Some("Accept-Encoding"));
+
+    let (head, body) = get_encoded(addr, "/", "gzip").await;
+    let head = common::parse_response(&head);
+    assert_eq!(head.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(
+        head.header("Content-Length"),
+        Some(body.len().to_string().as_str())
+    );
+    assert!(body.len() < plain.body.len());
+    let mut decoded = String::new();
+    GzDecoder::new(&body[..])
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, plain.body);
+
+    let (head, body) = get_encoded(addr, "/", "gzip, br").await;
+    assert_eq!(
+        common::parse_response(&head).header("Content-Encoding"),
+        Some("br")
+    );
+    let mut decoded = String::new();
+    brotli::Decompressor::new(&body[..], 4096)
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, plain.body);
+}
+
+#[tokio::test]
+async fn test_small_responses_and_disabled_compression_go_out_as_is() {
+    // /health is well under the default 1 KiB threshold
+    let addr = spawn_router(&AppConfig::default()).await;
+    let (head, body) = get_encoded(addr, "/health", "gzip").await;
+    assert_eq!(
+        common::parse_response(&head).header("Content-Encoding"),
+        None
+    );
+    assert!(serde_json::from_slice::<serde_json::Value>(&body).is_ok());
+
+    let mut config = AppConfig::default();
+    config.server.compression_enabled = false;
+    let addr = spawn_router(&config).await;
+    let (head, _) = get_encoded(addr, "/", "gzip, br").await;
+    let head = common::parse_response(&head);
+    assert_eq!(head.header("Content-Encoding"), None);
+    assert_eq!(head.header("Vary"), None);
+}
@@ -0,0 +1,18 @@
+// Tests that set environment variables live in their own binary, since
+// `set_var` races with any concurrent read, config loading included
+
+use rusty_ssl::AppConfig;
+
+#[test]
+fn test_missing_config_path_error_names_file_and_env_var() {
+    let missing = "/nonexistent/rusty-ssl/missing-config.toml";
+    // SAFETY: the only test in this binary, so nothing reads the
+    // environment concurrently
+    unsafe { std::env::set_var("RUSTY_SSL_CONFIG_PATH", missing) };
+    let result = AppConfig::load();
+    unsafe { std::env::remove_var("RUSTY_SSL_CONFIG_PATH") };
+
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains(missing), "{message}");
+    assert!(message.contains("RUSTY_SSL_CONFIG_PATH"), "{message}");
+}
@@ -0,0 +1,656 @@
+mod common;
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use rusty_ssl::server::{drain_connections, serve_listener};
+use rusty_ssl::{
+    AcceptPolicy, AppConfig, ConnectionHandler, Decision, Router, SslManager, TtlController,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+#[tokio::test]
+async fn test_slow_header_client_is_disconnected() {
+    let mut config = AppConfig::default();
+    config.server.header_read_timeout_ms = 200;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_handler(ConnectionHandler::new(router, &config)).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = b"GET /health HTTP/1.1\r\nHost: localhost\r\nX-Padding: slowloris\r\n\r\n";
+    let started = Instant::now();
+
+    // Dribble the headers one byte at a time, well past the timeout
+    for byte in request {
+        if stream.write_all(&[*byte]).await.is_err() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+
+    let mut buf = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut buf))
+        .await
+        .expect("server kept the slow connection open");
+
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    let response = String::from_utf8_lossy(&buf);
+    assert!(
+        !response.starts_with("HTTP/1.1 200"),
+        "slow client was served: {response}"
+    );
+}
+
+struct RejectIp(IpAddr);
+
+impl AcceptPolicy for RejectIp {
+    fn decide(&self, peer: SocketAddr) -> Decision {
+        if peer.ip() == self.0 {
+            Decision::Reject
+        } else {
+            Decision::Accept
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_accept_policy_rejects_ip() {
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let stats = router.stats();
+
+    let mut handler = ConnectionHandler::new(router, &config);
+    handler.set_accept_policy(Arc::new(RejectIp(IpAddr::V4(Ipv4Addr::LOCALHOST))));
+    let addr = common::spawn_handler(handler).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 0);
+    assert_eq!(stats.rejected_connections.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_connection_summary_on_clean_close() {
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let (summary_tx, mut summary_rx) = tokio::sync::mpsc::channel(4);
+
+    let mut handler = ConnectionHandler::new(router, &config);
+    handler.set_summary_sender(summary_tx);
+    let addr = common::spawn_handler(handler).await;
+
+    let requests = "GET /health/live HTTP/1.1\r\nHost: localhost\r\n\r\n\
+                    GET /health/ready HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(requests.as_bytes()).await.unwrap();
+    let mut received = Vec::new();
+    stream.read_to_end(&mut received).await.unwrap();
+
+    let summary = tokio::time::timeout(Duration::from_secs(5), summary_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(summary.clean);
+    assert_eq!(summary.request_count, 2);
+    assert_eq!(summary.last_path.as_deref(), Some("/health/ready"));
+    assert_eq!(summary.protocol, Some(hyper::Version::HTTP_11));
+    assert_eq!(summary.bytes_in, requests.len() as u64);
+    assert_eq!(summary.bytes_out, received.len() as u64);
+}
+
+#[tokio::test]
+async fn test_reconnect_within_grace_keeps_the_tracked_connection() {
+    let config = AppConfig::default();
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(20),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_reconnect_grace(Duration::from_secs(30));
+    let ttl_controller = Arc::new(ttl_controller);
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let (summary_tx, mut summary_rx) = tokio::sync::mpsc::channel(4);
+    let mut handler = ConnectionHandler::new(router, &config);
+    handler.set_summary_sender(summary_tx);
+    let addr = common::spawn_handler(handler).await;
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    tokio::time::timeout(Duration::from_secs(5), summary_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let original = ttl_controller.get_connection_info(ip).unwrap();
+
+    // Idle past its TTL, but the connection closed within the grace window
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    ttl_controller.cleanup_expired_connections().await;
+    assert!(ttl_controller.get_connection_info(ip).is_some());
+
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    let resumed = ttl_controller.get_connection_info(ip).unwrap();
+    assert_eq!(resumed.id, original.id);
+    assert_eq!(resumed.established_at, original.established_at);
+    assert!(resumed.request_count > original.request_count);
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.total_connections, 1);
+    assert_eq!(stats.expired_connections, 0);
+}
+
+#[tokio::test]
+async fn test_slow_reader_is_dropped_and_counted() {
+    let mut config = AppConfig::default();
+    config.server.write_timeout_ms = 200;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let stats = router.stats();
+    let addr = common::spawn_handler(ConnectionHandler::new(router, &config)).await;
+
+    // Pipeline far more response data than socket buffers hold, then never read
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let requests = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".repeat(5_000);
+    let writer = tokio::spawn(async move {
+        let _ = stream.write_all(requests.as_bytes()).await;
+        stream
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while stats.slow_client_drops.load(Ordering::Relaxed) == 0 {
+        assert!(Instant::now() < deadline, "slow reader was never dropped");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    drop(writer);
+}
+
+/// Stands in for a TLS stream whose socket has filled up: writes land in
+/// the session buffer at once, but flushing them never completes
+struct UnflushableIo {
+    request: &'static [u8],
+}
+
+impl AsyncRead for UnflushableIo {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        if self.request.is_empty() {
+            return std::task::Poll::Pending;
+        }
+        let (chunk, rest) = self
+            .request
+            .split_at(self.request.len().min(buf.remaining()));
+        buf.put_slice(chunk);
+        self.request = rest;
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UnflushableIo {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Pending
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Pending
+    }
+}
+
+#[tokio::test]
+async fn test_slow_reader_of_small_response_is_dropped_and_counted() {
+    let mut config = AppConfig::default();
+    config.server.write_timeout_ms = 200;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let stats = router.stats();
+    let handler = ConnectionHandler::new(router, &config);
+
+    let io = UnflushableIo {
+        request: b"GET /health/live HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    };
+    let served = tokio::time::timeout(
+        Duration::from_secs(5),
+        handler.serve(io, IpAddr::V4(Ipv4Addr::LOCALHOST)),
+    )
+    .await
+    .expect("stalled flush held the connection open");
+    assert!(served.is_err());
+    assert_eq!(stats.slow_client_drops.load(Ordering::Relaxed), 1);
+}
+
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_RST_STREAM: u8 = 0x3;
+const FRAME_SETTINGS: u8 = 0x4;
+const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const REFUSED_STREAM: u32 = 0x7;
+
+fn h2_frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len() as u32;
+    let mut frame = vec![(len >> 16) as u8, (len >> 8) as u8, len as u8, kind, flags];
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+async fn read_h2_frame(stream: &mut TcpStream) -> (u8, u8, u32, Vec<u8>) {
+    let mut header = [0u8; 9];
+    stream.read_exact(&mut header).await.unwrap();
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await.unwrap();
+    (header[3], header[4], stream_id, payload)
+}
+
+#[tokio::test]
+async fn test_http2_excess_streams_are_refused() {
+    let mut config = AppConfig::default();
+    config.server.http2_max_concurrent_streams = 1;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_handler(ConnectionHandler::new(router, &config)).await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut hello = H2_PREFACE.to_vec();
+    hello.extend(h2_frame(FRAME_SETTINGS, 0, 0, &[]));
+    stream.write_all(&hello).await.unwrap();
+
+    // The server advertises the limit in its initial SETTINGS frame
+    let (kind, _, _, payload) = read_h2_frame(&mut stream).await;
+    assert_eq!(kind, FRAME_SETTINGS);
+    let advertised = payload.chunks(6).find_map(|setting| {
+        (u16::from_be_bytes([setting[0], setting[1]]) == SETTINGS_MAX_CONCURRENT_STREAMS)
+            .then(|| u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]))
+    });
+    assert_eq!(advertised, Some(1));
+
+    // GET /health/live, HPACK-encoded without the dynamic table
+    let mut block = vec![0x82, 0x86, 0x04, 12];
+    block.extend_from_slice(b"/health/live");
+    block.extend_from_slice(&[0x01, 9]);
+    block.extend_from_slice(b"localhost");
+
+    // Open three streams at once; only one may be in flight
+    let mut burst = h2_frame(FRAME_SETTINGS, 0x1, 0, &[]);
+    for stream_id in [1, 3, 5] {
+        burst.extend(h2_frame(FRAME_HEADERS, 0x5, stream_id, &block));
+    }
+    stream.write_all(&burst).await.unwrap();
+
+    let refused = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let (kind, _, stream_id, payload) = read_h2_frame(&mut stream).await;
+            if kind == FRAME_RST_STREAM
+                && u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]])
+                    == REFUSED_STREAM
+            {
+                return stream_id;
+            }
+        }
+    })
+    .await
+    .expect("no stream was refused");
+    assert!(refused == 3 || refused == 5);
+}
+
+/// Sends one keep-alive request and reads its complete response
+async fn keep_alive_get<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, path: &str) -> String {
+    let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut received = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed mid-response");
+        received.extend_from_slice(&buf[..n]);
+        let response = common::parse_response(&String::from_utf8_lossy(&received));
+        let expected: usize = response
+            .header("content-length")
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(usize::MAX);
+        if response.body.len() >= expected {
+            return String::from_utf8_lossy(&received).into_owned();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_lowering_max_connections_keeps_existing_connections() {
+    let mut config = AppConfig::default();
+    config.server.max_connections = 3;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = Arc::new(ConnectionHandler::new(router, &config));
+    let addr = common::spawn_shared_handler(handler.clone()).await;
+
+    let mut first = TcpStream::connect(addr).await.unwrap();
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    keep_alive_get(&mut first, "/health/live").await;
+    keep_alive_get(&mut second, "/health/live").await;
+    assert_eq!(handler.active_connections(), 2);
+
+    handler.set_max_connections(1);
+
+    // New connections are refused while usage exceeds the lowered cap
+    assert_eq!(common::get(addr, "/health/live").await.status, 0);
+
+    // Existing connections keep being served
+    assert!(
+        keep_alive_get(&mut first, "/health/live")
+            .await
+            .starts_with("HTTP/1.1 200")
+    );
+    assert!(
+        keep_alive_get(&mut second, "/health/live")
+            .await
+            .starts_with("HTTP/1.1 200")
+    );
+
+    // Once usage drops under the cap, new connections are admitted again
+    drop(first);
+    drop(second);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while handler.active_connections() > 0 {
+        assert!(
+            Instant::now() < deadline,
+            "closed connections were never released"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+}
+
+#[tokio::test]
+async fn test_request_past_request_timeout_drops_connection() {
+    let mut config = AppConfig::default();
+    config.server.request_timeout_secs = 1;
+    let ttl_controller = Arc::new(TtlController::new(
+        Duration::from_secs(1),
+        Duration::from_secs(1),
+        Duration::from_secs(60),
+        1000,
+    ));
+    let (router, ssl_manager) = common::stalling_router(ttl_controller.clone(), config.clone());
+    let addr = common::spawn_handler(ConnectionHandler::new(Arc::new(router), &config)).await;
+
+    // Holding the SSL manager stalls the reload past the timeout
+    let guard = ssl_manager.lock().await;
+    let started = Instant::now();
+    let response = common::send_raw(addr, common::RELOAD_CERT_REQUEST).await;
+    let elapsed = started.elapsed();
+    drop(guard);
+    assert_eq!(response.status, 0, "stalled request got a response");
+    assert!(
+        elapsed >= Duration::from_secs(1),
+        "dropped after {elapsed:?}"
+    );
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "dropped after {elapsed:?}"
+    );
+
+    // The abandoned request's connection entry still expires normally
+    assert_eq!(ttl_controller.get_stats().active_connections, 1);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    ttl_controller.cleanup_expired_connections().await;
+    assert_eq!(ttl_controller.get_stats().active_connections, 0);
+}
+
+#[tokio::test]
+async fn test_shutdown_finishes_in_flight_request_then_closes() {
+    let config = AppConfig::default();
+    let (router, ssl_manager) = common::stalling_router(common::ttl_controller(), config.clone());
+    let handler = Arc::new(ConnectionHandler::new(Arc::new(router), &config));
+    let shutdown = handler.shutdown_token();
+    let addr = common::spawn_shared_handler(handler).await;
+
+    // Holding the SSL manager keeps the request in flight across the shutdown
+    let guard = ssl_manager.lock().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(
+            b"POST /admin/reload-cert HTTP/1.1\r\nHost: localhost\r\n\
+              Authorization: Bearer secret\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown.cancel();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(guard);
+
+    // The response is delivered and the keep-alive connection then closed
+    let mut received = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut received))
+        .await
+        .expect("connection stayed open after shutdown")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&received).starts_with("HTTP/1.1 200"));
+}
+
+#[tokio::test]
+async fn test_drain_connections_aborts_stragglers_after_grace() {
+    let mut connections = JoinSet::new();
+    connections.spawn(async {});
+    connections.spawn(tokio::time::sleep(Duration::from_millis(50)));
+    assert_eq!(
+        drain_connections(&mut connections, Duration::from_secs(5)).await,
+        0
+    );
+
+    connections.spawn(std::future::pending());
+    connections.spawn(async {});
+    let started = Instant::now();
+    assert_eq!(
+        drain_connections(&mut connections, Duration::from_millis(200)).await,
+        1
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_metrics_report_connection_permits_in_use() {
+    let mut config = AppConfig::default();
+    config.server.max_connections = 2;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_handler(ConnectionHandler::new(router, &config)).await;
+
+    let mut held = TcpStream::connect(addr).await.unwrap();
+    keep_alive_get(&mut held, "/health/live").await;
+
+    // The held connection plus the one asking for metrics
+    let response = common::get(addr, "/metrics").await;
+    assert_eq!(response.status, 200);
+    let metrics: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(metrics["server_stats"]["connection_permits_in_use"], 2);
+    assert_eq!(metrics["server_stats"]["max_connections"], 2);
+
+    // Both slots are taken while the held connection stays open
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    keep_alive_get(&mut second, "/health/live").await;
+    assert_eq!(common::get(addr, "/health/live").await.status, 0);
+}
+
+#[tokio::test]
+async fn test_waiting_for_connection_slot_queues_clients_at_the_cap() {
+    let mut config = AppConfig::default();
+    config.server.max_connections = 1;
+    config.server.wait_for_connection_slot = true;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = Arc::new(ConnectionHandler::new(router, &config));
+    let addr = common::spawn_shared_handler(handler.clone()).await;
+
+    let mut held = TcpStream::connect(addr).await.unwrap();
+    keep_alive_get(&mut held, "/health/live").await;
+
+    let mut queued = tokio::spawn(common::get(addr, "/health/live"));
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), &mut queued)
+            .await
+            .is_err(),
+        "client beyond the cap was served before a slot freed up"
+    );
+    assert_eq!(handler.active_connections(), 1);
+
+    // Freeing the slot lets the queued client through instead of dropping it
+    drop(held);
+    let response = tokio::time::timeout(Duration::from_secs(5), queued)
+        .await
+        .expect("queued client was never admitted")
+        .unwrap();
+    assert_eq!(response.status, 200);
+}
+
+/// TLS listener advertising h2 via ALPN, with `http2_enabled` as given
+async fn spawn_h2_server(http2_enabled: bool) -> (SocketAddr, Arc<TtlController>) {
+    let mut config = AppConfig::default();
+    config.server.http2_enabled = http2_enabled;
+    config.ssl = common::fixture_ssl_config();
+    config.ssl.alpn = vec!["h2".to_string(), "http/1.1".to_string()];
+    let ssl_manager = SslManager::new(&config.ssl).unwrap();
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_tls_handler(
+        ssl_manager.acceptor(),
+        ConnectionHandler::new(router, &config),
+    )
+    .await;
+    (addr, ttl_controller)
+}
+
+#[tokio::test]
+async fn test_h2_client_is_served_over_http2() {
+    let (addr, ttl_controller) = spawn_h2_server(true).await;
+
+    let (status, version) = common::h2_tls_get(addr, "/health").await.unwrap();
+    assert_eq!(status, 200);
+    assert_eq!(version, hyper::Version::HTTP_2);
+
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.http2_connections, 1);
+    assert_eq!(stats.http1_connections, 0);
+}
+
+#[tokio::test]
+async fn test_http2_disabled_refuses_h2_preface() {
+    let (addr, _) = spawn_h2_server(false).await;
+    assert!(common::h2_tls_get(addr, "/health").await.is_err());
+}
+
+#[tokio::test]
+async fn test_close_notify_is_counted_separately_from_abrupt_close() {
+    let config = AppConfig::default();
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let stats = router.stats();
+    let addr = common::spawn_tls_handler(
+        ssl_manager.acceptor(),
+        ConnectionHandler::new(router, &config),
+    )
+    .await;
+
+    // Sends close_notify before closing the socket
+    let mut polite = common::tls_connect(addr, common::tls_client_config())
+        .await
+        .unwrap();
+    keep_alive_get(&mut polite, "/health").await;
+    polite.shutdown().await.unwrap();
+
+    // Drops the TCP connection mid-session
+    let mut rude = common::tls_connect(addr, common::tls_client_config())
+        .await
+        .unwrap();
+    keep_alive_get(&mut rude, "/health").await;
+    let (tcp, _) = rude.into_inner();
+    drop(tcp);
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while stats.clean_close.load(Ordering::Relaxed) + stats.abrupt_close.load(Ordering::Relaxed) < 2
+        && Instant::now() < deadline
+    {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(stats.clean_close.load(Ordering::Relaxed), 1);
+    assert_eq!(stats.abrupt_close.load(Ordering::Relaxed), 1);
+    assert_eq!(stats.to_json()["abrupt_close"], 1);
+}
+
+#[tokio::test]
+async fn test_every_listener_serves_until_shutdown() {
+    let config = AppConfig::default();
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = Arc::new(ConnectionHandler::new(router, &config));
+
+    let mut listeners = JoinSet::new();
+    let mut addrs = Vec::new();
+    for _ in 0..2 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        addrs.push(listener.local_addr().unwrap());
+        listeners.spawn(serve_listener(
+            handler.clone(),
+            listener,
+            ssl_manager.acceptor(),
+        ));
+    }
+
+    for addr in addrs {
+        let response = common::tls_get(addr, "/health").await;
+        assert_eq!(response.status, 200, "{addr}");
+    }
+
+    // One shutdown token stops every accept loop
+    handler.shutdown_token().cancel();
+    let stopped = tokio::time::timeout(Duration::from_secs(5), listeners.join_all())
+        .await
+        .expect("a listener kept accepting after shutdown");
+    assert_eq!(stopped.len(), 2);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_serves_plaintext_and_tracks_placeholder_peer() {
+    use rusty_ssl::server::{UNIX_SOCKET_PEER, serve_unix_listener};
+    use rusty_ssl::utils::net::bind_unix;
+
+    let config = AppConfig::default();
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let handler = Arc::new(ConnectionHandler::new(router, &config));
+    let path = std::env::temp_dir().join(format!("rusty-ssl-{}.sock", uuid::Uuid::new_v4()));
+    let listener = bind_unix(&path, false).unwrap();
+    let server = tokio::spawn(serve_unix_listener(handler.clone(), listener, None));
+
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let response = common::exchange(
+        &mut stream,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert!(
+        ttl_controller
+            .get_connection_info(UNIX_SOCKET_PEER.ip())
+            .is_some()
+    );
+
+    handler.shutdown_token().cancel();
+    server.await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rusty_ssl::{AppConfig, Router};
+
+async fn spawn_cors_router(origins: &[&str], allow_credentials: bool) -> SocketAddr {
+    let mut config = AppConfig::default();
+    config.cors.allowed_origins = origins.iter().map(|origin| origin.to_string()).collect();
+    config.cors.allowed_methods = vec!["GET".to_string(), "POST".to_string()];
+    config.cors.allow_credentials = allow_credentials;
+    config.cors.max_age_secs = 120;
+    common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await
+}
+
+fn preflight(origin: &str) -> String {
+    format!(
+        "OPTIONS /metrics HTTP/1.1\r\nHost: localhost\r\nOrigin: {origin}\r\n\
+         Access-Control-Request-Method: GET\r\n\
+         Access-Control-Request-Headers: authorization\r\nConnection: close\r\n\r\n"
+    )
+}
+
+fn get_from(origin: &str, path: &str) -> String {
+    format!(
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nOrigin: {origin}\r\nConnection: close\r\n\r\n"
+    )
+}
+
+#[tokio::test]
+async fn test_preflight_from_allowed_origin_is_204() {
+    let addr = spawn_cors_router(&["https://dash.example.com"], false).await;
+
+    let response = common::send_raw(addr, &preflight("https://dash.example.com")).await;
+    assert_eq!(response.status, 204);
+    assert_eq!(
+        response.header("Access-Control-Allow-Origin"),
+        Some("https://dash.example.com")
+    );
+    assert_eq!(
+        response.header("Access-Control-Allow-Methods"),
+        Some("GET, POST")
+    );
+    assert_eq!(
+        response.header("Access-Control-Allow-Headers"),
+        Some("authorization")
+    );
+    assert_eq!(response.header("Access-Control-Max-Age"), Some("120"));
+    assert_eq!(response.header("Access-Control-Allow-Credentials"), None);
+
+    // Other origins get no CORS headers, and OPTIONS isn't a route
+    let response = common::send_raw(addr, &preflight("https://evil.example.com")).await;
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Access-Control-Allow-Origin"), None);
+}
+
+#[tokio::test]
+async fn test_simple_get_from_allowed_origin_carries_cors_headers() {
+    let addr = spawn_cors_router(&["https://dash.example.com"], false).await;
+
+    let response = common::send_raw(addr, &get_from("https://dash.example.com", "/metrics")).await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Access-Control-Allow-Origin"),
+        Some("https://dash.example.com")
+    );
+
+    let response = common::send_raw(addr, &get_from("https://evil.example.com", "/metrics")).await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Access-Control-Allow-Origin"), None);
+
+    // Without an Origin header the request isn't cross-origin at all
+    let response = common::get(addr, "/metrics").await;
+    assert_eq!(response.header("Access-Control-Allow-Origin"), None);
+}
+
+#[tokio::test]
+async fn test_wildcard_origin_is_echoed_only_with_credentials() {
+    let addr = spawn_cors_router(&["*"], false).await;
+    let response = common::send_raw(addr, &get_from("https://any.example.com", "/health")).await;
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("*"));
+
+    let addr = spawn_cors_router(&["*"], true).await;
+    let response = common::send_raw(addr, &get_from("https://any.example.com", "/health")).await;
+    assert_eq!(
+        response.header("Access-Control-Allow-Origin"),
+        Some("https://any.example.com")
+    );
+    assert_eq!(
+        response.header("Access-Control-Allow-Credentials"),
+        Some("true")
+    );
+}
+
+#[test]
+fn test_unknown_cors_method_fails_validation() {
+    let mut config = AppConfig::default();
+    config.cors.allowed_methods = vec!["GET".to_string(), "NOT A METHOD".to_string()];
+    let errors = config.validate().unwrap_err();
+    assert!(
+        errors.iter().any(|error| error.to_string()
+            == "cors.allowed_methods: 'NOT A METHOD' is not an HTTP method")
+    );
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use hyper::HeaderMap;
+use hyper::header::HeaderValue;
+use rusty_ssl::server::forwarded::client_ip;
+use rusty_ssl::utils::cidr::IpCidr;
+use rusty_ssl::{AppConfig, Router};
+
+fn ip(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+}
+
+fn forwarded_for(values: &[&str]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for value in values {
+        headers.append("X-Forwarded-For", HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+fn proxies() -> Vec<IpCidr> {
+    vec!["10.0.0.0/8".parse().unwrap(), "fd00::/8".parse().unwrap()]
+}
+
+#[test]
+fn test_forged_header_from_untrusted_peer_is_ignored() {
+    let headers = forwarded_for(&["198.51.100.7"]);
+    assert_eq!(
+        client_ip(ip("203.0.113.9"), &headers, &proxies()),
+        ip("203.0.113.9")
+    );
+    assert_eq!(
+        client_ip(ip("203.0.113.9"), &headers, &[]),
+        ip("203.0.113.9")
+    );
+}
+
+#[test]
+fn test_rightmost_untrusted_hop_is_the_client() {
+    // The client prepended a spoofed hop; the load balancer appended the real one
+    let headers = forwarded_for(&["1.2.3.4, 198.51.100.7, 10.0.0.2"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies()),
+        ip("198.51.100.7")
+    );
+
+    // Repeated headers are read as one list
+    let headers = forwarded_for(&["1.2.3.4", "198.51.100.7"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies()),
+        ip("198.51.100.7")
+    );
+
+    let headers = forwarded_for(&["2001:db8::7, fd00::2"]);
+    assert_eq!(
+        client_ip(ip("fd00::1"), &headers, &proxies()),
+        ip("2001:db8::7")
+    );
+}
+
+#[test]
+fn test_forwarded_hops_may_carry_ports() {
+    let headers = forwarded_for(&["198.51.100.7:4711"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies()),
+        ip("198.51.100.7")
+    );
+
+    let headers = forwarded_for(&["[2001:db8::7]:4711"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies()),
+        ip("2001:db8::7")
+    );
+}
+
+#[test]
+fn test_malformed_or_missing_hops_fall_back_to_last_vouched_address() {
+    let proxies = proxies();
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &HeaderMap::new(), &proxies),
+        ip("10.0.0.1")
+    );
+
+    let headers = forwarded_for(&["198.51.100.7, garbage"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies),
+        ip("10.0.0.1")
+    );
+
+    let headers = forwarded_for(&["198.51.100.7, garbage, 10.0.0.3"]);
+    assert_eq!(
+        client_ip(ip("10.0.0.1"), &headers, &proxies),
+        ip("10.0.0.3")
+    );
+}
+
+#[tokio::test]
+async fn test_router_tracks_forwarded_client_only_behind_trusted_proxy() {
+    let request = "GET /whoami HTTP/1.1\r\nHost: localhost\r\n\
+                   X-Forwarded-For: 198.51.100.7\r\nConnection: close\r\n\r\n";
+
+    // The test client connects from 127.0.0.1, which is not trusted here
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+    let whoami = common::send_raw(addr, request).await.json();
+    assert_eq!(whoami["ip"], "127.0.0.1");
+    assert!(
+        ttl_controller
+            .get_connection_info(ip("198.51.100.7"))
+            .is_none()
+    );
+
+    let mut config = AppConfig::default();
+    config.server.trusted_proxies = vec!["127.0.0.1".parse().unwrap()];
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_router(router).await;
+    let whoami = common::send_raw(addr, request).await.json();
+    assert_eq!(whoami["ip"], "198.51.100.7");
+    let connection = ttl_controller
+        .get_connection_info(ip("198.51.100.7"))
+        .unwrap();
+    assert_eq!(connection.peer_ip, ip("127.0.0.1"));
+    assert!(
+        ttl_controller
+            .get_connection_info(ip("127.0.0.1"))
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_denied_forwarded_client_is_refused_behind_trusted_proxy() {
+    let request = "GET /whoami HTTP/1.1\r\nHost: localhost\r\n\
+                   X-Forwarded-For: 203.0.113.7\r\nConnection: close\r\n\r\n";
+    let mut config = AppConfig::default();
+    config.server.trusted_proxies = vec!["127.0.0.1".parse().unwrap()];
+    config.access_control.deny = vec!["203.0.113.0/24".parse().unwrap()];
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::send_raw(addr, request).await;
+    assert_eq!(response.status, 403);
+    assert_eq!(response.json()["error"], "Forbidden");
+    assert!(
+        ttl_controller
+            .get_connection_info(ip("203.0.113.7"))
+            .is_none()
+    );
+
+    // The proxy's own requests are still served
+    let direct = "GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    assert_eq!(common::send_raw(addr, direct).await.status, 200);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use rusty_ssl::{AppConfig, Router};
+
+#[tokio::test]
+async fn test_health_without_checks_is_healthy() {
+    let router = Router::new(common::ttl_controller(), &AppConfig::default());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 200);
+    let body = response.json();
+    assert_eq!(body["status"], "healthy");
+    assert_eq!(body["service"], "rusty-ssl");
+    assert_eq!(body["checks"], serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn test_registered_checks_drive_status() {
+    let cache_up = Arc::new(AtomicBool::new(true));
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.register_health_check("database", || async { Ok(()) });
+    let flag = cache_up.clone();
+    router.register_health_check("cache", move || {
+        let up = flag.load(Ordering::SeqCst);
+        async move {
+            if up {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }
+    });
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.json()["checks"],
+        serde_json::json!({"database": "ok", "cache": "ok"})
+    );
+
+    cache_up.store(false, Ordering::SeqCst);
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 503);
+    let body = response.json();
+    assert_eq!(body["status"], "unhealthy");
+    assert_eq!(body["checks"]["database"], "ok");
+    assert_eq!(body["checks"]["cache"], "connection refused");
+}
+
+#[tokio::test]
+async fn test_slow_checks_time_out_concurrently() {
+    let mut config = AppConfig::default();
+    config.server.health_check_timeout_ms = 200;
+    let mut router = Router::new(common::ttl_controller(), &config);
+    for name in ["upstream-a", "upstream-b", "upstream-c"] {
+        router.register_health_check(name, || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        });
+    }
+    router.register_health_check("panics", || async { panic!("boom") });
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let started = Instant::now();
+    let response = common::get(addr, "/health").await;
+    // Three checks run side by side, not one after another
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    assert_eq!(response.status, 503);
+    let checks = &response.json()["checks"];
+    assert_eq!(checks["upstream-a"], "timed out after 200ms");
+    assert_eq!(checks["upstream-c"], "timed out after 200ms");
+    assert!(
+        checks["panics"]
+            .as_str()
+            .unwrap()
+            .starts_with("check panicked")
+    );
+}
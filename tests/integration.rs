@@ -1,4 +1,7 @@
+mod common;
+
 use rusty_ssl::AppConfig;
+use rusty_ssl::utils::config::{AcmeConfig, RootPage};
 
 #[test]
 fn test_config_loading() {
@@ -16,6 +19,18 @@ fn test_server_addr() {
     assert_eq!(addr.to_string(), "0.0.0.0:8443");
 }
 
+#[test]
+fn test_listen_addrs_override_host_and_port() {
+    let mut config = AppConfig::default();
+    assert_eq!(
+        config.listen_addrs().unwrap(),
+        vec![config.server_addr().unwrap()]
+    );
+
+    config.server.listen = vec!["0.0.0.0:443".parse().unwrap(), "[::]:443".parse().unwrap()];
+    assert_eq!(config.listen_addrs().unwrap(), config.server.listen);
+}
+
 #[test]
 fn test_durations() {
     let config = AppConfig::default();
@@ -23,3 +38,133 @@ fn test_durations() {
     assert_eq!(config.max_ttl().as_secs(), 3600);
     assert_eq!(config.request_timeout().as_secs(), 30);
 }
+
+/// The settings every `tests/fixtures/config/sample.*` file holds
+fn expected_sample() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.server.host = "127.0.0.1".to_string();
+    config.server.port = 9443;
+    config.server.listen = vec![
+        "127.0.0.1:9443".parse().unwrap(),
+        "[::1]:9443".parse().unwrap(),
+    ];
+    config.ttl.default_ttl_secs = 120;
+    config.logging.level = "debug".to_string();
+    config.logging.format = "json".to_string();
+    config
+        .logging
+        .static_fields
+        .insert("service".to_string(), "rusty-ssl".to_string());
+    config.cors.allowed_origins = vec!["https://example.com".to_string()];
+    config
+}
+
+#[test]
+fn test_config_file_formats_load_alike() {
+    let expected = serde_json::to_value(expected_sample()).unwrap();
+    for name in ["sample.toml", "sample.yaml", "sample.json"] {
+        let loaded = AppConfig::from_file(common::fixture_path("config").join(name)).unwrap();
+        assert_eq!(serde_json::to_value(loaded).unwrap(), expected, "{name}");
+    }
+}
+
+#[test]
+fn test_config_format_follows_extension() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-formats-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("app.toml"), "[server]\nport = 1111\n").unwrap();
+    std::fs::write(dir.join("app.yml"), "server:\n  port: 2222\n").unwrap();
+
+    let yaml = AppConfig::load_from(dir.join("app.yml").to_str().unwrap()).unwrap();
+    let toml = AppConfig::from_file(dir.join("app.toml")).unwrap();
+    let unsuffixed = AppConfig::load_from(dir.join("app").to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(yaml.server.port, 2222);
+    assert_eq!(toml.server.port, 1111);
+    assert!(unsuffixed.is_err());
+}
+
+#[test]
+fn test_unknown_config_extension_is_an_error() {
+    let path = std::env::temp_dir().join(format!("rusty-ssl-{}.ini", std::process::id()));
+    std::fs::write(&path, "[server]\nport = 1111\n").unwrap();
+    let result = AppConfig::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("unsupported extension 'ini'"), "{message}");
+    assert!(message.contains(".yaml"), "{message}");
+}
+
+#[test]
+fn test_validate_reports_every_error() {
+    let mut config = AppConfig {
+        ssl: common::fixture_ssl_config(),
+        ..AppConfig::default()
+    };
+    assert_eq!(config.validate(), Ok(()));
+
+    config.server.port = 0;
+    config.ssl.key_path = "/nonexistent/rusty-ssl/key.pem".into();
+    config.ttl.default_ttl_secs = config.ttl.max_ttl_secs + 1;
+    config.logging.level = "verbose".to_string();
+    config.logging.format = "xml".to_string();
+
+    let fields: Vec<String> = config
+        .validate()
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.field)
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "server.port",
+            "ssl.key_path",
+            "ttl.default_ttl_secs",
+            "logging.level",
+            "logging.format",
+        ]
+    );
+}
+
+#[test]
+fn test_zero_max_connections_fails_validation() {
+    let mut config = AppConfig {
+        ssl: common::fixture_ssl_config(),
+        ..AppConfig::default()
+    };
+    config.server.max_connections = 0;
+    let errors = config.validate().unwrap_err();
+    assert_eq!(errors[0].field, "server.max_connections");
+}
+
+#[test]
+fn test_acme_mode_skips_certificate_path_checks() {
+    let mut config = AppConfig::default();
+    config.ssl.cert_path = "/nonexistent/rusty-ssl/cert.pem".into();
+    config.ssl.key_path = "/nonexistent/rusty-ssl/key.pem".into();
+    assert!(config.validate().is_err());
+
+    config.ssl.acme = Some(AcmeConfig::default());
+    assert_eq!(config.validate(), Ok(()));
+}
+
+#[test]
+fn test_root_page_rejects_unknown_values() {
+    let path = std::env::temp_dir().join(format!("rusty-ssl-root-{}.toml", std::process::id()));
+    let load = |value: &str| {
+        std::fs::write(&path, format!("[server]\nroot_page = \"{value}\"\n")).unwrap();
+        AppConfig::from_file(&path).map(|config| config.server.root_page)
+    };
+    let dashboard = load("dashboard");
+    let legacy = load("endpoints");
+    let typo = load("Dashboard");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(dashboard.unwrap(), RootPage::Dashboard);
+    assert_eq!(legacy.unwrap(), RootPage::Static);
+    let message = typo.unwrap_err().to_string();
+    assert!(message.contains("Dashboard"), "{message}");
+}
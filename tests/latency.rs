@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use rusty_ssl::AppConfig;
+use rusty_ssl::server::LatencyHistogram;
+
+#[test]
+fn test_latency_percentiles_interpolate_within_buckets() {
+    let histogram = LatencyHistogram::new(&[10.0, 100.0, 1000.0]);
+    assert_eq!(histogram.snapshot().percentile(0.5), None);
+
+    // 90 fast requests, 9 moderate ones and one very slow one
+    for _ in 0..90 {
+        histogram.record(Duration::from_millis(5));
+    }
+    for _ in 0..9 {
+        histogram.record(Duration::from_millis(50));
+    }
+    histogram.record(Duration::from_secs(5));
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.counts, vec![90, 9, 0, 1]);
+    assert_eq!(snapshot.count(), 100);
+    assert_eq!(snapshot.sum, Duration::from_millis(90 * 5 + 9 * 50 + 5000));
+
+    let p50 = snapshot.percentile(0.5).unwrap();
+    assert!(p50 > Duration::ZERO && p50 <= Duration::from_millis(10));
+    let p95 = snapshot.percentile(0.95).unwrap();
+    assert!(p95 > Duration::from_millis(10) && p95 <= Duration::from_millis(100));
+    // Beyond the last bound only the bound itself is known
+    assert_eq!(snapshot.percentile(1.0), Some(Duration::from_secs(1)));
+
+    let json = snapshot.to_json();
+    assert_eq!(json["count"], 100);
+    assert!(json["p99_ms"].as_f64().unwrap() <= 100.0);
+}
+
+#[test]
+fn test_latency_on_a_bound_falls_in_that_bucket() {
+    let histogram = LatencyHistogram::new(&[10.0, 100.0]);
+    histogram.record(Duration::from_millis(10));
+    histogram.record(Duration::from_millis(11));
+    assert_eq!(histogram.snapshot().counts, vec![1, 1, 0]);
+}
+
+#[test]
+fn test_unordered_latency_buckets_fail_validation() {
+    let mut config = AppConfig::default();
+    config.metrics.latency_buckets_ms = vec![10.0, 5.0];
+    let rejects_buckets = |config: &AppConfig| {
+        config
+            .validate()
+            .unwrap_err()
+            .iter()
+            .any(|error| error.field == "metrics.latency_buckets_ms")
+    };
+    assert!(rejects_buckets(&config));
+
+    config.metrics.latency_buckets_ms = vec![];
+    assert!(rejects_buckets(&config));
+}
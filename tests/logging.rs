@@ -0,0 +1,125 @@
+mod common;
+
+use std::io::Write;
+
+use rusty_ssl::AppConfig;
+use rusty_ssl::utils::config::{FileLogConfig, LogRotation, TelemetryConfig};
+use rusty_ssl::utils::log_file::SizeRollingFile;
+use rusty_ssl::utils::logger::{configured_subscriber, logging_subscriber};
+
+fn config_with_static_fields(format: &str) -> rusty_ssl::utils::config::LoggingConfig {
+    let mut config = AppConfig::default().logging;
+    config.format = format.to_string();
+    config
+        .static_fields
+        .insert("service".to_string(), "rusty-ssl".to_string());
+    config
+        .static_fields
+        .insert("environment".to_string(), "staging".to_string());
+    config
+}
+
+#[test]
+fn test_static_fields_added_to_json_events() {
+    let buffer = common::LogBuffer::default();
+    let writer = buffer.clone();
+    let subscriber =
+        logging_subscriber(&config_with_static_fields("json"), move || writer.clone()).unwrap();
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(request_id = 7, "handled request");
+    });
+
+    let line: serde_json::Value = serde_json::from_str(buffer.contents().trim()).unwrap();
+    assert_eq!(line["service"], "rusty-ssl");
+    assert_eq!(line["environment"], "staging");
+    assert_eq!(line["fields"]["message"], "handled request");
+    assert_eq!(line["fields"]["request_id"], 7);
+}
+
+#[test]
+fn test_static_fields_added_to_pretty_events() {
+    let buffer = common::LogBuffer::default();
+    let writer = buffer.clone();
+    let subscriber =
+        logging_subscriber(&config_with_static_fields("pretty"), move || writer.clone()).unwrap();
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("handled request");
+    });
+
+    let output = buffer.contents();
+    assert!(output.contains("environment=staging service=rusty-ssl"));
+    assert!(output.contains("handled request"));
+}
+
+fn temp_log_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("rusty-ssl-logs-{}", uuid::Uuid::new_v4()))
+}
+
+#[test]
+fn test_file_logging_writes_to_configured_path() {
+    let dir = temp_log_dir();
+    let path = dir.join("server.log");
+    let mut config = AppConfig::default().logging;
+    config.format = "json".to_string();
+    config.file = Some(FileLogConfig {
+        path: path.clone(),
+        rotation: LogRotation::Size,
+        stdout: false,
+        ..FileLogConfig::default()
+    });
+
+    let (subscriber, guard) = configured_subscriber(&config, &TelemetryConfig::default()).unwrap();
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("written to the log file");
+    });
+    // Flushes the background writer
+    drop(guard);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let line: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+    assert_eq!(line["fields"]["message"], "written to the log file");
+    assert!(!contents.contains('\u{1b}'), "{contents}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_daily_log_files_are_date_suffixed() {
+    let dir = temp_log_dir();
+    let mut config = AppConfig::default().logging;
+    config.file = Some(FileLogConfig {
+        path: dir.join("server.log"),
+        stdout: false,
+        ..FileLogConfig::default()
+    });
+
+    let (subscriber, guard) = configured_subscriber(&config, &TelemetryConfig::default()).unwrap();
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("written to today's file");
+    });
+    drop(guard);
+
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let contents = std::fs::read_to_string(dir.join(format!("server.log.{today}"))).unwrap();
+    assert!(contents.contains("written to today's file"), "{contents}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_size_rotation_keeps_max_files() {
+    let dir = temp_log_dir();
+    let path = dir.join("server.log");
+    let mut file = SizeRollingFile::open(&path, 10, 3).unwrap();
+    for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+    drop(file);
+
+    let read = |suffix: &str| std::fs::read_to_string(format!("{}{suffix}", path.display()));
+    assert_eq!(read("").unwrap(), "fourth\n");
+    assert_eq!(read(".1").unwrap(), "third\n");
+    assert_eq!(read(".2").unwrap(), "second\n");
+    assert!(read(".3").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::HeaderValue;
+use hyper::{Request, Response, StatusCode};
+use rusty_ssl::server::{
+    AccessLogMiddleware, ClientIp, Middleware, MiddlewareFuture, Next, RequestIdMiddleware,
+};
+use rusty_ssl::{AppConfig, Router};
+
+/// Records the order middlewares ran in and tags the response
+struct Trace {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Trace {
+    fn handle<'a>(&'a self, req: Request<Incoming>, next: Next<'a>) -> MiddlewareFuture<'a> {
+        let client = req.extensions().get::<ClientIp>().copied();
+        self.calls.lock().unwrap().push(format!(
+            "{} {:?}",
+            self.name,
+            client.map(|ClientIp(ip)| ip)
+        ));
+        Box::pin(async move {
+            let mut response = next.run(req).await?;
+            response
+                .headers_mut()
+                .append("x-trace", HeaderValue::from_static(self.name));
+            Ok(response)
+        })
+    }
+}
+
+/// Refuses every request without running the route
+struct Deny;
+
+impl Middleware for Deny {
+    fn handle<'a>(&'a self, _req: Request<Incoming>, _next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async {
+            let response = Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Full::new(Bytes::from("denied")).boxed())?;
+            Ok(response)
+        })
+    }
+}
+
+fn localhost() -> IpAddr {
+    "127.0.0.1".parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_middlewares_run_in_order_around_the_route() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    for name in ["outer", "inner"] {
+        router.add_middleware(Arc::new(Trace {
+            name,
+            calls: calls.clone(),
+        }));
+    }
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 200);
+    // Responses unwind innermost first, so the outer tag is appended last
+    assert_eq!(response.header("x-trace"), Some("outer"));
+    assert_eq!(
+        *calls.lock().unwrap(),
+        ["outer Some(127.0.0.1)", "inner Some(127.0.0.1)"]
+    );
+}
+
+#[tokio::test]
+async fn test_short_circuited_requests_are_still_tracked() {
+    let ttl_controller = common::ttl_controller();
+    let mut router = Router::new(ttl_controller.clone(), &AppConfig::default());
+    router.add_middleware(Arc::new(Deny));
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(response.status, 403);
+    assert_eq!(response.body, "denied");
+    assert!(ttl_controller.get_connection_info(localhost()).is_some());
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated_or_echoed() {
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.add_middleware(Arc::new(RequestIdMiddleware));
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let generated = common::get(addr, "/health").await;
+    let id = generated.header("x-request-id").unwrap();
+    assert!(uuid::Uuid::parse_str(id).is_ok(), "{id}");
+
+    let echoed = common::send_raw(
+        addr,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nX-Request-Id: lb-1234\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(echoed.header("x-request-id"), Some("lb-1234"));
+}
+
+#[tokio::test]
+async fn test_router_tags_requests_with_an_id_and_span() {
+    let (_guard, logs) = common::capture_logs();
+    let router = Router::new(common::ttl_controller(), &AppConfig::default());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let generated = common::get(addr, "/health").await;
+    let id = generated.header("x-request-id").unwrap();
+    assert!(uuid::Uuid::parse_str(id).is_ok(), "{id}");
+
+    let echoed = common::send_raw(
+        addr,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nX-Request-Id: trace-42\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(echoed.header("x-request-id"), Some("trace-42"));
+
+    // Handler logs inherit the span fields
+    let logs = logs.contents();
+    let line = logs
+        .lines()
+        .find(|line| line.contains("request_id=trace-42"))
+        .unwrap_or_else(|| panic!("{logs}"));
+    assert!(line.contains("client_ip=127.0.0.1"), "{line}");
+    assert!(line.contains("method=GET"), "{line}");
+    assert!(line.contains("path=/health"), "{line}");
+    assert!(line.contains("Request: GET /health"), "{line}");
+}
+
+#[tokio::test]
+async fn test_access_log_records_status_per_request() {
+    let (_guard, logs) = common::capture_logs();
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.add_middleware(Arc::new(AccessLogMiddleware));
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    common::get(addr, "/health").await;
+    common::get(addr, "/nope").await;
+
+    let logs = logs.contents();
+    assert!(logs.contains("127.0.0.1 \"GET /health\" 200"), "{logs}");
+    assert!(logs.contains("127.0.0.1 \"GET /nope\" 404"), "{logs}");
+}
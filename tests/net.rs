@@ -0,0 +1,57 @@
+use std::io;
+use std::net::SocketAddr;
+
+use rusty_ssl::AppConfig;
+use rusty_ssl::utils::net::{bind_error, bind_tcp};
+use tokio::net::TcpStream;
+
+#[test]
+fn test_bind_permission_error_suggests_fixes() {
+    let addr: SocketAddr = "0.0.0.0:443".parse().unwrap();
+    let err = bind_error(addr, io::Error::from(io::ErrorKind::PermissionDenied));
+
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    let message = err.to_string();
+    assert!(message.contains("0.0.0.0:443"));
+    assert!(message.contains("CAP_NET_BIND_SERVICE"));
+    assert!(message.contains("1024"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bind_unix_refuses_existing_path_unless_forced() {
+    use rusty_ssl::utils::net::bind_unix;
+
+    let path = std::env::temp_dir().join(format!("rusty-ssl-{}.sock", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "").unwrap();
+
+    let err = bind_unix(&path, false).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert!(err.to_string().contains("unix_socket_force"));
+
+    bind_unix(&path, true).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_bind_tcp_applies_socket_options_to_accepted_connections() {
+    let mut config = AppConfig::default();
+    config.server.so_keepalive_secs = 30;
+    let listener = bind_tcp("127.0.0.1:0".parse().unwrap(), &config).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let _client = TcpStream::connect(addr).await.unwrap();
+    let (accepted, _) = listener.accept().await.unwrap();
+    assert!(accepted.nodelay().unwrap());
+    assert!(socket2::SockRef::from(&accepted).keepalive().unwrap());
+
+    config.server.tcp_nodelay = false;
+    config.server.so_keepalive_secs = 0;
+    let listener = bind_tcp("127.0.0.1:0".parse().unwrap(), &config).unwrap();
+    let _client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (accepted, _) = listener.accept().await.unwrap();
+    assert!(!accepted.nodelay().unwrap());
+    assert!(!socket2::SockRef::from(&accepted).keepalive().unwrap());
+}
@@ -0,0 +1,244 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use rusty_ssl::handlers::ProxyHandler;
+use rusty_ssl::utils::config::ProxyConfig;
+use rusty_ssl::{AppConfig, Router};
+use tokio::net::TcpListener;
+
+/// Backend answering every request with a JSON description of it
+async fn spawn_echo_upstream() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let service = service_fn(|req: Request<Incoming>| async move {
+                    let method = req.method().to_string();
+                    let uri = req.uri().to_string();
+                    let headers: BTreeMap<String, String> = req
+                        .headers()
+                        .iter()
+                        .map(|(name, value)| {
+                            (name.to_string(), value.to_str().unwrap().to_string())
+                        })
+                        .collect();
+                    let body = req.into_body().collect().await.unwrap().to_bytes();
+                    let echo = serde_json::json!({
+                        "method": method,
+                        "uri": uri,
+                        "headers": headers,
+                        "body": String::from_utf8_lossy(&body),
+                    });
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .status(201)
+                            .header("Content-Type", "application/json")
+                            .header("X-Upstream", "echo")
+                            .header("Keep-Alive", "timeout=5")
+                            .body(Full::new(Bytes::from(echo.to_string())))
+                            .unwrap(),
+                    )
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+    addr
+}
+
+async fn spawn_proxy(routes: &[(&str, String)]) -> SocketAddr {
+    let mut config = AppConfig::default();
+    config.proxy.routes = routes
+        .iter()
+        .map(|(prefix, url)| (prefix.to_string(), url.clone()))
+        .collect();
+    config.ssl = common::fixture_ssl_config();
+    config.validate().unwrap();
+    common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await
+}
+
+#[tokio::test]
+async fn test_requests_below_prefix_are_forwarded() {
+    let upstream = spawn_echo_upstream().await;
+    let addr = spawn_proxy(&[("/api", format!("http://{upstream}/v1"))]).await;
+
+    let body = r#"{"name":"probe"}"#;
+    let response = common::send_raw(
+        addr,
+        &format!(
+            "POST /api/users?page=2 HTTP/1.1\r\nHost: edge.example.com\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\
+             X-Custom: kept\r\nProxy-Authorization: Basic c2VjcmV0\r\n\
+             Connection: close, X-Drop-Me\r\nX-Drop-Me: 1\r\n\r\n{body}",
+            body.len()
+        ),
+    )
+    .await;
+
+    assert_eq!(response.status, 201);
+    assert_eq!(response.header("X-Upstream"), Some("echo"));
+    assert_eq!(response.header("Keep-Alive"), None);
+
+    let echo = response.json();
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["uri"], "/v1/users?page=2");
+    assert_eq!(echo["body"], body);
+    let headers = &echo["headers"];
+    assert_eq!(headers["host"], upstream.to_string());
+    assert_eq!(headers["x-forwarded-host"], "edge.example.com");
+    assert_eq!(headers["x-forwarded-for"], "127.0.0.1");
+    assert_eq!(headers["x-custom"], "kept");
+    assert!(headers.get("proxy-authorization").is_none());
+    assert!(headers.get("x-drop-me").is_none());
+
+    // The prefix itself maps to the upstream path
+    let response = common::get(addr, "/api").await;
+    assert_eq!(response.json()["uri"], "/v1");
+}
+
+#[tokio::test]
+async fn test_prefix_matches_whole_segments_and_builtins_win() {
+    let api = spawn_echo_upstream().await;
+    let catch_all = spawn_echo_upstream().await;
+    let addr = spawn_proxy(&[
+        ("/", format!("http://{catch_all}/site")),
+        ("/api/", format!("http://{api}")),
+    ])
+    .await;
+
+    // The longest matching prefix wins, on segment boundaries only
+    assert_eq!(
+        common::get(addr, "/api/items").await.json()["uri"],
+        "/items"
+    );
+    assert_eq!(
+        common::get(addr, "/apiary").await.json()["uri"],
+        "/site/apiary"
+    );
+
+    // Routes the router serves itself are never forwarded
+    let health = common::get(addr, "/health").await;
+    assert_eq!(health.status, 200);
+    assert_eq!(health.header("X-Upstream"), None);
+}
+
+#[tokio::test]
+async fn test_spoofed_forwarded_for_is_replaced() {
+    let upstream = spawn_echo_upstream().await;
+    let addr = spawn_proxy(&[("/api", format!("http://{upstream}"))]).await;
+
+    let response = common::send_raw(
+        addr,
+        "GET /api HTTP/1.1\r\nHost: localhost\r\nX-Forwarded-For: 203.0.113.9\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.json()["headers"]["x-forwarded-for"], "127.0.0.1");
+}
+
+#[tokio::test]
+async fn test_trusted_proxy_chain_is_extended() {
+    let upstream = spawn_echo_upstream().await;
+    let mut config = AppConfig::default();
+    config
+        .proxy
+        .routes
+        .insert("/api".to_string(), format!("http://{upstream}"));
+    config.server.trusted_proxies = vec!["127.0.0.1".parse().unwrap()];
+    let addr = common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await;
+
+    let response = common::send_raw(
+        addr,
+        "GET /api HTTP/1.1\r\nHost: localhost\r\nX-Forwarded-For: 203.0.113.9\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(
+        response.json()["headers"]["x-forwarded-for"],
+        "203.0.113.9, 127.0.0.1"
+    );
+}
+
+#[tokio::test]
+async fn test_unreachable_upstream_is_502() {
+    // Bind then drop a listener so nothing answers on the port
+    let closed = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let addr = spawn_proxy(&[("/api", format!("http://{closed}"))]).await;
+
+    let response = common::get(addr, "/api/users").await;
+    assert_eq!(response.status, 502);
+    assert_eq!(response.json()["error"], "Bad Gateway");
+}
+
+#[test]
+fn test_invalid_routes_fail_validation() {
+    for (prefix, upstream) in [
+        ("/api", "https://backend"),
+        ("/api", "backend:8080"),
+        ("/api", "http://backend?x=1"),
+        ("api", "http://backend"),
+        ("/api/:id", "http://backend"),
+    ] {
+        let config = ProxyConfig {
+            routes: BTreeMap::from([(prefix.to_string(), upstream.to_string())]),
+            ..ProxyConfig::default()
+        };
+        assert!(
+            ProxyHandler::new(&config).is_err(),
+            "{prefix} -> {upstream}"
+        );
+
+        let app = AppConfig {
+            proxy: config,
+            ..AppConfig::default()
+        };
+        let errors = app.validate().unwrap_err();
+        assert!(errors.iter().any(|error| error.field == "proxy.routes"));
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_upstream_response_is_bad_gateway() {
+    let upstream = spawn_echo_upstream().await;
+    let mut config = AppConfig::default();
+    config
+        .proxy
+        .routes
+        .insert("/api".to_string(), format!("http://{upstream}"));
+    config.proxy.max_response_bytes = 1024;
+    let addr = common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await;
+
+    // The echo repeats the request body, so its response outgrows the cap
+    for (size, status) in [(100, 201), (4096, 502)] {
+        let request = format!(
+            "POST /api/echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: {size}\r\n\
+             Connection: close\r\n\r\n{}",
+            "x".repeat(size)
+        );
+        assert_eq!(
+            common::send_raw(addr, &request).await.status,
+            status,
+            "{size}"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::net::{Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use rusty_ssl::server::proxy_protocol::{ProxyProtocolError, V2_SIGNATURE, read_header};
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, SslManager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Parses `input`, returning the result and whatever was left unread
+async fn parse(input: &[u8]) -> (Result<Option<SocketAddr>, ProxyProtocolError>, Vec<u8>) {
+    let mut stream = input;
+    let result = read_header(&mut stream).await;
+    (result, stream.to_vec())
+}
+
+fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+    let mut header = V2_SIGNATURE.to_vec();
+    header.push(0x20 | command);
+    header.push(family);
+    header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+    header.extend_from_slice(addresses);
+    header
+}
+
+#[tokio::test]
+async fn test_v1_header_is_stripped() {
+    let (result, rest) = parse(b"PROXY TCP4 203.0.113.7 192.0.2.1 56324 443\r\n\x16\x03\x01").await;
+    assert_eq!(result.unwrap(), Some("203.0.113.7:56324".parse().unwrap()));
+    assert_eq!(rest, b"\x16\x03\x01");
+
+    let (result, _) = parse(b"PROXY TCP6 2001:db8::7 2001:db8::1 4711 443\r\n").await;
+    assert_eq!(result.unwrap(), Some("[2001:db8::7]:4711".parse().unwrap()));
+
+    let (result, rest) = parse(b"PROXY UNKNOWN\r\nGET").await;
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(rest, b"GET");
+}
+
+#[tokio::test]
+async fn test_v2_header_is_stripped() {
+    let mut inet = vec![203, 0, 113, 7, 192, 0, 2, 1];
+    inet.extend_from_slice(&56324u16.to_be_bytes());
+    inet.extend_from_slice(&443u16.to_be_bytes());
+    let mut input = v2_header(0x1, 0x11, &inet);
+    input.extend_from_slice(b"\x16\x03\x01");
+    let (result, rest) = parse(&input).await;
+    assert_eq!(result.unwrap(), Some("203.0.113.7:56324".parse().unwrap()));
+    assert_eq!(rest, b"\x16\x03\x01");
+
+    let mut inet6 = "2001:db8::7".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+    inet6.extend_from_slice(&[0; 16]);
+    inet6.extend_from_slice(&4711u16.to_be_bytes());
+    inet6.extend_from_slice(&443u16.to_be_bytes());
+    // Trailing TLVs are skipped along with the addresses
+    inet6.extend_from_slice(&[0x04, 0x00, 0x01, 0xff]);
+    let (result, rest) = parse(&v2_header(0x1, 0x21, &inet6)).await;
+    assert_eq!(result.unwrap(), Some("[2001:db8::7]:4711".parse().unwrap()));
+    assert!(rest.is_empty());
+
+    // LOCAL connections come from the proxy itself
+    let (result, rest) = parse(&v2_header(0x0, 0x00, &[])).await;
+    assert_eq!(result.unwrap(), None);
+    assert!(rest.is_empty());
+}
+
+#[tokio::test]
+async fn test_malformed_headers_are_rejected() {
+    // No CRLF within the 107 bytes a v1 line may take
+    let overlong = [b"PROXY ".as_slice(), &[b'1'; 200]].concat();
+    let cases: [&[u8]; 6] = [
+        b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        b"PROXY TCP4 203.0.113.7 192.0.2.1 56324\r\n",
+        b"PROXY TCP4 2001:db8::7 192.0.2.1 56324 443\r\n",
+        b"PROXY TCP4 203.0.113.7 192.0.2.1 99999 443\r\n",
+        b"PROXY UDP4 203.0.113.7 192.0.2.1 56324 443\r\n",
+        &overlong,
+    ];
+    for input in cases {
+        assert!(
+            parse(input).await.0.is_err(),
+            "{:?}",
+            String::from_utf8_lossy(input)
+        );
+    }
+
+    // Wrong version, then an INET block too short for its addresses
+    let mut wrong_version = v2_header(0x1, 0x11, &[0; 12]);
+    wrong_version[12] = 0x11;
+    assert!(parse(&wrong_version).await.0.is_err());
+    assert!(parse(&v2_header(0x1, 0x11, &[0; 4])).await.0.is_err());
+
+    // Truncated before the advertised length
+    let truncated = v2_header(0x1, 0x11, &[0; 12]);
+    assert!(matches!(
+        parse(&truncated[..20]).await.0,
+        Err(ProxyProtocolError::Io(_))
+    ));
+}
+
+async fn spawn_proxied_server() -> SocketAddr {
+    let mut config = AppConfig::default();
+    config.server.proxy_protocol = true;
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    common::spawn_tls_handler(
+        ssl_manager.acceptor(),
+        ConnectionHandler::new(router, &config),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_source_from_header_is_the_client_ip() {
+    let addr = spawn_proxied_server().await;
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"PROXY TCP4 203.0.113.7 192.0.2.1 56324 443\r\n")
+        .await
+        .unwrap();
+    let mut tls = common::tls_handshake(stream, common::tls_client_config(), "localhost")
+        .await
+        .unwrap();
+    let whoami = common::exchange(
+        &mut tls,
+        "GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await
+    .json();
+    assert_eq!(whoami["ip"], "203.0.113.7");
+}
+
+#[tokio::test]
+async fn test_connection_without_header_is_closed() {
+    let addr = spawn_proxied_server().await;
+
+    // A TLS ClientHello where the header should be
+    assert!(
+        common::tls_connect(addr, common::tls_client_config())
+            .await
+            .is_err()
+    );
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"PROXY TCP4 not-an-ip 192.0.2.1 56324 443\r\n")
+        .await
+        .unwrap();
+    let mut buf = Vec::new();
+    let read = stream.read_to_end(&mut buf).await.unwrap_or(0);
+    assert_eq!(read, 0);
+}
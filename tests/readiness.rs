@@ -0,0 +1,150 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusty_ssl::server::{ReadyFile, serve_listener, shutdown_listeners};
+use rusty_ssl::utils::config::SslConfig;
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, SslManager};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+
+/// Serves a router whose certificate comes from `ssl`, with the TTL
+/// cleanup task running
+async fn spawn_ready_router(ssl: &SslConfig) -> SocketAddr {
+    let ttl_controller = common::ttl_controller();
+    let cleanup = ttl_controller.clone();
+    tokio::spawn(async move { cleanup.start_cleanup_task().await });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !ttl_controller.cleanup_running() {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let ssl_manager = SslManager::new(ssl).unwrap();
+    let mut router = Router::new(ttl_controller, &AppConfig::default());
+    router.set_certificate_info(ssl_manager.certificate_info_handle());
+    common::spawn_router(Arc::new(router)).await
+}
+
+#[test]
+fn test_ready_file_written_on_ready_and_removed_on_drain() {
+    let path = std::env::temp_dir().join(format!("rusty-ssl-ready-{}", uuid::Uuid::new_v4()));
+    let ready_file = ReadyFile::new(Some(path.clone()));
+
+    ready_file.mark_ready();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains(&format!("pid={}", std::process::id())));
+
+    ready_file.mark_not_ready();
+    assert!(!path.exists());
+
+    // Draining twice must not fail on the already-removed file
+    ready_file.mark_not_ready();
+}
+
+#[tokio::test]
+async fn test_shutdown_removes_ready_file_before_draining_finishes() {
+    let config = AppConfig::default();
+    let (router, ssl_manager) = common::stalling_router(common::ttl_controller(), config.clone());
+    let handler = Arc::new(ConnectionHandler::new(Arc::new(router), &config));
+    let shutdown = handler.shutdown_token();
+    let acceptor = SslManager::new(&common::fixture_ssl_config())
+        .unwrap()
+        .acceptor();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut server_tasks = JoinSet::new();
+    server_tasks.spawn(serve_listener(handler, listener, acceptor));
+
+    let path = std::env::temp_dir().join(format!("rusty-ssl-ready-{}", uuid::Uuid::new_v4()));
+    let ready_file = ReadyFile::new(Some(path.clone()));
+    ready_file.mark_ready();
+    assert!(path.exists());
+
+    // Holding the SSL manager keeps a request in flight through the drain
+    let guard = ssl_manager.lock().await;
+    let mut stream = common::tls_connect(addr, common::tls_client_config())
+        .await
+        .unwrap();
+    stream
+        .write_all(common::RELOAD_CERT_REQUEST.as_bytes())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let draining = tokio::spawn(async move {
+        shutdown_listeners(
+            &shutdown,
+            &mut server_tasks,
+            &ready_file,
+            Duration::from_secs(10),
+        )
+        .await
+    });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("ready file kept while draining");
+    assert!(!draining.is_finished());
+
+    drop(guard);
+    let response = common::exchange(&mut stream, "").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(draining.await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_ready_with_valid_certificate_and_cleanup_running() {
+    let addr = spawn_ready_router(&common::fixture_ssl_config()).await;
+
+    let response = common::get(addr, "/health/ready").await;
+    assert_eq!(response.status, 200);
+    let body = response.json();
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["failed"], serde_json::json!([]));
+    assert_eq!(body["checks"]["ssl_certificates"], "ready");
+    assert_eq!(body["checks"]["ttl_controller"], "ready");
+}
+
+#[tokio::test]
+async fn test_expired_certificate_is_not_ready() {
+    let addr = spawn_ready_router(&common::expired_ssl_config()).await;
+
+    let response = common::get(addr, "/health/ready").await;
+    assert_eq!(response.status, 503);
+    let body = response.json();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["failed"], serde_json::json!(["ssl_certificates"]));
+    assert_eq!(
+        body["checks"]["ssl_certificates"],
+        "certificate expired at 2020-12-31T23:59:59+00:00"
+    );
+    assert_eq!(body["checks"]["ttl_controller"], "ready");
+
+    // Liveness is unaffected
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+}
+
+#[tokio::test]
+async fn test_not_ready_without_certificate_or_cleanup_task() {
+    let router = Router::new(common::ttl_controller(), &AppConfig::default());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/health/ready").await;
+    assert_eq!(response.status, 503);
+    let body = response.json();
+    assert_eq!(
+        body["failed"],
+        serde_json::json!(["ssl_certificates", "ttl_controller"])
+    );
+    assert_eq!(body["checks"]["ssl_certificates"], "no certificate loaded");
+    assert_eq!(body["checks"]["ttl_controller"], "cleanup task not running");
+}
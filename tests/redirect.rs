@@ -0,0 +1,69 @@
+mod common;
+
+use std::time::Duration;
+
+use hyper::Request;
+use rusty_ssl::server::redirect::redirect_response;
+use rusty_ssl::server::serve_https_redirect;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+fn location(req: &Request<()>, https_port: u16) -> Option<String> {
+    redirect_response(req, https_port)
+        .headers()
+        .get("location")
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+#[test]
+fn test_redirect_keeps_host_path_and_query() {
+    let req = Request::get("/reports?page=2")
+        .header("host", "example.com:80")
+        .body(())
+        .unwrap();
+    assert_eq!(redirect_response(&req, 443).status(), 301);
+    assert_eq!(
+        location(&req, 443).as_deref(),
+        Some("https://example.com/reports?page=2")
+    );
+    assert_eq!(
+        location(&req, 8443).as_deref(),
+        Some("https://example.com:8443/reports?page=2")
+    );
+
+    let req = Request::get("/").header("host", "[::1]").body(()).unwrap();
+    assert_eq!(location(&req, 443).as_deref(), Some("https://[::1]/"));
+}
+
+#[test]
+fn test_redirect_without_host_is_bad_request() {
+    let req = Request::get("/").body(()).unwrap();
+    assert_eq!(redirect_response(&req, 443).status(), 400);
+
+    let req = Request::get("/")
+        .header("host", "bad host/")
+        .body(())
+        .unwrap();
+    assert_eq!(redirect_response(&req, 443).status(), 400);
+}
+
+#[tokio::test]
+async fn test_redirect_listener_answers_until_shutdown() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+    let server = tokio::spawn(serve_https_redirect(listener, 8443, shutdown.clone()));
+
+    let response = common::send_raw(addr, "GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+    assert_eq!(response.status, 301);
+    assert_eq!(
+        response.header("location"),
+        Some("https://localhost:8443/health")
+    );
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("redirect listener kept accepting after shutdown")
+        .unwrap();
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rusty_ssl::server::ConfigReloader;
+use rusty_ssl::{AppConfig, ConnectionHandler, Router};
+use tokio::net::TcpStream;
+
+fn running_config() -> AppConfig {
+    AppConfig {
+        ssl: common::fixture_ssl_config(),
+        ..AppConfig::default()
+    }
+}
+
+fn ip(last: u8) -> IpAddr {
+    IpAddr::from([10, 0, 0, last])
+}
+
+#[test]
+fn test_reload_applies_new_ttl_to_new_connections() {
+    let ttl_controller = common::ttl_controller();
+    let reloader = ConfigReloader::new(running_config(), ttl_controller.clone());
+    ttl_controller.register_connection(ip(1));
+
+    let mut new = running_config();
+    new.ttl.default_ttl_secs = 60;
+    new.ttl.max_ttl_secs = 600;
+    new.ttl.cleanup_interval_secs = 5;
+    let outcome = reloader.apply(new).unwrap();
+    assert_eq!(
+        outcome.applied,
+        [
+            "ttl.cleanup_interval_secs",
+            "ttl.default_ttl_secs",
+            "ttl.max_ttl_secs"
+        ]
+    );
+    assert!(outcome.requires_restart.is_empty());
+
+    ttl_controller.register_connection(ip(2));
+    let ttl_of = |ip| ttl_controller.get_connection_info(ip).unwrap().ttl;
+    assert_eq!(ttl_of(ip(2)), Duration::from_secs(60));
+    // Connections already tracked keep theirs
+    assert_eq!(ttl_of(ip(1)), Duration::from_secs(300));
+    assert_eq!(
+        ttl_controller.limits().cleanup_interval,
+        Duration::from_secs(5)
+    );
+    assert_eq!(reloader.config().ttl.default_ttl_secs, 60);
+}
+
+#[test]
+fn test_reload_leaves_restart_only_settings_alone() {
+    let reloader = ConfigReloader::new(running_config(), common::ttl_controller());
+
+    let mut new = running_config();
+    new.server.port = 9443;
+    new.ttl.default_ttl_secs = 120;
+    let outcome = reloader.apply(new).unwrap();
+    assert_eq!(outcome.applied, ["ttl.default_ttl_secs"]);
+    assert_eq!(outcome.requires_restart, ["server.port"]);
+    assert_eq!(reloader.config().server.port, 8443);
+}
+
+#[test]
+fn test_invalid_reload_keeps_running_config() {
+    let ttl_controller = common::ttl_controller();
+    let reloader = ConfigReloader::new(running_config(), ttl_controller.clone());
+
+    let mut new = running_config();
+    new.ttl.default_ttl_secs = 7200; // above max_ttl_secs
+    let errors = reloader.apply(new).unwrap_err();
+    assert_eq!(errors[0].field, "ttl.default_ttl_secs");
+    assert_eq!(
+        ttl_controller.limits().default_ttl,
+        Duration::from_secs(300)
+    );
+}
+
+#[test]
+fn test_reload_changes_rate_limits_in_place() {
+    let ttl_controller = common::ttl_controller();
+    let rate_limiter = ttl_controller.attach_rate_limiter(1.0, 1);
+    let reloader = ConfigReloader::new(running_config(), ttl_controller);
+    assert_eq!(rate_limiter.check(ip(1)).limit, 1);
+
+    let mut new = running_config();
+    new.rate_limit.requests_per_sec = 10.0;
+    new.rate_limit.burst = 5;
+    let outcome = reloader.apply(new).unwrap();
+    assert_eq!(
+        outcome.applied,
+        ["rate_limit.burst", "rate_limit.requests_per_sec"]
+    );
+    assert_eq!(rate_limiter.check(ip(3)).limit, 5);
+}
+
+#[tokio::test]
+async fn test_reload_lowers_connection_cap_in_place() {
+    let mut config = running_config();
+    config.server.max_connections = 2;
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let handler = Arc::new(ConnectionHandler::new(router, &config));
+    let addr = common::spawn_shared_handler(handler.clone()).await;
+
+    // Without the handler the cap can't change live
+    let mut new = config.clone();
+    new.server.max_connections = 1;
+    let outcome = ConfigReloader::new(config.clone(), ttl_controller.clone())
+        .apply(new.clone())
+        .unwrap();
+    assert_eq!(outcome.requires_restart, ["server.max_connections"]);
+
+    let mut reloader = ConfigReloader::new(config, ttl_controller.clone());
+    reloader.set_connection_handler(handler.clone());
+    let _held = TcpStream::connect(addr).await.unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while handler.active_connections() < 1 {
+        assert!(Instant::now() < deadline, "held connection never admitted");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+
+    let outcome = reloader.apply(new).unwrap();
+    assert_eq!(outcome.applied, ["server.max_connections"]);
+    assert_eq!(reloader.config().server.max_connections, 1);
+    assert_eq!(ttl_controller.max_connections(), 1);
+    assert_eq!(common::get(addr, "/health/live").await.status, 0);
+}
+
+#[test]
+fn test_reload_is_published_to_the_router() {
+    let config = running_config();
+    let router = Router::new(common::ttl_controller(), &config);
+    let mut reloader = ConfigReloader::new(config.clone(), common::ttl_controller());
+    reloader.set_shared_config(router.config_handle());
+
+    let mut new = config;
+    new.ttl.default_ttl_secs = 60;
+    reloader.apply(new).unwrap();
+    assert_eq!(router.config_handle().load().ttl.default_ttl_secs, 60);
+    assert!(Arc::ptr_eq(
+        &reloader.config(),
+        &router.config_handle().load_full()
+    ));
+}
@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use hyper::Method;
+use rusty_ssl::server::{RouteTable, RouteTableError};
+
+#[test]
+fn test_many_routes_dispatch_correctly_and_fast() {
+    const ROUTES: usize = 10_000;
+    let mut table = RouteTable::new(ROUTES + 2);
+    for i in 0..ROUTES {
+        table
+            .insert(Method::GET, &format!("/api/v1/resource{i}"), i)
+            .unwrap();
+    }
+    table
+        .insert(Method::GET, "/api/v1/items/:id", ROUTES)
+        .unwrap();
+    table
+        .insert(Method::POST, "/api/v1/resource0", ROUTES + 1)
+        .unwrap();
+
+    let paths: Vec<String> = (0..ROUTES)
+        .map(|i| format!("/api/v1/resource{i}"))
+        .collect();
+    for (i, path) in paths.iter().enumerate() {
+        assert_eq!(table.lookup(&Method::GET, path).map(|(r, _)| *r), Some(i));
+    }
+    assert_eq!(
+        table
+            .lookup(&Method::POST, "/api/v1/resource0")
+            .map(|(r, _)| *r),
+        Some(ROUTES + 1)
+    );
+    let (route, params) = table.lookup(&Method::GET, "/api/v1/items/42").unwrap();
+    assert_eq!(*route, ROUTES);
+    assert_eq!(params.get("id"), Some("42"));
+    assert!(table.lookup(&Method::GET, "/api/v1/missing").is_none());
+    assert!(table.lookup(&Method::DELETE, "/api/v1/resource7").is_none());
+
+    // A linear scan over 10k routes would take far longer than this even
+    // in debug builds
+    let started = Instant::now();
+    for _ in 0..10 {
+        for path in &paths {
+            std::hint::black_box(table.lookup(&Method::GET, path));
+        }
+    }
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "100k lookups took {elapsed:?}"
+    );
+}
+
+#[test]
+fn test_route_table_enforces_max_routes_and_rejects_duplicates() {
+    let mut table = RouteTable::new(2);
+    table.insert(Method::GET, "/a", 1).unwrap();
+    assert_eq!(
+        table.insert(Method::GET, "/a", 2),
+        Err(RouteTableError::Duplicate {
+            method: Method::GET,
+            path: "/a".to_string()
+        })
+    );
+    table.insert(Method::GET, "/b/:id", 2).unwrap();
+    assert_eq!(
+        table.insert(Method::GET, "/c", 3),
+        Err(RouteTableError::Full(2))
+    );
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn test_allowed_methods_cover_exact_and_pattern_routes() {
+    let mut table = RouteTable::new(8);
+    table.insert(Method::POST, "/items", 0).unwrap();
+    table.insert(Method::GET, "/items", 1).unwrap();
+    table.insert(Method::GET, "/items/:id", 2).unwrap();
+    table.insert(Method::DELETE, "/:kind/:id", 3).unwrap();
+
+    assert_eq!(table.allowed_methods("/items"), [Method::GET, Method::POST]);
+    assert_eq!(
+        table.allowed_methods("/items/7"),
+        [Method::DELETE, Method::GET]
+    );
+    assert!(table.allowed_methods("/nope").is_empty());
+}
@@ -0,0 +1,947 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Response};
+use rusty_ssl::server::{PathParams, RouteTableError, StartTime, StartupTimings};
+use rusty_ssl::utils::config::RootPage;
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, SslManager};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_whoami_cache_control_matches_remaining_ttl() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/whoami").await;
+    assert_eq!(response.status, 200);
+
+    let body = response.json();
+    let remaining = body["time_until_expiry"].as_u64().unwrap();
+    assert!(remaining > 0 && remaining <= 300);
+    assert_eq!(
+        response.header("Cache-Control"),
+        Some(format!("private, max-age={}", remaining).as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_lowercase_method_is_normalized() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::send_raw(
+        addr,
+        "get /health/live HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["status"], "alive");
+}
+
+#[tokio::test]
+async fn test_nonstandard_method_rejected_with_501() {
+    let mut config = AppConfig::default();
+    config.server.reject_nonstandard_methods = true;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::send_raw(
+        addr,
+        "BREW /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 501);
+    assert_eq!(response.json()["error"], "Not Implemented");
+}
+
+#[tokio::test]
+async fn test_metrics_detail_requires_token() {
+    let mut config = AppConfig::default();
+    config.auth.tokens = vec!["scrape-secret".to_string()];
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let unauthed = common::get(addr, "/metrics").await.json();
+    assert!(unauthed["ttl_stats"]["active_connections"].is_u64());
+    assert!(unauthed.get("active_connections").is_none());
+
+    let authed = common::send_raw(
+        addr,
+        "GET /metrics HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer scrape-secret\r\nConnection: close\r\n\r\n",
+    )
+    .await
+    .json();
+    let detail = authed["active_connections"].as_array().unwrap();
+    assert_eq!(detail[0]["ip"], "127.0.0.1");
+}
+
+#[tokio::test]
+async fn test_metrics_reports_protocol_split() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    assert_eq!(metrics["ttl_stats"]["http1_connections"], 1);
+    assert_eq!(metrics["ttl_stats"]["http2_connections"], 0);
+    assert_eq!(metrics["active_connections"][0]["protocol"], "HTTP/1.1");
+}
+
+#[tokio::test]
+async fn test_fail_closed_on_expired_certificate() {
+    let mut config = AppConfig {
+        ssl: common::expired_ssl_config(),
+        ..Default::default()
+    };
+    config.ssl.fail_closed_on_expired_cert = true;
+
+    let ssl_manager = SslManager::new(&config.ssl).unwrap();
+    assert!(ssl_manager.get_certificate_info().unwrap().is_expired);
+
+    let mut router = Router::new(common::ttl_controller(), &config);
+    router.set_certificate_info(ssl_manager.certificate_info_handle());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/metrics").await;
+    assert_eq!(response.status, 503);
+    assert_eq!(response.json()["error"], "Certificate Expired");
+
+    // Probes stay reachable so the failure is visible to orchestrators
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    // ...but only the probe routes themselves, not anything sharing the prefix
+    assert_eq!(common::get(addr, "/healthz-anything").await.status, 503);
+}
+
+#[tokio::test]
+async fn test_fail_closed_once_not_after_passes_between_monitor_ticks() {
+    let mut config = AppConfig {
+        ssl: common::fixture_ssl_config(),
+        ..Default::default()
+    };
+    config.ssl.fail_closed_on_expired_cert = true;
+
+    let ssl_manager = SslManager::new(&config.ssl).unwrap();
+    let certificate_info = ssl_manager.certificate_info_handle();
+    let mut router = Router::new(common::ttl_controller(), &config);
+    router.set_certificate_info(certificate_info.clone());
+    let addr = common::spawn_router(Arc::new(router)).await;
+    assert_eq!(common::get(addr, "/metrics").await.status, 200);
+
+    // The monitor hasn't run yet, so the cached flag still says valid
+    if let Some(cert) = certificate_info.write().unwrap().as_mut() {
+        cert.not_after = SystemTime::now() - Duration::from_secs(1);
+        assert!(!cert.is_expired);
+    }
+    assert_eq!(common::get(addr, "/metrics").await.status, 503);
+}
+
+#[tokio::test]
+async fn test_json_content_type_includes_configured_charset() {
+    let mut config = AppConfig::default();
+    config.server.json_charset = Some("utf-8".to_string());
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/health").await;
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("application/json; charset=utf-8")
+    );
+}
+
+#[tokio::test]
+async fn test_request_logging_can_be_disabled() {
+    for log_requests in [true, false] {
+        let (_guard, logs) = common::capture_logs();
+        let mut config = AppConfig::default();
+        config.logging.log_requests = log_requests;
+        let router = Arc::new(Router::new(common::ttl_controller(), &config));
+        let addr = common::spawn_router(router).await;
+
+        assert_eq!(common::get(addr, "/health/live").await.status, 200);
+        assert_eq!(
+            logs.contents().contains("Request: GET /health/live"),
+            log_requests
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_authenticated_connection_gets_boosted_ttl() {
+    let mut config = AppConfig::default();
+    config.auth.tokens = vec!["scrape-secret".to_string()];
+    config.auth.ttl_boost_factor = 2.0;
+
+    for (token, expected_ttl) in [(Some("scrape-secret"), 600), (None, 300)] {
+        let ttl_controller = common::ttl_controller();
+        let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+        let addr = common::spawn_router(router).await;
+
+        let auth_header = token
+            .map(|token| format!("Authorization: Bearer {token}\r\n"))
+            .unwrap_or_default();
+        common::send_raw(
+            addr,
+            &format!(
+                "GET /metrics HTTP/1.1\r\nHost: localhost\r\n{auth_header}Connection: close\r\n\r\n"
+            ),
+        )
+        .await;
+
+        let ttl = ttl_controller
+            .get_connection_info("127.0.0.1".parse().unwrap())
+            .unwrap()
+            .ttl;
+        assert_eq!(ttl.as_secs(), expected_ttl);
+    }
+}
+
+#[tokio::test]
+async fn test_connections_csv_export() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/connections.csv").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("text/csv; charset=utf-8")
+    );
+    assert_eq!(
+        response.header("Content-Disposition"),
+        Some("attachment; filename=\"connections.csv\"")
+    );
+
+    let lines: Vec<&str> = response.body.lines().collect();
+    assert_eq!(
+        lines[0],
+        "ip,id,established_secs,last_activity_secs,ttl_secs,request_count,expired"
+    );
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with("127.0.0.1,"));
+    assert!(lines[1].ends_with(",300,1,false"));
+}
+
+#[tokio::test]
+async fn test_dashboard_root_page_fetches_metrics() {
+    let mut config = AppConfig::default();
+    config.server.root_page = RootPage::Dashboard;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    assert!(response.body.contains("fetch('/metrics'"));
+    assert!(response.body.contains("days_until_expiry"));
+    assert!(!response.body.contains("https://"));
+}
+
+#[tokio::test]
+async fn test_connection_detail_extracts_ip_param() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/connections/127.0.0.1").await;
+    assert_eq!(response.status, 200);
+    let body = response.json();
+    assert_eq!(body["ip"], "127.0.0.1");
+    assert_eq!(body["ttl_seconds"], 300);
+
+    let response = common::get(addr, "/connections/::1").await;
+    assert_eq!(response.status, 404);
+}
+
+#[tokio::test]
+async fn test_connection_detail_rejects_malformed_ip() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/connections/not-an-ip").await;
+    assert_eq!(response.status, 400);
+    let body = response.json();
+    assert_eq!(body["error"], "Bad Request");
+    assert!(body["message"].as_str().unwrap().contains("'ip'"));
+
+    let response = common::get(addr, "/connections/999.1.1.1").await;
+    assert_eq!(response.status, 400);
+}
+
+#[tokio::test]
+async fn test_metrics_stream_emits_ndjson_frames() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut config = AppConfig::default();
+    config.server.metrics_stream_interval_ms = 50;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /metrics/stream HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+
+    // The response never ends on its own, so read until two frames arrived
+    let mut raw = String::new();
+    let frames = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "stream closed early");
+            raw.push_str(&String::from_utf8_lossy(&buf[..n]));
+
+            let (_, body) = raw.split_once("\r\n\r\n").unwrap_or(("", ""));
+            let frames: Vec<serde_json::Value> = body
+                .split(['\r', '\n'])
+                .filter(|line| line.starts_with('{'))
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            if frames.len() >= 2 {
+                return frames;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    let response = common::parse_response(&raw);
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("application/x-ndjson")
+    );
+    for frame in &frames {
+        assert_eq!(frame["ttl_stats"]["active_connections"], 1);
+        assert!(frame["timestamp"].is_u64());
+    }
+}
+
+#[tokio::test]
+async fn test_metrics_deltas_track_each_scraper() {
+    let mut config = AppConfig::default();
+    config.auth.tokens = vec!["scraper-a".to_string(), "scraper-b".to_string()];
+    config.metrics.report_deltas = true;
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let scrape = |token: &'static str| async move {
+        common::send_raw(
+            addr,
+            &format!(
+                "GET /metrics HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {token}\r\nConnection: close\r\n\r\n"
+            ),
+        )
+        .await
+        .json()
+    };
+
+    let first = scrape("scraper-a").await;
+    assert_eq!(first["deltas"]["total_connections"], 1);
+
+    ttl_controller.register_connection("192.0.2.1".parse().unwrap());
+    ttl_controller.register_connection("192.0.2.2".parse().unwrap());
+
+    let second = scrape("scraper-a").await;
+    let cumulative = |m: &serde_json::Value| m["ttl_stats"]["total_connections"].as_u64().unwrap();
+    assert_eq!(
+        second["deltas"]["total_connections"].as_u64().unwrap(),
+        cumulative(&second) - cumulative(&first)
+    );
+    assert_eq!(second["deltas"]["total_connections"], 2);
+    assert_eq!(second["deltas"]["expired_connections"], 0);
+
+    // Another token has its own baseline
+    let other = scrape("scraper-b").await;
+    assert_eq!(other["deltas"]["total_connections"], cumulative(&other));
+}
+
+#[tokio::test]
+async fn test_absolute_form_request_is_routed_by_path() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::send_raw(
+        addr,
+        "GET https://proxy.example:8443/health/live?probe=1 HTTP/1.1\r\nHost: proxy.example:8443\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["status"], "alive");
+
+    // An absolute-form target without a path addresses the root
+    let response = common::send_raw(
+        addr,
+        "GET http://proxy.example HTTP/1.1\r\nHost: proxy.example\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+}
+
+#[tokio::test]
+async fn test_metrics_cache_refreshes_after_max_age() {
+    let mut config = AppConfig::default();
+    config.metrics.cache_max_age_ms = 200;
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let total = |m: serde_json::Value| m["ttl_stats"]["total_connections"].as_u64().unwrap();
+    assert_eq!(total(common::get(addr, "/metrics").await.json()), 1);
+
+    ttl_controller.register_connection("192.0.2.1".parse().unwrap());
+
+    // Within the max age the cached document is served
+    assert_eq!(total(common::get(addr, "/metrics").await.json()), 1);
+
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    assert_eq!(total(common::get(addr, "/metrics").await.json()), 2);
+}
+
+#[tokio::test]
+async fn test_metrics_report_distinct_ips_behind_proxy() {
+    let ttl_controller = common::ttl_controller();
+    let router = Arc::new(Router::new(ttl_controller.clone(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    // Three forwarded clients arriving through one load balancer
+    let proxy = "10.0.0.1".parse().unwrap();
+    for client in ["198.51.100.1", "198.51.100.2", "198.51.100.3"] {
+        ttl_controller.register_forwarded_connection(client.parse().unwrap(), proxy);
+    }
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    // The scraper itself connects directly from 127.0.0.1
+    assert_eq!(metrics["ttl_stats"]["active_connections"], 4);
+    assert_eq!(metrics["ttl_stats"]["distinct_ips"], 2);
+
+    let detail = common::get(addr, "/connections/198.51.100.2").await.json();
+    assert_eq!(detail["peer_ip"], "10.0.0.1");
+}
+
+#[tokio::test]
+async fn test_route_timeouts_are_enforced_per_route() {
+    // Each server sets a different deadline on the route that gets stuck
+    let spawn = |reload_ms: u64, metrics_ms: u64| {
+        let mut config = AppConfig::default();
+        config
+            .server
+            .route_timeouts_ms
+            .insert("/admin/reload-cert".to_string(), reload_ms);
+        config
+            .server
+            .route_timeouts_ms
+            .insert("/metrics".to_string(), metrics_ms);
+        let (router, ssl_manager) = common::stalling_router(common::ttl_controller(), config);
+        (common::spawn_router(Arc::new(router)), ssl_manager)
+    };
+    let (short, short_ssl_manager) = spawn(100, 10_000);
+    let (long, long_ssl_manager) = spawn(10_000, 100);
+    let (short, long) = (short.await, long.await);
+
+    // Holding the SSL managers stalls certificate reloads
+    let short_guard = short_ssl_manager.lock().await;
+    let long_guard = long_ssl_manager.lock().await;
+
+    let started = std::time::Instant::now();
+    let response = common::send_raw(short, common::RELOAD_CERT_REQUEST).await;
+    assert_eq!(response.status, 504);
+    assert_eq!(response.json()["status"], 504);
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+    // The longer deadline outlasts the stall
+    let reload = tokio::spawn(common::send_raw(long, common::RELOAD_CERT_REQUEST));
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    drop(long_guard);
+    assert_eq!(reload.await.unwrap().status, 200);
+    drop(short_guard);
+}
+
+#[test]
+fn test_route_timeout_must_be_shorter_than_request_timeout() {
+    let mut config = AppConfig::default();
+    config.server.request_timeout_secs = 30;
+    config
+        .server
+        .route_timeouts_ms
+        .insert("/metrics".to_string(), 29_999);
+    assert!(
+        config
+            .validate()
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .all(|e| e.field != "server.route_timeouts_ms")
+    );
+
+    config
+        .server
+        .route_timeouts_ms
+        .insert("/metrics".to_string(), 30_000);
+    let errors = config.validate().unwrap_err();
+    assert!(
+        errors
+            .iter()
+            .any(|e| e.field == "server.route_timeouts_ms" && e.message.contains("'/metrics'"))
+    );
+}
+
+#[tokio::test]
+async fn test_admin_reload_cert_returns_new_certificate_info() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-reload-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let cert_path = dir.join("cert.pem");
+    std::fs::copy(common::fixture_path("cert.pem"), &cert_path).unwrap();
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cert_path = cert_path.clone();
+    let ssl_manager = Arc::new(tokio::sync::Mutex::new(
+        SslManager::new(&ssl_config).unwrap(),
+    ));
+
+    let mut config = AppConfig::default();
+    config.auth.tokens = vec!["secret".to_string()];
+    let mut router = Router::new(common::ttl_controller(), &config);
+    router.set_ssl_manager(ssl_manager.clone());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let reload = |token: &str| {
+        format!(
+            "POST /admin/reload-cert HTTP/1.1\r\nHost: localhost\r\n\
+             Authorization: Bearer {token}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+    };
+    assert_eq!(common::send_raw(addr, &reload("wrong")).await.status, 401);
+
+    // Rotate the file on disk, then ask for a reload
+    std::fs::copy(common::fixture_path("expired-cert.pem"), &cert_path).unwrap();
+    let response = common::send_raw(addr, &reload("secret")).await;
+    assert_eq!(response.status, 200);
+    let body = response.json();
+    assert_eq!(body["status"], "reloaded");
+    assert_eq!(body["certificate"]["not_after"], 1_609_459_199);
+    assert_eq!(body["certificate"]["is_expired"], true);
+    assert!(
+        ssl_manager
+            .lock()
+            .await
+            .get_certificate_info()
+            .unwrap()
+            .is_expired
+    );
+
+    // A broken file is reported and leaves the manager usable
+    std::fs::write(&cert_path, "not a certificate").unwrap();
+    let response = common::send_raw(addr, &reload("secret")).await;
+    assert_eq!(response.status, 500);
+    assert_eq!(response.json()["error"], "Certificate Reload Failed");
+}
+
+#[tokio::test]
+async fn test_metrics_reports_scrape_duration() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    let duration = metrics["scrape_duration_seconds"].as_f64().unwrap();
+    assert!(duration >= 0.0);
+}
+
+#[tokio::test]
+async fn test_metrics_report_uptime_and_start_time() {
+    let before = chrono::Utc::now().timestamp();
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    assert_eq!(metrics["uptime_seconds"], 0);
+    let started_at = metrics["started_at"].as_str().unwrap();
+    let started_at = chrono::DateTime::parse_from_rfc3339(started_at).unwrap();
+    assert!(started_at.timestamp() >= before);
+    assert!(started_at.timestamp() <= chrono::Utc::now().timestamp());
+
+    let prometheus = common::get(addr, "/metrics/prometheus").await.body;
+    assert!(prometheus.contains("# TYPE process_start_time_seconds gauge\n"));
+    let start: f64 = prometheus
+        .lines()
+        .find_map(|line| line.strip_prefix("process_start_time_seconds "))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(start as i64, started_at.timestamp());
+
+    // /health shares the same start time
+    let health = common::get(addr, "/health").await.json();
+    assert_eq!(health["uptime_seconds"], 0);
+}
+
+#[test]
+fn test_uptime_is_monotonic() {
+    let start_time = StartTime::now();
+    let first = start_time.uptime();
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(start_time.uptime() >= first + Duration::from_millis(5));
+}
+
+#[tokio::test]
+async fn test_metrics_report_startup_timings() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router.clone()).await;
+    let before = common::get(addr, "/metrics").await.json();
+    assert!(before["server_stats"]["startup"].is_null());
+
+    // Time the same phases main does
+    let started = Instant::now();
+    let _config = AppConfig::default();
+    let config_load = started.elapsed();
+    let started = Instant::now();
+    SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let cert_load = started.elapsed();
+    let started = Instant::now();
+    let _listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let listener_bind = started.elapsed();
+    router.stats().record_startup(StartupTimings {
+        config_load,
+        cert_load,
+        listener_bind,
+    });
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    let startup = &metrics["server_stats"]["startup"];
+    for phase in [
+        "config_load_seconds",
+        "cert_load_seconds",
+        "listener_bind_seconds",
+    ] {
+        assert!(
+            startup[phase].as_f64().unwrap() > 0.0,
+            "{phase} not positive"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_response_carries_bucket_headers() {
+    let mut config = AppConfig::default();
+    config.rate_limit.requests_per_sec = 0.5;
+    config.rate_limit.burst = 3;
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let addr = common::spawn_router(router).await;
+
+    for _ in 0..3 {
+        assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    }
+
+    let response = common::get(addr, "/health/live").await;
+    assert_eq!(response.status, 429);
+    assert_eq!(response.header("RateLimit-Limit"), Some("3"));
+    assert_eq!(response.header("RateLimit-Remaining"), Some("0"));
+    // An empty bucket refills one token every 2s and all three in 6s
+    assert_eq!(response.header("Retry-After"), Some("2"));
+    assert_eq!(response.header("RateLimit-Reset"), Some("6"));
+    assert_eq!(response.json()["status"], 429);
+}
+
+#[tokio::test]
+async fn test_ssl_status_reports_loaded_certificate() {
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.set_certificate_info(ssl_manager.certificate_info_handle());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/ssl-status").await;
+    assert_eq!(response.status, 200);
+    let body = response.json();
+    assert_eq!(body["status"], "active");
+    let certificate = &body["certificate"];
+    assert_eq!(certificate["subject"], "CN=localhost");
+    assert_eq!(certificate["issuer"], "CN=localhost");
+    assert_eq!(certificate["valid_from"], "2024-01-01T00:00:00Z");
+    assert_eq!(certificate["valid_until"], "2099-12-31T23:59:59Z");
+    assert_eq!(certificate["is_expired"], false);
+    // openssl x509 -in tests/fixtures/cert.pem -noout -fingerprint -sha256
+    assert_eq!(
+        certificate["fingerprint_sha256"],
+        "28:AB:7C:48:B5:AC:ED:43:14:3E:F5:11:63:67:C1:FE:\
+         96:A1:99:FC:C5:B0:EE:7E:EA:0E:F1:1B:B0:9A:D8:8A"
+    );
+    assert_eq!(certificate["dns_names"], serde_json::json!(["localhost"]));
+    // Served over plaintext, so there is no negotiated session to report
+    assert!(body["tls_version"].is_null());
+    assert!(body["cipher_suite"].is_null());
+}
+
+#[tokio::test]
+async fn test_ssl_status_degraded_once_not_after_passes_between_monitor_ticks() {
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let certificate_info = ssl_manager.certificate_info_handle();
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.set_certificate_info(certificate_info.clone());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    if let Some(cert) = certificate_info.write().unwrap().as_mut() {
+        cert.not_after = SystemTime::now() - Duration::from_secs(1);
+    }
+    let response = common::get(addr, "/ssl-status").await;
+    assert_eq!(response.status, 503);
+    let body = response.json();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["certificate"]["is_expired"], true);
+}
+
+#[tokio::test]
+async fn test_ssl_status_reports_negotiated_cipher_suite() {
+    for (suites, expected, version) in [
+        (
+            &["TLS13_CHACHA20_POLY1305_SHA256"][..],
+            "TLS13_CHACHA20_POLY1305_SHA256",
+            "1.3",
+        ),
+        (
+            &["TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"][..],
+            "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+            "1.2",
+        ),
+    ] {
+        let mut config = AppConfig {
+            ssl: common::fixture_ssl_config(),
+            ..AppConfig::default()
+        };
+        config.ssl.cipher_suites = suites.iter().map(|suite| suite.to_string()).collect();
+        let ssl_manager = SslManager::new(&config.ssl).unwrap();
+        let mut router = Router::new(common::ttl_controller(), &config);
+        router.set_certificate_info(ssl_manager.certificate_info_handle());
+        let handler = ConnectionHandler::new(Arc::new(router), &config);
+        let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+        let body = common::tls_get(addr, "/ssl-status").await.json();
+        assert_eq!(body["cipher_suite"], expected);
+        assert_eq!(body["tls_version"], version);
+    }
+}
+
+#[tokio::test]
+async fn test_ssl_status_degraded_when_certificate_expired() {
+    let ssl_manager = SslManager::new(&common::expired_ssl_config()).unwrap();
+    let mut router = Router::new(common::ttl_controller(), &AppConfig::default());
+    router.set_certificate_info(ssl_manager.certificate_info_handle());
+    let addr = common::spawn_router(Arc::new(router)).await;
+
+    let response = common::get(addr, "/ssl-status").await;
+    assert_eq!(response.status, 503);
+    let body = response.json();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["certificate"]["valid_until"], "2020-12-31T23:59:59Z");
+    assert!(body["certificate"]["days_until_expiry"].as_i64().unwrap() < 0);
+}
+
+#[tokio::test]
+async fn test_prometheus_metrics_expose_ttl_stats() {
+    let ttl_controller = common::ttl_controller();
+    ttl_controller.register_connection("192.0.2.1".parse().unwrap());
+    ttl_controller.register_connection("192.0.2.2".parse().unwrap());
+    ttl_controller.force_cleanup_connection("192.0.2.2".parse().unwrap());
+    let router = Arc::new(Router::new(ttl_controller, &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::get(addr, "/metrics/prometheus").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("text/plain; version=0.0.4; charset=utf-8")
+    );
+
+    let body = &response.body;
+    assert!(body.contains("# TYPE rusty_ssl_active_connections gauge\n"));
+    assert!(body.contains("# TYPE rusty_ssl_total_connections counter\n"));
+    assert!(body.contains("# TYPE rusty_ssl_expired_connections counter\n"));
+    assert!(body.contains("# TYPE rusty_ssl_average_ttl_seconds gauge\n"));
+    assert!(body.contains("# HELP rusty_ssl_scrape_duration_seconds "));
+
+    // The scrape itself registers 127.0.0.1 next to the remaining client
+    let sample = |name: &str| {
+        body.lines()
+            .find_map(|line| line.strip_prefix(&format!("{name} ")))
+            .unwrap_or_else(|| panic!("no sample for {name}"))
+            .to_string()
+    };
+    assert_eq!(sample("rusty_ssl_active_connections"), "2");
+    assert_eq!(sample("rusty_ssl_total_connections"), "3");
+    assert_eq!(sample("rusty_ssl_expired_connections"), "1");
+    assert_eq!(sample("rusty_ssl_average_ttl_seconds"), "300");
+
+    // Existing dashboards keep getting JSON from /metrics
+    let json = common::get(addr, "/metrics").await.json();
+    assert_eq!(json["ttl_stats"]["active_connections"], 2);
+}
+
+#[tokio::test]
+async fn test_request_latency_is_reported_in_json_and_prometheus() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+    for _ in 0..3 {
+        assert_eq!(common::get(addr, "/health/live").await.status, 200);
+    }
+
+    let metrics = common::get(addr, "/metrics").await.json();
+    assert_eq!(metrics["latency"]["count"], 3);
+    for key in ["p50_ms", "p95_ms", "p99_ms"] {
+        assert!(metrics["latency"][key].as_f64().unwrap() > 0.0, "{key}");
+    }
+
+    // The JSON scrape above has been recorded by now
+    let body = common::get(addr, "/metrics/prometheus").await.body;
+    assert!(body.contains("# TYPE rusty_ssl_request_duration_seconds histogram\n"));
+    assert!(body.contains("rusty_ssl_request_duration_seconds_bucket{le=\"+Inf\"} 4\n"));
+    assert!(body.contains("rusty_ssl_request_duration_seconds_count 4\n"));
+    assert!(body.contains("rusty_ssl_request_duration_percentile_seconds{quantile=\"0.99\"} "));
+}
+
+#[tokio::test]
+async fn test_requests_beyond_burst_get_429_until_connection_expires() {
+    const BURST: u32 = 5;
+    const EXCESS: u32 = 4;
+    let mut config = AppConfig::default();
+    config.rate_limit.requests_per_sec = 0.1;
+    config.rate_limit.burst = BURST;
+    let ttl_controller = Arc::new(rusty_ssl::TtlController::new(
+        std::time::Duration::from_secs(1),
+        std::time::Duration::from_secs(1),
+        std::time::Duration::from_secs(60),
+        1000,
+    ));
+    let router = Arc::new(Router::new(ttl_controller.clone(), &config));
+    let addr = common::spawn_router(router).await;
+
+    let mut statuses = Vec::new();
+    for _ in 0..BURST + EXCESS {
+        let response = common::get(addr, "/health/live").await;
+        if response.status == 429 {
+            assert!(response.header("Retry-After").is_some());
+        }
+        statuses.push(response.status);
+    }
+    assert_eq!(
+        statuses.iter().filter(|&&status| status == 200).count(),
+        BURST as usize
+    );
+    assert_eq!(
+        statuses.iter().filter(|&&status| status == 429).count(),
+        EXCESS as usize
+    );
+    assert!(
+        statuses[..BURST as usize]
+            .iter()
+            .all(|&status| status == 200)
+    );
+
+    // The client's bucket goes away together with its connection
+    let rate_limiter = ttl_controller.attach_rate_limiter(100.0, 100);
+    assert_eq!(rate_limiter.tracked_clients(), 1);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    ttl_controller.cleanup_expired_connections().await;
+    assert_eq!(rate_limiter.tracked_clients(), 0);
+    assert_eq!(common::get(addr, "/health/live").await.status, 200);
+}
+
+#[tokio::test]
+async fn test_registered_routes_are_served_next_to_builtin_ones() {
+    let ttl_controller = common::ttl_controller();
+    let mut router = Router::new(ttl_controller.clone(), &AppConfig::default());
+    router
+        .register(Method::GET, "/greet/:name", |req, client_ip| async move {
+            let name = req
+                .extensions()
+                .get::<PathParams>()
+                .and_then(|params| params.get("name"))
+                .unwrap_or_default()
+                .to_string();
+            Ok(Response::new(Full::new(Bytes::from(format!(
+                "hello {name} at {client_ip}"
+            )))))
+        })
+        .unwrap();
+    router
+        .register(Method::POST, "/echo", |req, _| async move {
+            let body = req.into_body().collect().await?.to_bytes();
+            Ok(Response::new(Full::new(body)))
+        })
+        .unwrap();
+
+    // Built-in routes can't be shadowed
+    let taken = router.register(Method::GET, "/health", |_, _| async {
+        Ok(Response::new(Full::new(Bytes::new())))
+    });
+    assert_eq!(
+        taken,
+        Err(RouteTableError::Duplicate {
+            method: Method::GET,
+            path: "/health".to_string(),
+        })
+    );
+
+    let addr = common::spawn_router(Arc::new(router)).await;
+    let greeting = common::get(addr, "/greet/ferris").await;
+    assert_eq!(greeting.status, 200);
+    assert_eq!(greeting.body, "hello ferris at 127.0.0.1");
+    // Custom routes are tracked like any other request
+    assert!(
+        ttl_controller
+            .get_connection_info("127.0.0.1".parse().unwrap())
+            .is_some()
+    );
+
+    let echo = common::send_raw(
+        addr,
+        "POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\
+         Connection: close\r\n\r\nping",
+    )
+    .await;
+    assert_eq!(echo.body, "ping");
+
+    assert_eq!(common::get(addr, "/health").await.status, 200);
+    assert_eq!(common::get(addr, "/greet").await.status, 404);
+    let wrong_method = common::get(addr, "/echo").await;
+    assert_eq!(wrong_method.status, 405);
+    assert_eq!(wrong_method.header("Allow"), Some("POST"));
+}
+
+#[tokio::test]
+async fn test_known_path_with_wrong_method_is_405_with_allow() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+
+    let response = common::send_raw(
+        addr,
+        "POST /health HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET"));
+    assert_eq!(response.json()["status"], 405);
+
+    // Patterns count too
+    let response = common::send_raw(
+        addr,
+        "DELETE /connections/127.0.0.1 HTTP/1.1\r\nHost: localhost\r\n\
+         Connection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET"));
+
+    let response = common::get(addr, "/nope").await;
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Allow"), None);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use rusty_ssl::TtlController;
+use rusty_ssl::server::{ConnectionSnapshot, StatsSnapshot, spawn_snapshot_task};
+
+#[tokio::test]
+async fn test_snapshot_written_on_interval_and_restorable() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-snapshot-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("connections.json");
+
+    let ttl_controller = common::ttl_controller();
+    let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+    let connection_id = ttl_controller.register_connection(ip);
+
+    let task = spawn_snapshot_task(
+        ttl_controller.clone(),
+        path.clone(),
+        Duration::from_millis(50),
+    );
+
+    // A later tick must pick up connections registered after the first write
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let late_ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 11));
+    ttl_controller.register_connection(late_ip);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    task.abort();
+
+    let snapshot = ConnectionSnapshot::load(&path).unwrap();
+    assert_eq!(snapshot.connections.len(), 2);
+    assert!(!dir.join("connections.json.tmp").exists());
+
+    let restored = common::ttl_controller();
+    assert_eq!(restored.restore_snapshot(&snapshot), 2);
+    let connection = restored.get_connection_info(ip).unwrap();
+    assert_eq!(connection.id, connection_id);
+    assert_eq!(connection.ttl, Duration::from_secs(300));
+    assert!(restored.get_connection_info(late_ip).is_some());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn stats_controller(path: &std::path::Path) -> TtlController {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(1),
+        Duration::from_secs(1),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_stats_path(path.to_path_buf());
+    ttl_controller
+}
+
+#[tokio::test]
+async fn test_stats_counters_resume_after_restart() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-stats-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ttl-stats.json");
+
+    let ttl_controller = stats_controller(&path);
+    for i in 1..=3 {
+        ttl_controller.register_connection(IpAddr::V4(Ipv4Addr::new(192, 0, 2, i)));
+    }
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    // Each cleanup tick persists the counters, but not the connections
+    ttl_controller.cleanup_expired_connections().await;
+    ttl_controller.register_connection(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 4)));
+    ttl_controller.persist_stats().unwrap();
+    drop(ttl_controller);
+
+    let saved = StatsSnapshot::load(&path).unwrap();
+    assert_eq!((saved.total_connections, saved.expired_connections), (4, 3));
+
+    let restarted = stats_controller(&path);
+    let stats = restarted.get_stats();
+    assert_eq!(stats.total_connections, 4);
+    assert_eq!(stats.expired_connections, 3);
+    assert_eq!(stats.active_connections, 0);
+
+    restarted.register_connection(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 5)));
+    assert_eq!(restarted.get_stats().total_connections, 5);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_missing_or_corrupt_stats_start_from_zero() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-stats-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ttl-stats.json");
+
+    let fresh = stats_controller(&path);
+    assert_eq!(fresh.get_stats().total_connections, 0);
+
+    std::fs::write(&path, b"{not json").unwrap();
+    let (_guard, logs) = common::capture_logs();
+    let corrupt = stats_controller(&path);
+    let stats = corrupt.get_stats();
+    assert_eq!((stats.total_connections, stats.expired_connections), (0, 0));
+    assert!(logs.contents().contains("Ignoring unreadable TTL stats"));
+
+    // The next write replaces the corrupt file
+    corrupt.persist_stats().unwrap();
+    assert_eq!(StatsSnapshot::load(&path).unwrap().total_connections, 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,632 @@
+mod common;
+
+use std::sync::Arc;
+
+use rustls::CipherSuite;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::CertificateDer;
+use rusty_ssl::server::spawn_certificate_watcher;
+use rusty_ssl::server::ssl_manager::{SslError, sanitize_chain, selected_crypto_provider};
+use rusty_ssl::utils::config::{AcmeConfig, ClientAuthMode, StartupRetryConfig};
+use rusty_ssl::{AppConfig, ConnectionHandler, Router, SslManager};
+
+#[tokio::test]
+async fn test_tls_buffer_tuning_applied() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.buffer_limit_bytes = Some(16 * 1024);
+    ssl_config.max_fragment_size = Some(4096);
+
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    assert_eq!(ssl_manager.get_config().max_fragment_size, Some(4096));
+    assert_eq!(ssl_manager.acceptor().buffer_limit(), Some(16 * 1024));
+}
+
+#[tokio::test]
+async fn test_invalid_fragment_size_rejected() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.max_fragment_size = Some(16);
+
+    assert!(matches!(
+        SslManager::new(&ssl_config),
+        Err(SslError::InvalidMaxFragmentSize(16))
+    ));
+}
+
+#[tokio::test]
+async fn test_selected_crypto_provider_installed_and_serves_tls() {
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+
+    let installed = CryptoProvider::get_default().expect("no crypto provider installed");
+    let expected = selected_crypto_provider();
+    let suites = |provider: &CryptoProvider| -> Vec<_> {
+        provider.cipher_suites.iter().map(|s| s.suite()).collect()
+    };
+    let groups = |provider: &CryptoProvider| -> Vec<_> {
+        provider.kx_groups.iter().map(|g| g.name()).collect()
+    };
+    assert_eq!(suites(installed), suites(&expected));
+    assert_eq!(groups(installed), groups(&expected));
+
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let handler = ConnectionHandler::new(router, &AppConfig::default());
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let response = common::tls_get(addr, "/health/live").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["status"], "alive");
+}
+
+fn load_chain(path: &std::path::Path) -> Vec<CertificateDer<'static>> {
+    let pem = std::fs::read(path).unwrap();
+    rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_duplicate_chain_cert_is_not_served_twice() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cert_path = common::fixture_chain(&["cert.pem", "cert.pem"]);
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let handler = ConnectionHandler::new(router, &AppConfig::default());
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let stream = common::tls_connect(addr, common::tls_client_config())
+        .await
+        .unwrap();
+    let served = stream.get_ref().1.peer_certificates().unwrap();
+    assert_eq!(served.len(), 1);
+
+    std::fs::remove_file(&ssl_config.cert_path).unwrap();
+}
+
+#[test]
+fn test_self_signed_root_detected_in_chain() {
+    let path = common::fixture_chain(&["leaf-cert.pem", "ca-cert.pem"]);
+    let chain = load_chain(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(chain.len(), 2);
+
+    let stripped = sanitize_chain(chain.clone(), true);
+    assert_eq!(stripped, chain[..1]);
+
+    // Detection alone leaves the chain untouched
+    assert_eq!(sanitize_chain(chain.clone(), false), chain);
+
+    // A lone self-signed leaf is the certificate itself, not a bundled root
+    let leaf_only = load_chain(&common::fixture_path("cert.pem"));
+    assert_eq!(sanitize_chain(leaf_only.clone(), true), leaf_only);
+}
+
+#[tokio::test]
+async fn test_handshake_failure_rate_raises_metrics_alert() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut config = AppConfig::default();
+    config.ssl.handshake_failure_threshold = 0.5;
+    config.ssl.handshake_failure_min_samples = 5;
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let metrics = common::tls_get(addr, "/metrics").await.json();
+    assert_eq!(metrics["server_stats"]["handshake_failure_alert"], false);
+
+    // Plaintext probes fail the handshake, as scanners' do
+    for _ in 0..5 {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await;
+        let mut buf = Vec::new();
+        let _ = stream.read_to_end(&mut buf).await;
+    }
+
+    let metrics = common::tls_get(addr, "/metrics").await.json();
+    let server_stats = &metrics["server_stats"];
+    assert_eq!(server_stats["handshake_failures"], 5);
+    assert_eq!(server_stats["handshake_failure_alert"], true);
+    assert!(server_stats["handshake_failure_rate"].as_f64().unwrap() >= 0.5);
+}
+
+#[tokio::test]
+async fn test_require_sni_rejects_clients_without_server_name() {
+    let config = AppConfig::default();
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.require_sni = true;
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let mut no_sni = common::tls_client_config();
+    no_sni.enable_sni = false;
+    assert!(common::tls_connect(addr, no_sni).await.is_err());
+
+    // Clients that name the server are still served
+    let metrics = common::tls_get(addr, "/metrics").await.json();
+    assert_eq!(metrics["server_stats"]["sni_rejections"], 1);
+    assert_eq!(metrics["server_stats"]["handshake_failures"], 1);
+}
+
+#[tokio::test]
+async fn test_certificate_info_reads_validity_from_certificate() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let ssl_manager = SslManager::new(&common::expired_ssl_config()).unwrap();
+    let info = ssl_manager.get_certificate_info().unwrap();
+    // 2020-01-01T00:00:00Z and 2020-12-31T23:59:59Z
+    assert_eq!(
+        info.not_before,
+        UNIX_EPOCH + Duration::from_secs(1_577_836_800)
+    );
+    assert_eq!(
+        info.not_after,
+        UNIX_EPOCH + Duration::from_secs(1_609_459_199)
+    );
+    assert!(info.is_expired);
+    assert!(info.days_until_expiry < 0);
+
+    let ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let info = ssl_manager.get_certificate_info().unwrap();
+    // 2099-12-31T23:59:59Z
+    assert_eq!(
+        info.not_after,
+        UNIX_EPOCH + Duration::from_secs(4_102_444_799)
+    );
+    assert!(!info.is_expired);
+    assert!(info.days_until_expiry > 365 * 70);
+}
+
+#[tokio::test]
+async fn test_pkcs8_pkcs1_and_sec1_keys_all_serve_tls() {
+    let config = AppConfig::default();
+    for (cert, key) in [
+        ("cert.pem", "key.pem"),
+        ("cert.pem", "rsa-key.pem"),
+        ("ec-cert.pem", "ec-key.pem"),
+    ] {
+        let mut ssl_config = common::fixture_ssl_config();
+        ssl_config.cert_path = common::fixture_path(cert);
+        ssl_config.key_path = common::fixture_path(key);
+        let ssl_manager =
+            SslManager::new(&ssl_config).unwrap_or_else(|e| panic!("{key} was not loaded: {e}"));
+
+        let router = Arc::new(Router::new(common::ttl_controller(), &config));
+        let handler = ConnectionHandler::new(router, &config);
+        let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+        assert_eq!(
+            common::tls_get(addr, "/health/live").await.status,
+            200,
+            "{key}"
+        );
+    }
+}
+
+async fn served_certificate(
+    addr: std::net::SocketAddr,
+    server_name: &str,
+) -> CertificateDer<'static> {
+    let stream = common::tls_connect_as(addr, common::tls_client_config(), server_name)
+        .await
+        .unwrap();
+    stream.get_ref().1.peer_certificates().unwrap()[0].clone()
+}
+
+#[tokio::test]
+async fn test_sni_certificates_selected_by_hostname_and_reloadable() {
+    use rusty_ssl::utils::config::SniCert;
+
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-sni-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (alt_cert, alt_key) = (dir.join("alt-cert.pem"), dir.join("alt-key.pem"));
+    std::fs::copy(common::fixture_path("alt-cert.pem"), &alt_cert).unwrap();
+    std::fs::copy(common::fixture_path("alt-key.pem"), &alt_key).unwrap();
+
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.sni_certs = vec![SniCert {
+        hostname: "Alt.Test".to_string(),
+        cert_path: alt_cert.clone(),
+        key_path: alt_key.clone(),
+    }];
+    let mut ssl_manager = SslManager::new(&ssl_config).unwrap();
+    assert_eq!(
+        ssl_manager.sni_resolver().unwrap().hostnames(),
+        ["alt.test"]
+    );
+
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let default_cert = load_chain(&common::fixture_path("cert.pem")).remove(0);
+    let alt = load_chain(&common::fixture_path("alt-cert.pem")).remove(0);
+    assert_eq!(served_certificate(addr, "alt.test").await, alt);
+    // Unknown names fall back to the default certificate
+    assert_eq!(served_certificate(addr, "other.test").await, default_cert);
+
+    // Reloading swaps the certificate behind the already-running acceptor
+    std::fs::copy(common::fixture_path("ec-cert.pem"), &alt_cert).unwrap();
+    std::fs::copy(common::fixture_path("ec-key.pem"), &alt_key).unwrap();
+    ssl_manager.reload_certificates().await.unwrap();
+    let ec = load_chain(&common::fixture_path("ec-cert.pem")).remove(0);
+    assert_eq!(served_certificate(addr, "alt.test").await, ec);
+}
+
+async fn tls_whoami(
+    addr: std::net::SocketAddr,
+    client: rustls::ClientConfig,
+) -> common::RawResponse {
+    let Ok(mut stream) = common::tls_connect(addr, client).await else {
+        return common::parse_response("");
+    };
+    common::exchange(
+        &mut stream,
+        "GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await
+}
+
+async fn spawn_client_auth_server(mode: ClientAuthMode) -> std::net::SocketAddr {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.client_auth.mode = mode;
+    ssl_config.client_auth.ca_path = Some(common::fixture_path("ca-cert.pem"));
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    common::spawn_tls_handler(ssl_manager.acceptor(), handler).await
+}
+
+#[tokio::test]
+async fn test_required_client_auth_rejects_clients_without_certificate() {
+    let addr = spawn_client_auth_server(ClientAuthMode::Required).await;
+
+    let response = tls_whoami(addr, common::tls_client_config()).await;
+    assert_eq!(response.status, 0);
+
+    let client = common::tls_client_config_with_cert("client-cert.pem", "client-key.pem");
+    let response = tls_whoami(addr, client).await;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.json()["client_cert"]["subject_cn"],
+        "rusty-ssl-client"
+    );
+}
+
+#[tokio::test]
+async fn test_optional_client_auth_serves_anonymous_clients() {
+    let addr = spawn_client_auth_server(ClientAuthMode::Optional).await;
+
+    let response = tls_whoami(addr, common::tls_client_config()).await;
+    assert_eq!(response.status, 200);
+    assert!(response.json()["client_cert"].is_null());
+
+    // A certificate from an untrusted issuer is still refused
+    let client = common::tls_client_config_with_cert("cert.pem", "key.pem");
+    assert_eq!(tls_whoami(addr, client).await.status, 0);
+}
+
+#[test]
+fn test_client_auth_without_ca_is_rejected() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.client_auth.mode = ClientAuthMode::Required;
+    assert!(matches!(
+        SslManager::new(&ssl_config),
+        Err(SslError::ClientAuth(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_certificate_files_changing_on_disk_reload_once() {
+    use std::time::{Duration, Instant};
+
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-watch-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cert_path = dir.join("cert.pem");
+    ssl_config.key_path = dir.join("key.pem");
+    std::fs::copy(common::fixture_path("cert.pem"), &ssl_config.cert_path).unwrap();
+    std::fs::copy(common::fixture_path("key.pem"), &ssl_config.key_path).unwrap();
+
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    let acceptor = ssl_manager.acceptor();
+    let ssl_manager = Arc::new(tokio::sync::Mutex::new(ssl_manager));
+    let _watcher =
+        spawn_certificate_watcher(ssl_manager.clone(), &ssl_config, Duration::from_millis(200))
+            .unwrap();
+
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(acceptor, handler).await;
+
+    // A renewal rewrites the certificate and key back to back
+    std::fs::copy(common::fixture_path("ec-cert.pem"), &ssl_config.cert_path).unwrap();
+    std::fs::copy(common::fixture_path("ec-key.pem"), &ssl_config.key_path).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while ssl_manager.lock().await.reload_count() == 0 {
+        assert!(
+            Instant::now() < deadline,
+            "certificate change was never picked up"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(ssl_manager.lock().await.reload_count(), 1);
+
+    // The acceptor handed out before the change serves the new certificate
+    let ec = load_chain(&common::fixture_path("ec-cert.pem")).remove(0);
+    assert_eq!(served_certificate(addr, "localhost").await, ec);
+}
+
+#[tokio::test]
+async fn test_metrics_require_client_certificate_when_configured() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.client_auth.require_for_metrics = true;
+    ssl_config.client_auth.ca_path = Some(common::fixture_path("ca-cert.pem"));
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+
+    let config = AppConfig {
+        ssl: ssl_config,
+        ..Default::default()
+    };
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    // Anonymous clients still complete the handshake and reach other routes
+    assert_eq!(common::tls_get(addr, "/health").await.status, 200);
+    let response = common::tls_get(addr, "/metrics").await;
+    assert_eq!(response.status, 403);
+    assert_eq!(response.json()["error"], "Forbidden");
+
+    let client = common::tls_client_config_with_cert("client-cert.pem", "client-key.pem");
+    let mut stream = common::tls_connect(addr, client).await.unwrap();
+    let response = common::exchange(
+        &mut stream,
+        "GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+}
+
+/// ACME settings pointing at a directory nothing listens on
+fn unreachable_acme() -> AcmeConfig {
+    AcmeConfig {
+        directory_url: "http://127.0.0.1:1/directory".to_string(),
+        domains: vec!["localhost".to_string()],
+        http_challenge_addr: "127.0.0.1:0".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_acme_skips_order_while_cached_certificate_is_fresh() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.acme = Some(unreachable_acme());
+
+    let ssl_manager = SslManager::provision(&ssl_config).await.unwrap();
+    assert!(!ssl_manager.get_certificate_info().unwrap().is_expired);
+}
+
+#[tokio::test]
+async fn test_acme_orders_when_cached_certificate_is_due() {
+    let mut ssl_config = common::expired_ssl_config();
+    ssl_config.acme = Some(unreachable_acme());
+
+    // The order is attempted, and the CA being unreachable is reported
+    assert!(matches!(
+        SslManager::provision(&ssl_config).await,
+        Err(SslError::Acme(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_configured_cipher_suites_restrict_negotiation() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cipher_suites = vec!["TLS13_AES_256_GCM_SHA384".to_string()];
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    let suites: Vec<_> = ssl_manager
+        .get_config()
+        .crypto_provider()
+        .cipher_suites
+        .iter()
+        .map(|suite| suite.suite())
+        .collect();
+    assert_eq!(suites, [CipherSuite::TLS13_AES_256_GCM_SHA384]);
+
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let stream = common::tls_connect(addr, common::tls_client_config())
+        .await
+        .unwrap();
+    let negotiated = stream.get_ref().1.negotiated_cipher_suite().unwrap();
+    assert_eq!(negotiated.suite(), CipherSuite::TLS13_AES_256_GCM_SHA384);
+}
+
+#[test]
+fn test_unknown_cipher_suite_fails_validation() {
+    let mut config = AppConfig::default();
+    config.ssl.cipher_suites = vec!["TLS13_ROT13_SHA1".to_string()];
+
+    let errors = config.validate().unwrap_err();
+    let message = errors
+        .iter()
+        .find(|error| error.field == "ssl.cipher_suites")
+        .unwrap()
+        .to_string();
+    assert!(message.contains("TLS13_ROT13_SHA1"), "{message}");
+    assert!(message.contains("TLS13_AES_256_GCM_SHA384"), "{message}");
+}
+
+#[tokio::test]
+async fn test_alpn_protocols_are_advertised() {
+    let default_ssl = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    assert_eq!(
+        default_ssl.get_config().alpn_protocols,
+        [b"http/1.1".to_vec()]
+    );
+
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.alpn = vec!["h2".to_string(), "http/1.1".to_string()];
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    let config = AppConfig::default();
+    let router = Arc::new(Router::new(common::ttl_controller(), &config));
+    let handler = ConnectionHandler::new(router, &config);
+    let addr = common::spawn_tls_handler(ssl_manager.acceptor(), handler).await;
+
+    let mut client = common::tls_client_config();
+    client.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let mut stream = common::tls_connect(addr, client).await.unwrap();
+    assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+
+    let response = common::exchange(
+        &mut stream,
+        "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    )
+    .await;
+    assert_eq!(response.status, 200);
+}
+
+fn quick_retry(max_attempts: u32) -> StartupRetryConfig {
+    StartupRetryConfig {
+        max_attempts,
+        initial_delay_ms: 10,
+        max_delay_ms: 40,
+    }
+}
+
+fn missing_cert_config(dir: &std::path::Path) -> rusty_ssl::utils::config::SslConfig {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cert_path = dir.join("cert.pem");
+    ssl_config.key_path = dir.join("key.pem");
+    ssl_config
+}
+
+#[tokio::test]
+async fn test_startup_waits_for_certificate_files_to_appear() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-retry-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut ssl_config = missing_cert_config(&dir);
+    ssl_config.startup_retry = Some(quick_retry(50));
+
+    let writer = {
+        let dir = dir.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            std::fs::copy(common::fixture_path("key.pem"), dir.join("key.pem")).unwrap();
+            // Renamed into place so the load never sees a partial file
+            std::fs::copy(common::fixture_path("cert.pem"), dir.join("cert.tmp")).unwrap();
+            std::fs::rename(dir.join("cert.tmp"), dir.join("cert.pem")).unwrap();
+        })
+    };
+    let ssl_manager = SslManager::provision(&ssl_config).await;
+    writer.await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(ssl_manager.is_ok());
+}
+
+#[tokio::test]
+async fn test_startup_retry_gives_up_after_max_attempts() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-retry-{}", uuid::Uuid::new_v4()));
+    let mut ssl_config = missing_cert_config(&dir);
+    ssl_config.startup_retry = Some(quick_retry(3));
+
+    let started = std::time::Instant::now();
+    let result = SslManager::provision(&ssl_config).await;
+    assert!(matches!(result, Err(SslError::CertificateNotFound { .. })));
+    // Waited 10ms, then 20ms, between the three attempts
+    assert!(started.elapsed() >= std::time::Duration::from_millis(30));
+}
+
+#[tokio::test]
+async fn test_startup_retry_fails_fast_on_invalid_certificate() {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-retry-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("cert.pem"), "not a certificate").unwrap();
+    std::fs::copy(common::fixture_path("key.pem"), dir.join("key.pem")).unwrap();
+    let mut ssl_config = missing_cert_config(&dir);
+    ssl_config.startup_retry = Some(StartupRetryConfig {
+        initial_delay_ms: 60_000,
+        max_delay_ms: 60_000,
+        ..StartupRetryConfig::default()
+    });
+
+    let result = SslManager::provision(&ssl_config).await;
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(matches!(result, Err(SslError::NoCertificatesFound)));
+}
+
+#[test]
+fn test_startup_retry_skips_certificate_existence_checks() {
+    let dir = std::env::temp_dir().join("rusty-ssl-retry-missing");
+    let mut config = AppConfig {
+        ssl: missing_cert_config(&dir),
+        ..AppConfig::default()
+    };
+    let errors = config.validate().unwrap_err();
+    assert!(errors.iter().any(|e| e.field == "ssl.cert_path"));
+
+    config.ssl.startup_retry = Some(StartupRetryConfig::default());
+    assert!(config.validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_reload_logs_certificate_fingerprint() {
+    let (_guard, logs) = common::capture_logs();
+    let mut ssl_manager = SslManager::new(&common::fixture_ssl_config()).unwrap();
+    let fingerprint = ssl_manager
+        .get_certificate_info()
+        .unwrap()
+        .fingerprint_sha256;
+    assert_eq!(fingerprint.len(), 32 * 3 - 1);
+
+    ssl_manager.reload_certificates().await.unwrap();
+    let logs = logs.contents();
+    assert!(
+        logs.contains(&format!("unchanged (SHA-256 {fingerprint})")),
+        "{logs}"
+    );
+}
+
+#[tokio::test]
+async fn test_certificate_info_lists_dns_names() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.cert_path = common::fixture_path("san-cert.pem");
+    ssl_config.key_path = common::fixture_path("san-key.pem");
+    let ssl_manager = SslManager::new(&ssl_config).unwrap();
+    let info = ssl_manager.get_certificate_info().unwrap();
+
+    // The IP address SAN is left out
+    assert_eq!(
+        info.dns_names,
+        ["example.test", "www.example.test", "*.api.example.test"]
+    );
+    assert!(info.covers("www.example.test"));
+    assert!(info.covers("EXAMPLE.test."));
+    assert!(info.covers("v1.api.example.test"));
+    assert!(!info.covers("api.example.test"));
+    assert!(!info.covers("a.b.api.example.test"));
+    assert!(!info.covers("other.test"));
+}
+
+#[tokio::test]
+async fn test_mismatched_key_is_refused_at_startup() {
+    let mut ssl_config = common::fixture_ssl_config();
+    ssl_config.key_path = common::fixture_path("alt-key.pem");
+
+    let error = SslManager::new(&ssl_config).err().unwrap();
+    assert!(matches!(error, SslError::KeyCertMismatch { .. }), "{error}");
+    let message = error.to_string();
+    assert!(message.contains("alt-key.pem"), "{message}");
+    assert!(message.contains("cert.pem"), "{message}");
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rusty_ssl::server::match_path;
+use rusty_ssl::{AppConfig, Router};
+
+/// A temporary static root holding `app.js`, `css/site.css` and an
+/// empty `assets/` directory, next to a file outside the root
+fn static_root() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rusty-ssl-static-{}", uuid::Uuid::new_v4()));
+    let root = dir.join("public");
+    std::fs::create_dir_all(root.join("css")).unwrap();
+    std::fs::create_dir_all(root.join("assets")).unwrap();
+    std::fs::write(root.join("app.js"), "console.log('hi');").unwrap();
+    std::fs::write(root.join("css/site.css"), "body { margin: 0 }").unwrap();
+    std::fs::write(dir.join("secret.txt"), "top secret").unwrap();
+    root
+}
+
+async fn spawn_static_router(root: &Path) -> SocketAddr {
+    let mut config = AppConfig::default();
+    config.static_files.root = Some(root.to_path_buf());
+    common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await
+}
+
+fn get_with(path: &str, header: &str) -> String {
+    format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n{header}\r\nConnection: close\r\n\r\n")
+}
+
+#[test]
+fn test_tail_segment_captures_rest_of_path() {
+    let params = match_path("/static/*path", "/static/css/site.css").unwrap();
+    assert_eq!(params.get("path"), Some("css/site.css"));
+    assert!(match_path("/static/*path", "/static/").is_none());
+    assert!(match_path("/static/*path", "/static").is_none());
+    assert!(match_path("/static/*path", "/other/app.js").is_none());
+}
+
+#[tokio::test]
+async fn test_files_are_served_with_type_and_validators() {
+    let root = static_root();
+    let addr = spawn_static_router(&root).await;
+
+    let response = common::get(addr, "/static/app.js").await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "console.log('hi');");
+    assert_eq!(
+        response.header("Content-Type"),
+        Some("text/javascript; charset=utf-8")
+    );
+    let etag = response.header("ETag").unwrap().to_string();
+    let last_modified = response.header("Last-Modified").unwrap().to_string();
+
+    let nested = common::get(addr, "/static/css/site.css").await;
+    assert_eq!(nested.status, 200);
+    assert_eq!(
+        nested.header("Content-Type"),
+        Some("text/css; charset=utf-8")
+    );
+
+    let revalidated = common::send_raw(
+        addr,
+        &get_with("/static/app.js", &format!("If-None-Match: {etag}")),
+    )
+    .await;
+    assert_eq!(revalidated.status, 304);
+    assert!(revalidated.body.is_empty());
+
+    let since = format!("If-Modified-Since: {last_modified}");
+    let revalidated = common::send_raw(addr, &get_with("/static/app.js", &since)).await;
+    assert_eq!(revalidated.status, 304);
+
+    let stale = common::send_raw(addr, &get_with("/static/app.js", "If-None-Match: \"old\"")).await;
+    assert_eq!(stale.status, 200);
+
+    // Built-in endpoints are unaffected
+    assert_eq!(common::get(addr, "/health").await.status, 200);
+    std::fs::remove_dir_all(root.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_traversal_is_403_and_missing_files_are_404() {
+    let root = static_root();
+    let addr = spawn_static_router(&root).await;
+
+    for path in [
+        "/static/../secret.txt",
+        "/static/%2e%2e/secret.txt",
+        "/static/css/../../secret.txt",
+        "/static/css%2f..%2f..%2fsecret.txt",
+    ] {
+        let response = common::get(addr, path).await;
+        assert_eq!(response.status, 403, "{path}");
+        assert!(!response.body.contains("top secret"));
+    }
+
+    assert_eq!(common::get(addr, "/static/missing.js").await.status, 404);
+    assert_eq!(common::get(addr, "/static/assets").await.status, 404);
+    assert_eq!(common::get(addr, "/static/").await.status, 404);
+    std::fs::remove_dir_all(root.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_files_over_the_size_cap_are_refused() {
+    let root = static_root();
+    std::fs::write(root.join("big.bin"), vec![0u8; 4096]).unwrap();
+    let mut config = AppConfig::default();
+    config.static_files.root = Some(root.clone());
+    config.static_files.max_file_bytes = 1024;
+    let addr = common::spawn_router(Arc::new(Router::new(common::ttl_controller(), &config))).await;
+
+    assert_eq!(common::get(addr, "/static/app.js").await.status, 200);
+    let response = common::get(addr, "/static/big.bin").await;
+    assert_eq!(response.status, 500);
+    assert_eq!(response.json()["error"], "Internal Server Error");
+    std::fs::remove_dir_all(root.parent().unwrap()).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_out_of_root_is_403() {
+    let root = static_root();
+    std::os::unix::fs::symlink(
+        root.parent().unwrap().join("secret.txt"),
+        root.join("leak.txt"),
+    )
+    .unwrap();
+    let addr = spawn_static_router(&root).await;
+
+    assert_eq!(common::get(addr, "/static/leak.txt").await.status, 403);
+    std::fs::remove_dir_all(root.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_static_files_are_off_without_a_root() {
+    let router = Arc::new(Router::new(common::ttl_controller(), &AppConfig::default()));
+    let addr = common::spawn_router(router).await;
+    assert_eq!(common::get(addr, "/static/app.js").await.status, 404);
+}
+
+#[test]
+fn test_static_prefix_is_validated() {
+    let mut config = AppConfig {
+        ssl: common::fixture_ssl_config(),
+        ..AppConfig::default()
+    };
+    config.static_files.root = Some(PathBuf::from("/var/www"));
+    assert!(config.validate().is_ok());
+
+    for prefix in ["static", "/files/:name", "/assets/*"] {
+        config.static_files.prefix = prefix.to_string();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "static_files.prefix", "{prefix}");
+    }
+}
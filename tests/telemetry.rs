@@ -0,0 +1,95 @@
+#![cfg(feature = "otlp")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::sync::mpsc;
+
+use rusty_ssl::utils::config::TelemetryConfig;
+use rusty_ssl::utils::logger::configured_subscriber;
+use rusty_ssl::{AppConfig, Router};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+/// A collector that accepts one OTLP/HTTP export and hands over its path
+/// and body. Runs on its own thread since flushing blocks the caller.
+fn spawn_collector() -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        let path = request_line.split(' ').nth(1).unwrap().to_string();
+        tx.send((path, body)).unwrap();
+    });
+    (endpoint, rx)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[tokio::test]
+async fn test_request_spans_continue_inbound_traces() {
+    let (endpoint, exports) = spawn_collector();
+    let telemetry = TelemetryConfig {
+        otlp_endpoint: Some(endpoint),
+        ..TelemetryConfig::default()
+    };
+    let (subscriber, guard) =
+        configured_subscriber(&AppConfig::default().logging, &telemetry).unwrap();
+    let default = tracing::subscriber::set_default(subscriber);
+
+    let router = Router::new(common::ttl_controller(), &AppConfig::default());
+    let addr = common::spawn_router(Arc::new(router)).await;
+    let response = common::send_raw(
+        addr,
+        &format!(
+            "GET /health HTTP/1.1\r\nHost: localhost\r\n\
+             traceparent: 00-{TRACE_ID}-00f067aa0ba902b7-01\r\n\
+             Connection: close\r\n\r\n"
+        ),
+    )
+    .await;
+    assert_eq!(response.status, 200);
+
+    // Dropping the guard flushes the exporter
+    drop(default);
+    drop(guard);
+
+    let (path, body) = exports.recv().unwrap();
+    assert_eq!(path, "/v1/traces");
+    let trace_id: Vec<u8> = (0..TRACE_ID.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&TRACE_ID[i..i + 2], 16).unwrap())
+        .collect();
+    assert!(contains(&body, &trace_id), "span not in the inbound trace");
+    assert!(contains(&body, b"request"));
+    assert!(contains(&body, b"rusty-ssl"));
+}
@@ -0,0 +1,556 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusty_ssl::TtlController;
+use rusty_ssl::server::ConnectionEventKind;
+use rusty_ssl::server::ttl_controller::{ConnectionInfo, HISTORY_ENTRY_BYTES};
+use rusty_ssl::server::{AdaptiveTtlStrategy, TtlContext, TtlStrategy};
+use rusty_ssl::utils::config::{AdaptiveTtlConfig, TtlBand};
+
+#[test]
+fn test_request_count_saturates_and_history_stays_bounded() {
+    let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7));
+    let mut connection = ConnectionInfo::new(ip, Duration::from_secs(60));
+    connection.request_count = u64::MAX - 2;
+
+    for _ in 0..10_000 {
+        connection.update_activity();
+    }
+
+    assert_eq!(connection.request_count, u64::MAX);
+    assert_eq!(connection.history.len(), connection.history_limit());
+}
+
+struct FixedTtl(Duration);
+
+impl TtlStrategy for FixedTtl {
+    fn compute(&self, _ctx: &TtlContext) -> Duration {
+        self.0
+    }
+}
+
+#[tokio::test]
+async fn test_custom_ttl_strategy_is_used() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_ttl_strategy(Arc::new(FixedTtl(Duration::from_secs(42))));
+
+    let ip = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5));
+    ttl_controller.register_connection(ip);
+
+    let connection = ttl_controller.get_connection_info(ip).unwrap();
+    assert_eq!(connection.ttl, Duration::from_secs(42));
+}
+
+#[tokio::test]
+async fn test_ttl_clamped_to_cidr_band() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    // Adaptive logic that always asks for far more than the band allows
+    ttl_controller.set_ttl_strategy(Arc::new(FixedTtl(Duration::from_secs(3000))));
+    ttl_controller.set_ttl_bands(vec![
+        TtlBand {
+            cidr: "10.0.0.0/8".parse().unwrap(),
+            floor_secs: None,
+            ceiling_secs: Some(600),
+        },
+        TtlBand {
+            cidr: "10.1.0.0/16".parse().unwrap(),
+            floor_secs: None,
+            ceiling_secs: Some(60),
+        },
+    ]);
+
+    let banded = IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3));
+    let outside = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    ttl_controller.register_connection(banded);
+    ttl_controller.register_connection(outside);
+
+    // The first matching band wins, and other IPs only see the global max
+    let banded_ttl = ttl_controller.get_connection_info(banded).unwrap().ttl;
+    assert_eq!(banded_ttl, Duration::from_secs(600));
+    let outside_ttl = ttl_controller.get_connection_info(outside).unwrap().ttl;
+    assert_eq!(outside_ttl, Duration::from_secs(3000));
+}
+
+#[tokio::test]
+async fn test_ttl_raised_to_cidr_floor() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_ttl_strategy(Arc::new(FixedTtl(Duration::from_secs(5))));
+    ttl_controller.set_ttl_bands(vec![TtlBand {
+        cidr: "2001:db8::/32".parse().unwrap(),
+        floor_secs: Some(120),
+        ceiling_secs: None,
+    }]);
+
+    let ip: IpAddr = "2001:db8::7".parse().unwrap();
+    ttl_controller.register_connection(ip);
+    let ttl = ttl_controller.get_connection_info(ip).unwrap().ttl;
+    assert_eq!(ttl, Duration::from_secs(120));
+}
+
+#[tokio::test]
+async fn test_reconnect_within_grace_resumes_connection() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(20),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_reconnect_grace(Duration::from_secs(30));
+    let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 9));
+    let idle = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 10));
+
+    let id = ttl_controller.register_connection(ip);
+    ttl_controller.update_connection_activity(ip);
+    ttl_controller.register_connection(idle);
+    let original = ttl_controller.get_connection_info(ip).unwrap();
+    ttl_controller.connection_closed(ip);
+
+    // Only the IP whose connection just closed outlives its TTL
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    ttl_controller.cleanup_expired_connections().await;
+    assert!(ttl_controller.get_connection_info(idle).is_none());
+
+    ttl_controller.register_connection(ip);
+    let resumed = ttl_controller.get_connection_info(ip).unwrap();
+    assert_eq!(resumed.id, id);
+    assert_eq!(resumed.request_count, original.request_count + 1);
+    assert_eq!(resumed.established_at, original.established_at);
+
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.total_connections, 2);
+    assert_eq!(stats.expired_connections, 1);
+
+    // The grace window never brings back a connection removed by hand
+    assert!(ttl_controller.force_cleanup_connection(ip));
+    ttl_controller.register_connection(ip);
+    assert_ne!(ttl_controller.get_connection_info(ip).unwrap().id, id);
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.total_connections, 3);
+    assert_eq!(stats.expired_connections, 2);
+}
+
+#[tokio::test]
+async fn test_reconnect_without_grace_counts_new_connection() {
+    let ttl_controller = TtlController::new(
+        Duration::from_millis(20),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 9));
+
+    let id = ttl_controller.register_connection(ip);
+    ttl_controller.connection_closed(ip);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    ttl_controller.cleanup_expired_connections().await;
+
+    assert_ne!(ttl_controller.register_connection(ip), id);
+    assert_eq!(ttl_controller.get_stats().total_connections, 2);
+}
+
+#[tokio::test]
+async fn test_stats_split_connections_by_ip_version() {
+    let ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    let v4: [IpAddr; 2] = [
+        "198.51.100.1".parse().unwrap(),
+        "198.51.100.2".parse().unwrap(),
+    ];
+    let v6: [IpAddr; 3] = [
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8::2".parse().unwrap(),
+        "2001:db8::3".parse().unwrap(),
+    ];
+    for ip in v4.iter().chain(&v6) {
+        ttl_controller.register_connection(*ip);
+    }
+    assert!(ttl_controller.force_cleanup_connection(v6[0]));
+
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.active_connections, 4);
+    assert_eq!(stats.active_connections_v4, 2);
+    assert_eq!(stats.active_connections_v6, 2);
+    assert_eq!(stats.total_connections, 5);
+    assert_eq!(stats.total_connections_v4, 2);
+    assert_eq!(stats.total_connections_v6, 3);
+}
+
+#[tokio::test]
+async fn test_history_budget_evicts_oldest_connection_histories() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_history_limit(10);
+    // Room for five full histories across the whole map
+    let budget = 50 * HISTORY_ENTRY_BYTES;
+    ttl_controller.set_history_budget_bytes(budget);
+
+    let ips: Vec<IpAddr> = (1..=20)
+        .map(|i| IpAddr::V4(Ipv4Addr::new(203, 0, 113, i)))
+        .collect();
+    for ip in &ips {
+        ttl_controller.register_connection(*ip);
+        for _ in 0..9 {
+            ttl_controller.update_connection_activity(*ip);
+        }
+        assert!(ttl_controller.history_bytes() <= budget);
+    }
+
+    // The newest connections keep their history; the oldest lost theirs
+    let history_len = |ip| {
+        ttl_controller
+            .get_connection_info(ip)
+            .unwrap()
+            .history
+            .len()
+    };
+    assert_eq!(history_len(ips[19]), 10);
+    assert_eq!(history_len(ips[0]), 0);
+    let held: usize = ips.iter().map(|ip| history_len(*ip)).sum();
+    assert_eq!(held * HISTORY_ENTRY_BYTES, ttl_controller.history_bytes());
+
+    // Connections survive eviction and start a fresh history
+    ttl_controller.update_connection_activity(ips[0]);
+    assert_eq!(history_len(ips[0]), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_concurrent_registration_from_many_ips_needs_no_outer_lock() {
+    const TASKS: u32 = 64;
+    const IPS_PER_TASK: u32 = 500;
+    let ttl_controller = Arc::new(TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        (TASKS * IPS_PER_TASK) as usize,
+    ));
+
+    let started = std::time::Instant::now();
+    let workers: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let ttl_controller = ttl_controller.clone();
+            tokio::spawn(async move {
+                for i in 0..IPS_PER_TASK {
+                    let ip = IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + task * IPS_PER_TASK + i));
+                    ttl_controller.register_connection(ip);
+                    assert!(ttl_controller.update_connection_activity(ip));
+                }
+            })
+        })
+        .collect();
+
+    // Cleanup runs alongside the registrations instead of excluding them
+    let cleanup = {
+        let ttl_controller = ttl_controller.clone();
+        tokio::spawn(async move {
+            for _ in 0..10 {
+                ttl_controller.cleanup_expired_connections().await;
+                tokio::task::yield_now().await;
+            }
+        })
+    };
+
+    for worker in workers {
+        worker.await.unwrap();
+    }
+    cleanup.await.unwrap();
+    let elapsed = started.elapsed();
+
+    let stats = ttl_controller.get_stats();
+    let expected = (TASKS * IPS_PER_TASK) as usize;
+    assert_eq!(stats.active_connections, expected);
+    assert_eq!(stats.total_connections, expected as u64);
+    assert_eq!(stats.expired_connections, 0);
+    assert!(
+        elapsed < Duration::from_secs(10),
+        "registering {expected} IPs took {elapsed:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_full_controller_evicts_least_recently_active_connection() {
+    const MAX: usize = 4;
+    let ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        MAX,
+    );
+    let ips: Vec<IpAddr> = (1..=MAX as u8 + 1)
+        .map(|i| IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)))
+        .collect();
+
+    for ip in &ips[..MAX] {
+        ttl_controller.register_connection(*ip);
+        tokio::time::sleep(Duration::from_millis(2)).await;
+    }
+    // Re-registering a tracked IP never evicts
+    ttl_controller.register_connection(ips[0]);
+    assert_eq!(ttl_controller.get_stats().active_connections, MAX);
+
+    // ips[1] is now the one idle longest
+    ttl_controller.register_connection(ips[MAX]);
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.active_connections, MAX);
+    assert_eq!(stats.expired_connections, 1);
+    assert!(ttl_controller.get_connection_info(ips[1]).is_none());
+    assert!(ttl_controller.get_connection_info(ips[0]).is_some());
+    assert!(ttl_controller.get_connection_info(ips[MAX]).is_some());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_concurrent_registration_never_exceeds_max_connections() {
+    const MAX: usize = 16;
+    const TASKS: u32 = 32;
+    const IPS_PER_TASK: u32 = 200;
+    let ttl_controller = Arc::new(TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        MAX,
+    ));
+
+    let workers: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let ttl_controller = ttl_controller.clone();
+            tokio::spawn(async move {
+                for i in 0..IPS_PER_TASK {
+                    let ip = IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + task * IPS_PER_TASK + i));
+                    ttl_controller.register_connection(ip);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.await.unwrap();
+    }
+
+    // Every registration past the cap evicted exactly one connection; an
+    // overshoot would linger, since later ones evict only to the cap
+    let stats = ttl_controller.get_stats();
+    let registered = u64::from(TASKS * IPS_PER_TASK);
+    assert_eq!(stats.active_connections, MAX);
+    assert_eq!(stats.total_connections, registered);
+    assert_eq!(stats.expired_connections, registered - MAX as u64);
+}
+
+#[test]
+fn test_lowered_cap_evicts_as_new_ips_register() {
+    let ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        4,
+    );
+    for i in 1..=4 {
+        ttl_controller.register_connection(IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)));
+    }
+
+    ttl_controller.set_max_connections(2);
+    assert_eq!(ttl_controller.get_stats().active_connections, 4);
+    ttl_controller.register_connection(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)));
+    let stats = ttl_controller.get_stats();
+    assert_eq!(stats.active_connections, 2);
+    assert_eq!(stats.expired_connections, 3);
+}
+
+fn busy_connection(request_count: u64) -> ConnectionInfo {
+    let mut connection = ConnectionInfo::new(
+        IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7)),
+        Duration::from_secs(300),
+    );
+    connection.request_count = request_count;
+    connection
+}
+
+fn adaptive_ttl(
+    strategy: &AdaptiveTtlStrategy,
+    existing: Option<&ConnectionInfo>,
+    max: u64,
+) -> Duration {
+    strategy.compute(&TtlContext {
+        ip: IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7)),
+        existing,
+        default_ttl: Duration::from_secs(100),
+        max_ttl: Duration::from_secs(max),
+    })
+}
+
+#[test]
+fn test_adaptive_ttl_extends_only_past_threshold() {
+    let strategy = AdaptiveTtlStrategy::new(AdaptiveTtlConfig {
+        enabled: true,
+        request_threshold: 5,
+        extension_multiplier: 2.0,
+    });
+
+    assert_eq!(
+        adaptive_ttl(&strategy, None, 3600),
+        Duration::from_secs(100)
+    );
+    let at_threshold = busy_connection(5);
+    assert_eq!(
+        adaptive_ttl(&strategy, Some(&at_threshold), 3600),
+        Duration::from_secs(100)
+    );
+    let past_threshold = busy_connection(6);
+    assert_eq!(
+        adaptive_ttl(&strategy, Some(&past_threshold), 3600),
+        Duration::from_secs(200)
+    );
+
+    let disabled = AdaptiveTtlStrategy::new(AdaptiveTtlConfig {
+        enabled: false,
+        ..AdaptiveTtlConfig::default()
+    });
+    assert_eq!(
+        adaptive_ttl(&disabled, Some(&busy_connection(1000)), 3600),
+        Duration::from_secs(100)
+    );
+}
+
+#[test]
+fn test_adaptive_ttl_never_exceeds_max_ttl() {
+    let strategy = AdaptiveTtlStrategy::new(AdaptiveTtlConfig {
+        enabled: true,
+        request_threshold: 0,
+        extension_multiplier: 50.0,
+    });
+    let busy = busy_connection(1000);
+    assert_eq!(
+        adaptive_ttl(&strategy, Some(&busy), 150),
+        Duration::from_secs(150)
+    );
+}
+
+#[test]
+fn test_busy_connection_gets_adaptive_ttl_on_reregistration() {
+    let mut ttl_controller = TtlController::new(
+        Duration::from_secs(100),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_ttl_strategy(Arc::new(AdaptiveTtlStrategy::new(AdaptiveTtlConfig {
+        enabled: true,
+        request_threshold: 3,
+        extension_multiplier: 2.0,
+    })));
+    let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7));
+    let ttl_of = |ip| ttl_controller.get_connection_info(ip).unwrap().ttl;
+
+    ttl_controller.register_connection(ip);
+    assert_eq!(ttl_of(ip), Duration::from_secs(100));
+    while ttl_controller
+        .get_connection_info(ip)
+        .unwrap()
+        .request_count
+        <= 3
+    {
+        ttl_controller.register_connection(ip);
+    }
+    ttl_controller.register_connection(ip);
+    assert_eq!(ttl_of(ip), Duration::from_secs(200));
+
+    // A longer boosted TTL is not cut back to the adaptive one
+    ttl_controller.boost_ttl(ip, 10.0);
+    ttl_controller.register_connection(ip);
+    assert_eq!(ttl_of(ip), Duration::from_secs(1000));
+}
+
+#[tokio::test]
+async fn test_event_consumer_sees_one_removal_per_cleaned_up_ip() {
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(64);
+    let mut ttl_controller = TtlController::new(
+        Duration::from_millis(20),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    ttl_controller.set_event_sender(event_tx);
+
+    let expiring: Vec<IpAddr> = (1..=3)
+        .map(|i| IpAddr::V4(Ipv4Addr::new(192, 0, 2, i)))
+        .collect();
+    for ip in &expiring {
+        ttl_controller.register_connection(*ip);
+    }
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    let forced = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 100));
+    ttl_controller.register_connection(forced);
+    ttl_controller.register_connection(forced);
+    ttl_controller.cleanup_expired_connections().await;
+    assert!(ttl_controller.force_cleanup_connection(forced));
+
+    // Dropping the controller closes the channel, ending the consumer loop
+    drop(ttl_controller);
+    let mut removed = Vec::new();
+    while let Some(event) = event_rx.recv().await {
+        match event.kind {
+            ConnectionEventKind::Registered => {}
+            ConnectionEventKind::Expired | ConnectionEventKind::ForceRemoved => {
+                if event.kind == ConnectionEventKind::Expired {
+                    assert!(event.lifetime >= Duration::from_millis(20));
+                }
+                removed.push((event.kind, event.ip, event.request_count));
+            }
+        }
+    }
+
+    removed.sort_by_key(|(_, ip, _)| *ip);
+    let mut expected: Vec<_> = expiring
+        .iter()
+        .map(|ip| (ConnectionEventKind::Expired, *ip, 1))
+        .collect();
+    expected.push((ConnectionEventKind::ForceRemoved, forced, 2));
+    assert_eq!(removed, expected);
+}
+
+#[test]
+fn test_oversized_boost_factor_caps_at_max_ttl() {
+    let ttl_controller = TtlController::new(
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+        Duration::from_secs(60),
+        1000,
+    );
+    let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 9));
+    ttl_controller.register_connection(ip);
+    assert_eq!(
+        ttl_controller.boost_ttl(ip, f64::INFINITY),
+        Some(Duration::from_secs(3600))
+    );
+
+    let mut config = rusty_ssl::AppConfig::default();
+    for factor in [f64::INFINITY, f64::NAN, 0.5] {
+        config.auth.ttl_boost_factor = factor;
+        let errors = config.validate().unwrap_err();
+        assert!(
+            errors.iter().any(|e| e.field == "auth.ttl_boost_factor"),
+            "{factor}"
+        );
+    }
+}